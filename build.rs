@@ -0,0 +1,34 @@
+//! 构建时嵌入 `tokenizers/` 目录下的 tiktoken 词表
+//!
+//! 词表文件存在时以 `include_bytes!` 嵌入二进制文件，运行时无需携带词表目录；
+//! 文件不存在时照常构建，运行时再从 `tokenizer.vocab_dir` 读取或按字符估算
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ENCODINGS: [(&str, &str); 2] = [
+    ("BUNDLED_CL100K_BASE", "cl100k_base"),
+    ("BUNDLED_O200K_BASE", "o200k_base"),
+];
+
+fn main() {
+    let vocab_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("tokenizers");
+    // 监听整个目录，词表文件不存在时也不会每次构建都重新运行
+    println!("cargo:rerun-if-changed={}", vocab_dir.display());
+
+    let mut code = String::new();
+    for (constant, encoding) in ENCODINGS {
+        let path = vocab_dir.join(format!("{}.tiktoken", encoding));
+        let value = if path.is_file() {
+            format!("Some(include_bytes!({:?}))", path.display().to_string())
+        } else {
+            "None".to_string()
+        };
+        writeln!(code, "const {}: Option<&[u8]> = {};", constant, value).unwrap();
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bundled_vocab.rs");
+    fs::write(out, code).unwrap();
+}
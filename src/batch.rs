@@ -0,0 +1,589 @@
+use crate::document::list_documents;
+use crate::error::{Result, SmartFetchError};
+use crate::jobs::{
+    content_hash, FileStatus, JobFile, JobManifest, JobOptions, JobStatus, JobStore,
+};
+use crate::llm_client::Usage;
+use crate::SmartFetchService;
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use tracing_indicatif::indicatif_println;
+
+/// 批量提取结束后写入输出目录的报告文件名
+pub const BATCH_REPORT_FILE: &str = "batch-report.json";
+
+/// 批量提取的选项
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// 只处理匹配任一通配符的文件，为空时处理全部文件
+    pub include: Vec<String>,
+    /// 跳过匹配任一通配符的文件
+    pub exclude: Vec<String>,
+    pub custom_prompt: Option<String>,
+    pub template: Option<String>,
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    pub profile: Option<String>,
+    /// 同时处理的文件数
+    pub concurrency: usize,
+    /// 输出文件的扩展名
+    pub output_extension: String,
+    /// 续跑输入、输出目录相同的最近一次任务，跳过已成功且内容未变化的文件
+    pub resume: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            custom_prompt: None,
+            template: None,
+            variables: None,
+            profile: None,
+            concurrency: 4,
+            output_extension: "md".to_string(),
+            resume: false,
+        }
+    }
+}
+
+/// 单个文件的提取结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem {
+    /// 相对于输入目录的路径
+    pub input: PathBuf,
+    /// 相对于输出目录的路径，失败时为 `None`
+    pub output: Option<PathBuf>,
+    pub model: Option<String>,
+    pub usage: Option<Usage>,
+    pub cached: bool,
+    /// 上次运行已提取成功，本次跳过
+    pub skipped: bool,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl BatchItem {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// 批量提取的汇总报告
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    /// 任务 ID，可用于续跑和查看任务清单
+    pub job_id: String,
+    pub input_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 续跑时跳过的已完成文件数
+    pub skipped: usize,
+    /// 命中响应缓存的文件数
+    pub cached: usize,
+    /// 本次运行的 token 用量合计，命中缓存和跳过的文件不计入
+    pub usage: Option<Usage>,
+    pub duration_ms: u64,
+    /// 按输入路径排序的各文件结果
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn failures(&self) -> impl Iterator<Item = &BatchItem> {
+        self.items.iter().filter(|item| !item.succeeded())
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "共{}个文件，成功{}个，失败{}个",
+            self.total, self.succeeded, self.failed
+        )?;
+        if self.cached > 0 {
+            write!(f, "（{}个来自缓存）", self.cached)?;
+        }
+        if self.skipped > 0 {
+            write!(f, "，跳过{}个已完成的文件", self.skipped)?;
+        }
+        if let Some(usage) = &self.usage {
+            write!(
+                f,
+                "，token 用量 {}（输入 {} / 输出 {}）",
+                usage.total_tokens, usage.prompt_tokens, usage.completion_tokens
+            )?;
+        }
+        write!(f, "，耗时 {:.1} 秒", self.duration_ms as f64 / 1000.0)
+    }
+}
+
+/// 一组通配符，`*` 匹配除 `/` 外的任意字符，`**` 可跨目录，`?` 匹配单个字符，`[abc]` 匹配字符集合，
+/// `{a,b}` 匹配任一备选项，`\` 转义下一个字符；不含 `/` 的通配符只与文件名比较，含 `/` 的从根目录开始匹配
+#[derive(Debug, Clone, Default)]
+pub struct GlobSet {
+    path_patterns: Vec<Regex>,
+    name_patterns: Vec<Regex>,
+}
+
+impl GlobSet {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut set = Self::default();
+        for pattern in patterns {
+            let pattern = pattern.trim().trim_start_matches("./");
+            let anchored = pattern.contains('/');
+            let regex =
+                Regex::new(&glob_to_regex(pattern.trim_start_matches('/'))).map_err(|e| {
+                    SmartFetchError::ConfigError(format!("无效的通配符: {} - {}", pattern, e))
+                })?;
+            if anchored {
+                set.path_patterns.push(regex);
+            } else {
+                set.name_patterns.push(regex);
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path_patterns.is_empty() && self.name_patterns.is_empty()
+    }
+
+    /// `path` 为以 `/` 分隔的相对路径
+    pub fn is_match(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.path_patterns.iter().any(|regex| regex.is_match(path))
+            || self.name_patterns.iter().any(|regex| regex.is_match(name))
+    }
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    // 未闭合的 `{` 数量，其中的 `,` 分隔备选项
+    let mut alternations = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match char_class(&mut chars) {
+                Some(class) => regex.push_str(&class),
+                // 没有闭合的 `]` 时按普通字符处理
+                None => regex.push_str("\\["),
+            },
+            '{' => {
+                alternations += 1;
+                regex.push_str("(?:");
+            }
+            ',' if alternations > 0 => regex.push('|'),
+            '}' if alternations > 0 => {
+                alternations -= 1;
+                regex.push(')');
+            }
+            // `\` 转义下一个字符，位于末尾时表示它本身
+            '\\' => {
+                let escaped = chars.next().unwrap_or('\\');
+                regex.push_str(&regex::escape(&escaped.to_string()));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// 将 `[` 之后的字符集合转换为正则表达式，没有闭合的 `]` 时返回 `None` 且不消耗字符
+///
+/// `!` 或 `^` 开头表示取反，紧跟在开头的 `]` 是普通字符；集合不匹配路径分隔符
+fn char_class(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<String> {
+    let mut lookahead = chars.clone();
+    let negated = matches!(lookahead.peek(), Some('!' | '^'));
+    if negated {
+        lookahead.next();
+    }
+
+    let mut members = Vec::new();
+    loop {
+        match lookahead.next()? {
+            ']' if !members.is_empty() => break,
+            c => members.push(c),
+        }
+    }
+    *chars = lookahead;
+
+    let mut class = String::from(if negated { "[^/" } else { "[" });
+    for c in members.into_iter().filter(|c| *c != '/') {
+        // `-` 保留为范围，其余字符一律转义，避免被当作正则的集合运算
+        if c == '-' {
+            class.push('-');
+        } else {
+            class.push_str(&regex::escape(&c.to_string()));
+        }
+    }
+    class.push(']');
+    Some(class)
+}
+
+/// 列出目录中待提取的文件（相对路径，按路径排序）
+///
+/// 只包含扩展名在 `supported_formats` 中、匹配 `include` 且不匹配 `exclude` 的文件；
+/// 位于 `skip_dir`（通常是输出目录）中的文件会被跳过
+pub fn collect_batch_inputs(
+    input_dir: &Path,
+    options: &BatchOptions,
+    supported_formats: &[String],
+    skip_dir: Option<&Path>,
+) -> Result<Vec<PathBuf>> {
+    let include = GlobSet::new(&options.include)?;
+    let exclude = GlobSet::new(&options.exclude)?;
+    let mut inputs = list_documents(input_dir, supported_formats, skip_dir)?;
+    inputs.retain(|input| {
+        let path = slash_path(input);
+        (include.is_empty() || include.is_match(&path)) && !exclude.is_match(&path)
+    });
+    Ok(inputs)
+}
+
+/// 以 `/` 分隔的相对路径，用于通配符匹配
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 为每个输入文件分配输出路径：替换扩展名，与其他文件冲突时保留原扩展名（`a.txt.md`）
+fn output_paths(inputs: &[PathBuf], extension: &str) -> Vec<PathBuf> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for input in inputs {
+        *counts.entry(input.with_extension(extension)).or_default() += 1;
+    }
+
+    let mut used = HashSet::new();
+    inputs
+        .iter()
+        .map(|input| {
+            let output = input.with_extension(extension);
+            if counts[&output] > 1 || !used.insert(output.clone()) {
+                let mut name = input.as_os_str().to_os_string();
+                name.push(format!(".{}", extension));
+                PathBuf::from(name)
+            } else {
+                output
+            }
+        })
+        .collect()
+}
+
+impl SmartFetchService {
+    /// 批量提取目录中的文档，按输入目录的结构将每个文件的结果写入输出目录
+    ///
+    /// 单个文件失败不会中断批量提取，失败原因记录在报告中；报告同时写入输出目录的
+    /// [`BATCH_REPORT_FILE`]。每处理完一个文件都会更新任务清单，`options.resume` 为真时
+    /// 续跑输入、输出目录相同的最近一次任务。模板、模板变量或模型配置无效时直接返回错误
+    pub async fn extract_batch(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+        options: &BatchOptions,
+    ) -> Result<BatchReport> {
+        let started = Instant::now();
+        let template = self.template_name(options.template.as_deref())?;
+        self.template_manager
+            .resolve_variables(template, options.variables.clone().unwrap_or_default())?;
+        self.extraction_client(options.profile.as_deref(), template)?;
+
+        let inputs = collect_batch_inputs(
+            input_dir,
+            options,
+            &self.supported_formats(),
+            Some(output_dir),
+        )?;
+        let outputs = output_paths(&inputs, &options.output_extension);
+        let total = inputs.len();
+
+        tokio::fs::create_dir_all(output_dir).await?;
+        let mut manifest = self.batch_job(
+            input_dir.canonicalize()?,
+            output_dir.canonicalize()?,
+            JobOptions {
+                custom_prompt: options.custom_prompt.clone(),
+                template: template.to_string(),
+                variables: options.variables.clone(),
+                profile: options.profile.clone(),
+                output_extension: options.output_extension.clone(),
+            },
+            options.resume,
+        )?;
+        manifest.set_inputs(&inputs);
+        manifest.status = JobStatus::Running;
+        self.job_store.save(&manifest)?;
+        indicatif_println!("📂 任务 {}: 找到{}个待提取的文件", manifest.id, total);
+
+        let job_id = manifest.id.clone();
+        let manifest = Mutex::new(manifest);
+        let finished = AtomicUsize::new(0);
+        let mut items: Vec<BatchItem> = stream::iter(inputs.into_iter().zip(outputs))
+            .map(|(input, output)| {
+                let finished = &finished;
+                let manifest = &manifest;
+                async move {
+                    let previous = lock(manifest).file(&input).cloned();
+                    let (item, hash) = self
+                        .extract_batch_item(
+                            input_dir, output_dir, input, output, template, options, previous,
+                        )
+                        .await;
+                    self.record_batch_item(manifest, &item, hash);
+
+                    let done = finished.fetch_add(1, Ordering::SeqCst) + 1;
+                    match &item.error {
+                        None => indicatif_println!(
+                            "✅ [{}/{}] {:?}{}",
+                            done,
+                            total,
+                            item.input,
+                            if item.skipped {
+                                "（已完成，跳过）"
+                            } else if item.cached {
+                                "（缓存）"
+                            } else {
+                                ""
+                            }
+                        ),
+                        Some(error) => {
+                            indicatif_println!(
+                                "❌ [{}/{}] {:?}: {}",
+                                done,
+                                total,
+                                item.input,
+                                error
+                            )
+                        }
+                    }
+                    item
+                }
+            })
+            .buffer_unordered(options.concurrency.max(1))
+            .collect()
+            .await;
+        items.sort_by(|a, b| a.input.cmp(&b.input));
+
+        let mut manifest = manifest
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        manifest.finish();
+        self.job_store.save(&manifest)?;
+
+        let succeeded = items.iter().filter(|item| item.succeeded()).count();
+        let usage = items
+            .iter()
+            .filter(|item| !item.cached && !item.skipped)
+            .filter_map(|item| item.usage.as_ref())
+            .fold(None, |total: Option<Usage>, usage| {
+                let mut total = total.unwrap_or_default();
+                total.add(usage);
+                Some(total)
+            });
+        let report = BatchReport {
+            job_id,
+            input_dir: input_dir.to_path_buf(),
+            output_dir: output_dir.to_path_buf(),
+            total,
+            succeeded,
+            failed: total - succeeded,
+            skipped: items.iter().filter(|item| item.skipped).count(),
+            cached: items.iter().filter(|item| item.cached).count(),
+            usage,
+            duration_ms: started.elapsed().as_millis() as u64,
+            items,
+        };
+
+        tokio::fs::write(
+            output_dir.join(BATCH_REPORT_FILE),
+            serde_json::to_string_pretty(&report)?,
+        )
+        .await?;
+        Ok(report)
+    }
+
+    /// 任务清单存储
+    pub fn job_store(&self) -> &JobStore {
+        &self.job_store
+    }
+
+    /// 新建任务，或取出要续跑的任务
+    fn batch_job(
+        &self,
+        input_dir: PathBuf,
+        output_dir: PathBuf,
+        options: JobOptions,
+        resume: bool,
+    ) -> Result<JobManifest> {
+        if !resume {
+            return Ok(JobManifest::new(input_dir, output_dir, options));
+        }
+
+        let manifest = self
+            .job_store
+            .latest_for(&input_dir, &output_dir)?
+            .ok_or_else(|| {
+                SmartFetchError::ValidationError(format!(
+                    "没有可续跑的任务: {:?} → {:?}",
+                    input_dir, output_dir
+                ))
+            })?;
+        if manifest.options != options {
+            return Err(SmartFetchError::ValidationError(format!(
+                "提示词、模板、模板变量或模型配置与任务 {} 不一致，无法续跑",
+                manifest.id
+            )));
+        }
+        indicatif_println!(
+            "🔁 续跑任务 {}（已完成{}个文件）",
+            manifest.id,
+            manifest.count(FileStatus::Succeeded)
+        );
+        Ok(manifest)
+    }
+
+    /// 提取单个文件；上次运行已成功、内容未变化且输出文件仍存在时直接跳过
+    ///
+    /// 同时返回文件内容的哈希，读取文件失败时为 `None`
+    #[allow(clippy::too_many_arguments)]
+    async fn extract_batch_item(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+        input: PathBuf,
+        output: PathBuf,
+        template: &str,
+        options: &BatchOptions,
+        previous: Option<JobFile>,
+    ) -> (BatchItem, Option<String>) {
+        let started = Instant::now();
+        let input_path = input_dir.join(&input);
+        let output_path = output_dir.join(&output);
+        let hash = tokio::fs::read(&input_path)
+            .await
+            .ok()
+            .map(|bytes| content_hash(&bytes));
+
+        if let Some(previous) = previous.filter(|previous| {
+            previous.status == FileStatus::Succeeded
+                && previous.hash.is_some()
+                && previous.hash == hash
+                && previous.output.as_ref() == Some(&output)
+                && output_path.is_file()
+        }) {
+            let item = BatchItem {
+                input,
+                output: Some(output),
+                model: previous.model,
+                usage: previous.usage,
+                cached: false,
+                skipped: true,
+                error: None,
+                duration_ms: 0,
+            };
+            return (item, hash);
+        }
+
+        let result = async {
+            let extracted = self
+                .extract_content_with_progress(
+                    &input_path,
+                    options.custom_prompt.clone(),
+                    Some(template),
+                    options.variables.clone(),
+                    options.profile.as_deref(),
+                    None,
+                )
+                .await?;
+            if let Some(parent) = output_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&output_path, &extracted.content).await?;
+            Ok::<_, SmartFetchError>(extracted)
+        }
+        .await;
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let item = match result {
+            Ok(extracted) => BatchItem {
+                input,
+                output: Some(output),
+                model: Some(extracted.model),
+                usage: extracted.usage,
+                cached: extracted.cached,
+                skipped: false,
+                error: None,
+                duration_ms,
+            },
+            Err(e) => BatchItem {
+                input,
+                output: None,
+                model: None,
+                usage: None,
+                cached: false,
+                skipped: false,
+                error: Some(e.to_string()),
+                duration_ms,
+            },
+        };
+        (item, hash)
+    }
+
+    /// 将单个文件的结果写入任务清单，保存失败只记录警告，不中断批量提取
+    fn record_batch_item(
+        &self,
+        manifest: &Mutex<JobManifest>,
+        item: &BatchItem,
+        hash: Option<String>,
+    ) {
+        let mut manifest = lock(manifest);
+        if let Some(file) = manifest.file_mut(&item.input) {
+            file.hash = hash;
+            file.status = if item.succeeded() {
+                FileStatus::Succeeded
+            } else {
+                FileStatus::Failed
+            };
+            file.output = item.output.clone();
+            file.model = item.model.clone();
+            file.usage = item.usage.clone();
+            file.error = item.error.clone();
+            file.updated_at = Some(Utc::now());
+        }
+        manifest.updated_at = Utc::now();
+        if let Err(e) = self.job_store.save(&manifest) {
+            tracing::warn!("保存任务清单失败: {}", e);
+        }
+    }
+}
+
+fn lock(manifest: &Mutex<JobManifest>) -> MutexGuard<'_, JobManifest> {
+    manifest.lock().unwrap_or_else(PoisonError::into_inner)
+}
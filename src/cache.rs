@@ -0,0 +1,260 @@
+use crate::config::{CacheConfig, LLMConfig};
+use crate::error::Result;
+use crate::llm_client::{ChatMessage, LlmOutput, ResponseFormat, Usage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 参与计算缓存键的请求内容
+///
+/// 渲染后的提示词已包含预处理后的文档内容，因此内容、模板或模型参数任一变化都会得到不同的键
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    provider: &'a str,
+    model: &'a str,
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+    response_format: Option<&'a ResponseFormat>,
+    messages: &'a [ChatMessage],
+}
+
+/// 缓存文件中保存的一次模型调用结果
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    model: String,
+    content: String,
+    usage: Option<Usage>,
+    created_at: DateTime<Utc>,
+}
+
+/// 缓存目录的统计信息
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// 已超过有效期、等待清理的条目数
+    pub expired: usize,
+    pub size_bytes: u64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// 磁盘上的 LLM 响应缓存
+///
+/// 每个条目保存为 `<cache_dir>/<sha256>.json`；命中时刷新文件修改时间，
+/// 超出大小上限时按修改时间删除最久未使用的条目
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+    max_size_bytes: Option<u64>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            dir: config
+                .cache_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("cache")),
+            ttl: config
+                .ttl_seconds
+                .filter(|ttl| *ttl > 0)
+                .map(Duration::from_secs),
+            max_size_bytes: config
+                .max_size_mb
+                .filter(|size| *size > 0.0)
+                .map(|size| (size * 1024.0 * 1024.0) as u64),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 根据模型配置和请求消息计算缓存键（SHA-256 十六进制字符串）
+    pub fn key(
+        config: &LLMConfig,
+        messages: &[ChatMessage],
+        response_format: Option<&ResponseFormat>,
+    ) -> String {
+        let key = CacheKey {
+            provider: config.provider.as_deref().unwrap_or("openai"),
+            model: &config.model,
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            response_format,
+            messages,
+        };
+        let bytes = serde_json::to_vec(&key).unwrap_or_default();
+        Sha256::digest(&bytes)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// 查询缓存，过期或无法解析的条目会被删除
+    pub fn get(&self, key: &str) -> Option<LlmOutput> {
+        let path = self.entry_path(key);
+        let bytes = fs::read(&path).ok()?;
+        let entry: CacheEntry = match serde_json::from_slice(&bytes) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("删除无法解析的缓存条目 {:?}: {}", path, e);
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+        };
+        if self.is_expired(&entry) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        // 刷新修改时间，供按最久未使用淘汰
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(LlmOutput {
+            content: entry.content,
+            model: entry.model,
+            usage: entry.usage,
+            cached: true,
+        })
+    }
+
+    /// 写入缓存，写入后超出大小上限时淘汰旧条目
+    pub fn put(&self, key: &str, output: &LlmOutput) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = CacheEntry {
+            model: output.model.clone(),
+            content: output.content.clone(),
+            usage: output.usage.clone(),
+            created_at: Utc::now(),
+        };
+
+        // 先写临时文件再重命名，避免并发读取到写了一半的条目
+        let path = self.entry_path(key);
+        let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&temp_path, serde_json::to_vec(&entry)?)?;
+        fs::rename(&temp_path, &path)?;
+
+        self.evict()
+    }
+
+    /// 统计缓存条目数量和占用空间
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for (path, metadata) in self.entries()? {
+            stats.entries += 1;
+            stats.size_bytes += metadata.len();
+            let Some(entry) = fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<CacheEntry>(&bytes).ok())
+            else {
+                stats.expired += 1;
+                continue;
+            };
+            if self.is_expired(&entry) {
+                stats.expired += 1;
+            }
+            stats.oldest = Some(
+                stats
+                    .oldest
+                    .map_or(entry.created_at, |t| t.min(entry.created_at)),
+            );
+            stats.newest = Some(
+                stats
+                    .newest
+                    .map_or(entry.created_at, |t| t.max(entry.created_at)),
+            );
+        }
+        Ok(stats)
+    }
+
+    /// 删除全部缓存条目，返回删除的数量
+    pub fn clear(&self) -> Result<usize> {
+        let entries = self.entries()?;
+        for (path, _) in &entries {
+            fs::remove_file(path)?;
+        }
+        Ok(entries.len())
+    }
+
+    /// 删除过期和无法解析的条目，返回删除的数量
+    pub fn prune(&self) -> Result<usize> {
+        let mut removed = 0;
+        for (path, _) in self.entries()? {
+            let expired = fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<CacheEntry>(&bytes).ok())
+                .is_none_or(|entry| self.is_expired(&entry));
+            if expired {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 超出大小上限时从最久未使用的条目开始删除
+    fn evict(&self) -> Result<()> {
+        let Some(max_size_bytes) = self.max_size_bytes else {
+            return Ok(());
+        };
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, metadata)| metadata.len()).sum();
+        if total <= max_size_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(_, metadata)| metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+        for (path, metadata) in entries {
+            if total <= max_size_bytes {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total = total.saturating_sub(metadata.len());
+        }
+        Ok(())
+    }
+
+    /// 列出缓存目录中的条目文件，目录不存在时为空
+    fn entries(&self) -> Result<Vec<(PathBuf, fs::Metadata)>> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for dir_entry in read_dir {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let metadata = dir_entry.metadata()?;
+                if metadata.is_file() {
+                    entries.push((path, metadata));
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        self.ttl.is_some_and(|ttl| {
+            (Utc::now() - entry.created_at)
+                .to_std()
+                .is_ok_and(|age| age > ttl)
+        })
+    }
+}
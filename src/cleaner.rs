@@ -9,6 +9,7 @@ pub struct DocumentCleaner {
     base64_image_regex: Regex,
     binary_data_regex: Regex,
     html_tag_regex: Regex,
+    html_hidden_block_regex: Regex,
 }
 
 impl DocumentCleaner {
@@ -18,12 +19,15 @@ impl DocumentCleaner {
         let base64_image_regex = Regex::new(r"data:image/[^;]+;base64,[A-Za-z0-9+/=]+")?;
         let binary_data_regex = Regex::new(r"[^\x20-\x7E\r\n\t\u4E00-\u9FFF]")?;
         let html_tag_regex = Regex::new(r"<[^>]*>")?;
+        let html_hidden_block_regex =
+            Regex::new(r"(?is)<script[^>]*>.*?</script>|<style[^>]*>.*?</style>|<!--.*?-->")?;
 
         Ok(Self {
             config,
             base64_image_regex,
             binary_data_regex,
             html_tag_regex,
+            html_hidden_block_regex,
         })
     }
 
@@ -89,9 +93,12 @@ impl DocumentCleaner {
         cleaned.to_string()
     }
 
-    /// 移除HTML标签
+    /// 移除HTML标签，连同脚本、样式和注释的内容一并移除
+    ///
+    /// 完整的 HTML 文档会在加载时转换为 Markdown，这里只处理文本中零散的标签
     fn remove_html_tags(&self, content: &str) -> String {
-        let cleaned = self.html_tag_regex.replace_all(content, "");
+        let without_hidden = self.html_hidden_block_regex.replace_all(content, "");
+        let cleaned = self.html_tag_regex.replace_all(&without_hidden, "");
         cleaned.to_string()
     }
 
@@ -255,6 +262,19 @@ data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42m
         assert!(cleaned.contains("这是一个链接"));
     }
 
+    #[test]
+    fn test_remove_html_tags_drops_script_and_style() {
+        let config = CleaningConfig {
+            remove_html_tags: Some(true),
+            ..Default::default()
+        };
+        let cleaner = DocumentCleaner::new(config).unwrap();
+
+        let content = "<style>p { color: red; }</style><p>正文</p><script>alert(1)</script>";
+        let cleaned = cleaner.remove_html_tags(content);
+        assert_eq!(cleaned, "正文");
+    }
+
     #[test]
     fn test_truncate_long_strings() {
         let config = CleaningConfig::default();
@@ -1,5 +1,6 @@
 use crate::error::{Result, SmartFetchError};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,17 +10,75 @@ pub struct AppConfig {
     pub default_template: Option<String>,
     pub server: ServerConfig,
     pub processing: ProcessingConfig,
+    pub fetch: Option<FetchConfig>,
+    pub cache: Option<CacheConfig>,
+    pub tokenizer: Option<TokenizerConfig>,
+    pub jobs: Option<JobsConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
+    /// LLM 服务提供商：openai / anthropic / ollama / azure
+    pub provider: Option<String>,
     pub api_endpoint: String,
     pub api_key: Option<String>,
     pub model: String,
     pub max_tokens: Option<u32>,
+    /// 模型的上下文窗口（token 数），未设置时按模型名称推断，无法推断时不检查
+    pub context_window: Option<usize>,
     pub temperature: Option<f64>,
     pub timeout_seconds: Option<u64>,
     pub headers: Option<Vec<HeaderConfig>>,
+    /// 结构化提取时请求的输出模式：json_schema / json_object / none
+    pub response_format: Option<String>,
+    pub retry: Option<RetryConfig>,
+    /// 是否以 SSE 流式方式接收最终结果
+    pub stream: Option<bool>,
+    /// Azure OpenAI 专用配置（`provider = "azure"` 时生效）
+    pub azure: Option<AzureConfig>,
+    /// 未指定模型配置时使用的配置名称，为空时直接使用 `[llm]` 中的设置
+    pub default_profile: Option<String>,
+    /// 命名的模型配置（`[llm.profiles.<name>]`），未设置的字段继承 `[llm]`
+    pub profiles: Option<BTreeMap<String, LLMProfile>>,
+    /// 模型不可用、过载或超出上下文长度时依次尝试的模型配置名称
+    pub fallback: Option<Vec<String>>,
+}
+
+/// 命名的模型配置，可按请求选择（如 fast、accurate、cheap）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LLMProfile {
+    pub provider: Option<String>,
+    pub api_endpoint: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub context_window: Option<usize>,
+    pub temperature: Option<f64>,
+    pub timeout_seconds: Option<u64>,
+    pub response_format: Option<String>,
+    pub stream: Option<bool>,
+    /// 覆盖 `[llm]` 中的 fallback 列表
+    pub fallback: Option<Vec<String>>,
+}
+
+/// Azure OpenAI 部署配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AzureConfig {
+    /// 部署名称，未配置时使用模型名称
+    pub deployment: Option<String>,
+    /// API 版本，默认 2024-10-21
+    pub api_version: Option<String>,
+}
+
+/// LLM 请求失败重试配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// 可重试错误（429、5xx、网络错误）的最大重试次数，0 表示不重试
+    pub max_retries: Option<u32>,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub initial_backoff_ms: Option<u64>,
+    /// 单次等待时间上限（毫秒），同样限制响应中的 `Retry-After`
+    pub max_backoff_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,16 +92,105 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub max_connections: Option<u32>,
+    /// HTTP/SSE 模式下单次工具调用的超时时间（秒），stdio 模式不设超时
     pub request_timeout_seconds: Option<u64>,
+    /// 服务运行期间是否监视配置文件和模板目录，变更后自动重新加载
+    pub hot_reload: Option<bool>,
+    /// 检查文件变更的间隔（秒）
+    pub reload_interval_seconds: Option<u64>,
+    /// 以 `file://` MCP 资源公开的文档目录，只能读取这些目录下支持格式的文件；为空时不公开文件
+    pub resource_roots: Option<Vec<PathBuf>>,
+}
+
+/// LLM 响应缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// 是否缓存模型响应，相同内容、提示词和模型参数的请求直接返回缓存结果
+    pub enabled: Option<bool>,
+    pub cache_dir: Option<PathBuf>,
+    /// 缓存有效期（秒），0 表示永不过期
+    pub ttl_seconds: Option<u64>,
+    /// 缓存目录大小上限（MB），超出时删除最久未使用的条目，0 表示不限制
+    pub max_size_mb: Option<f64>,
+}
+
+/// token 计数和上下文窗口检查配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizerConfig {
+    /// 分词编码：auto（按模型名称选择）/ cl100k_base / o200k_base / estimate（按字符估算）
+    pub encoding: Option<String>,
+    /// 覆盖内置词表的 tiktoken 格式词表（`<编码名称>.tiktoken`）目录，未配置时使用构建时嵌入的词表
+    pub vocab_dir: Option<PathBuf>,
+    /// 提示词加 max_tokens 超出上下文窗口时的处理：chunk（自动分块）/ error（直接报错）
+    pub on_overflow: Option<String>,
+}
+
+/// 批量提取任务和后台提取任务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// 保存批量提取任务清单（`<任务 ID>.json`）的目录，用于断点续跑和查看历史任务
+    pub jobs_dir: Option<PathBuf>,
+    /// 同时运行的后台提取任务数，其余任务排队等待
+    pub max_concurrent: Option<usize>,
+    /// 最多保留的已结束后台任务数，超出时删除最早结束的任务
+    pub max_retained: Option<usize>,
+    /// 最多保留的同步提取结果数（MCP `result://` 资源），与后台任务分开计数
+    pub max_recorded_results: Option<usize>,
+    /// 已结束的后台任务及其结果、同步提取结果的保留时间（秒），0 表示不按时间删除
+    pub retention_seconds: Option<u64>,
+}
+
+/// 网页抓取配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchConfig {
+    pub timeout_seconds: Option<u64>,
+    pub max_size_mb: Option<f64>,
+    pub user_agent: Option<String>,
+    pub max_redirects: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingConfig {
     pub max_document_size_mb: Option<f64>,
     pub chunk_size: Option<usize>,
+    /// 允许处理的文件扩展名，为空时为文档加载器注册表支持的所有格式
+    #[serde(default)]
     pub supported_formats: Vec<String>,
     pub enable_preprocessing: Option<bool>,
     pub cleaning: Option<CleaningConfig>,
+    pub map_reduce: Option<MapReduceConfig>,
+    pub html: Option<HtmlConfig>,
+    pub structured: Option<StructuredConfig>,
+}
+
+/// 按 JSON Schema 进行结构化提取的配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredConfig {
+    /// 生成结构化提取提示词的模板
+    pub template: Option<String>,
+    /// 输出未通过校验时携带错误信息重新请求的最大次数
+    pub max_retries: Option<u32>,
+}
+
+/// HTML 转 Markdown 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlConfig {
+    /// 是否保留链接目标（`[文本](地址)`）
+    pub keep_links: Option<bool>,
+    /// 是否移除导航、页脚等页面模板内容
+    pub remove_boilerplate: Option<bool>,
+}
+
+/// 超长文档的分块提取（map-reduce）配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapReduceConfig {
+    pub enable_map_reduce: Option<bool>,
+    /// 对每个分块使用的模板，未设置时使用 default_template
+    pub map_template: Option<String>,
+    /// 合并分块结果使用的模板
+    pub reduce_template: Option<String>,
+    /// 同时处理的分块数
+    pub max_concurrent_chunks: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +212,10 @@ impl Default for AppConfig {
             default_template: Some("default".to_string()),
             server: ServerConfig::default(),
             processing: ProcessingConfig::default(),
+            fetch: Some(FetchConfig::default()),
+            cache: Some(CacheConfig::default()),
+            tokenizer: Some(TokenizerConfig::default()),
+            jobs: Some(JobsConfig::default()),
         }
     }
 }
@@ -71,13 +223,100 @@ impl Default for AppConfig {
 impl Default for LLMConfig {
     fn default() -> Self {
         Self {
+            provider: Some("openai".to_string()),
             api_endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
             api_key: None,
             model: "gpt-4".to_string(),
             max_tokens: Some(4000),
+            context_window: None,
             temperature: Some(0.7),
             timeout_seconds: Some(30),
             headers: None,
+            response_format: Some("json_schema".to_string()),
+            retry: Some(RetryConfig::default()),
+            stream: Some(true),
+            azure: None,
+            default_profile: None,
+            profiles: None,
+            fallback: None,
+        }
+    }
+}
+
+impl LLMConfig {
+    /// 已配置的模型配置名称（按名称排序）
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles
+            .as_ref()
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn unknown_profile(&self, name: &str) -> SmartFetchError {
+        let names = self.profile_names();
+        SmartFetchError::ConfigError(if names.is_empty() {
+            format!("未知的模型配置: {}（未配置任何模型配置）", name)
+        } else {
+            format!("未知的模型配置: {}（可选 {}）", name, names.join("、"))
+        })
+    }
+
+    /// 将命名的模型配置合并到基础配置上，得到该配置对应的完整 LLM 配置
+    ///
+    /// 结果保留 `profiles`，以便按名称解析该配置的 fallback 列表
+    pub fn profile(&self, name: &str) -> Result<LLMConfig> {
+        let profile = self
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .ok_or_else(|| self.unknown_profile(name))?
+            .clone();
+
+        let mut config = self.clone();
+        config.default_profile = None;
+        if profile.provider.is_some() {
+            config.provider = profile.provider;
+        }
+        if let Some(api_endpoint) = profile.api_endpoint {
+            config.api_endpoint = api_endpoint;
+        }
+        if profile.api_key.is_some() {
+            config.api_key = profile.api_key;
+        }
+        if let Some(model) = profile.model {
+            config.model = model;
+        }
+        if profile.max_tokens.is_some() {
+            config.max_tokens = profile.max_tokens;
+        }
+        if profile.context_window.is_some() {
+            config.context_window = profile.context_window;
+        }
+        if profile.temperature.is_some() {
+            config.temperature = profile.temperature;
+        }
+        if profile.timeout_seconds.is_some() {
+            config.timeout_seconds = profile.timeout_seconds;
+        }
+        if profile.response_format.is_some() {
+            config.response_format = profile.response_format;
+        }
+        if profile.stream.is_some() {
+            config.stream = profile.stream;
+        }
+        if profile.fallback.is_some() {
+            config.fallback = profile.fallback;
+        }
+        Ok(config)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: Some(3),
+            initial_backoff_ms: Some(1000),
+            max_backoff_ms: Some(30000),
         }
     }
 }
@@ -89,6 +328,53 @@ impl Default for ServerConfig {
             port: 8080,
             max_connections: Some(100),
             request_timeout_seconds: Some(60),
+            hot_reload: Some(true),
+            reload_interval_seconds: Some(2),
+            resource_roots: Some(Vec::new()),
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            cache_dir: Some(PathBuf::from("cache")),
+            ttl_seconds: Some(7 * 24 * 3600),
+            max_size_mb: Some(100.0),
+        }
+    }
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        Self {
+            encoding: Some("auto".to_string()),
+            vocab_dir: None,
+            on_overflow: Some("chunk".to_string()),
+        }
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            jobs_dir: Some(PathBuf::from("jobs")),
+            max_concurrent: Some(2),
+            max_retained: Some(100),
+            max_recorded_results: Some(100),
+            retention_seconds: Some(3600),
+        }
+    }
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: Some(30),
+            max_size_mb: Some(10.0),
+            user_agent: Some("mcp-smart-fetch/0.1.0".to_string()),
+            max_redirects: Some(10),
         }
     }
 }
@@ -98,9 +384,41 @@ impl Default for ProcessingConfig {
         Self {
             max_document_size_mb: Some(10.0),
             chunk_size: Some(4000),
-            supported_formats: vec!["txt".to_string(), "md".to_string()],
+            supported_formats: Vec::new(),
             enable_preprocessing: Some(true),
             cleaning: Some(CleaningConfig::default()),
+            map_reduce: Some(MapReduceConfig::default()),
+            html: Some(HtmlConfig::default()),
+            structured: Some(StructuredConfig::default()),
+        }
+    }
+}
+
+impl Default for StructuredConfig {
+    fn default() -> Self {
+        Self {
+            template: Some("structured".to_string()),
+            max_retries: Some(2),
+        }
+    }
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            keep_links: Some(false),
+            remove_boilerplate: Some(true),
+        }
+    }
+}
+
+impl Default for MapReduceConfig {
+    fn default() -> Self {
+        Self {
+            enable_map_reduce: Some(true),
+            map_template: None,
+            reduce_template: Some("reduce".to_string()),
+            max_concurrent_chunks: Some(1),
         }
     }
 }
@@ -143,6 +461,28 @@ impl AppConfig {
         Ok(config)
     }
 
+    /// 热重载时保留运行中的密钥和服务器设置
+    ///
+    /// API 密钥和自定义请求头不从重新读取的配置中更新，监听地址等服务器设置需重启后生效；
+    /// 重载时新增的模型配置使用配置文件中的密钥，不会继承基础配置的密钥
+    pub fn retain_runtime_settings(&mut self, current: &AppConfig) {
+        self.llm.api_key = current.llm.api_key.clone();
+        self.llm.headers = current.llm.headers.clone();
+        if let Some(profiles) = &mut self.llm.profiles {
+            for (name, profile) in profiles.iter_mut() {
+                let existing = current
+                    .llm
+                    .profiles
+                    .as_ref()
+                    .and_then(|profiles| profiles.get(name));
+                if let Some(existing) = existing {
+                    profile.api_key = existing.api_key.clone();
+                }
+            }
+        }
+        self.server = current.server.clone();
+    }
+
     /// 解析 u32 类型的环境变量
     fn parse_env_u32(env_var: &str, default: Option<u32>) -> Option<u32> {
         std::env::var(env_var)
@@ -199,6 +539,10 @@ impl AppConfig {
 
     fn apply_env_overrides(mut config: AppConfig) -> AppConfig {
         // LLM 配置的环境变量覆盖
+        if let Ok(provider) = std::env::var("LLM_PROVIDER") {
+            config.llm.provider = Some(provider);
+        }
+
         if let Ok(api_endpoint) = std::env::var("LLM_API_ENDPOINT") {
             config.llm.api_endpoint = api_endpoint;
         }
@@ -211,10 +555,41 @@ impl AppConfig {
             config.llm.model = model;
         }
 
+        if let Ok(profile) = std::env::var("LLM_PROFILE") {
+            config.llm.default_profile = Some(profile);
+        }
+
+        if let Ok(fallback) = std::env::var("LLM_FALLBACK") {
+            config.llm.fallback = Some(
+                fallback
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            );
+        }
+
         config.llm.max_tokens = Self::parse_env_u32("LLM_MAX_TOKENS", config.llm.max_tokens);
+        config.llm.context_window = Self::parse_env_usize("LLM_CONTEXT_WINDOW", config.llm.context_window);
         config.llm.temperature = Self::parse_env_f64("LLM_TEMPERATURE", config.llm.temperature);
         config.llm.timeout_seconds = Self::parse_env_u64("LLM_TIMEOUT_SECONDS", config.llm.timeout_seconds);
 
+        config.llm.stream = Self::parse_env_bool("LLM_STREAM", config.llm.stream);
+
+        if let Some(retry) = &mut config.llm.retry {
+            retry.max_retries = Self::parse_env_u32("LLM_MAX_RETRIES", retry.max_retries);
+            retry.initial_backoff_ms = Self::parse_env_u64("LLM_RETRY_INITIAL_BACKOFF_MS", retry.initial_backoff_ms);
+            retry.max_backoff_ms = Self::parse_env_u64("LLM_RETRY_MAX_BACKOFF_MS", retry.max_backoff_ms);
+        }
+
+        if let Ok(deployment) = std::env::var("AZURE_OPENAI_DEPLOYMENT") {
+            config.llm.azure.get_or_insert_with(AzureConfig::default).deployment = Some(deployment);
+        }
+        if let Ok(api_version) = std::env::var("AZURE_OPENAI_API_VERSION") {
+            config.llm.azure.get_or_insert_with(AzureConfig::default).api_version = Some(api_version);
+        }
+
         // 服务器配置的环境变量覆盖
         if let Ok(host) = std::env::var("SERVER_HOST") {
             config.server.host = host;
@@ -228,6 +603,19 @@ impl AppConfig {
 
         config.server.max_connections = Self::parse_env_u32("SERVER_MAX_CONNECTIONS", config.server.max_connections);
         config.server.request_timeout_seconds = Self::parse_env_u64("SERVER_REQUEST_TIMEOUT_SECONDS", config.server.request_timeout_seconds);
+        config.server.hot_reload = Self::parse_env_bool("SERVER_HOT_RELOAD", config.server.hot_reload);
+        config.server.reload_interval_seconds = Self::parse_env_u64("SERVER_RELOAD_INTERVAL_SECONDS", config.server.reload_interval_seconds);
+
+        if let Ok(roots) = std::env::var("SERVER_RESOURCE_ROOTS") {
+            config.server.resource_roots = Some(
+                roots
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|root| !root.is_empty())
+                    .map(PathBuf::from)
+                    .collect(),
+            );
+        }
 
         // 处理配置的环境变量覆盖
         config.templates_dir = Self::parse_env_path("TEMPLATES_DIR", &config.templates_dir);
@@ -250,14 +638,149 @@ impl AppConfig {
             cleaning.normalize_whitespace = Self::parse_env_bool("NORMALIZE_WHITESPACE", cleaning.normalize_whitespace);
         }
 
+        // HTML 转换配置的环境变量覆盖
+        if let Some(html) = &mut config.processing.html {
+            html.keep_links = Self::parse_env_bool("HTML_KEEP_LINKS", html.keep_links);
+            html.remove_boilerplate = Self::parse_env_bool("HTML_REMOVE_BOILERPLATE", html.remove_boilerplate);
+        }
+
+        // 网页抓取配置的环境变量覆盖
+        if let Some(fetch) = &mut config.fetch {
+            fetch.timeout_seconds = Self::parse_env_u64("FETCH_TIMEOUT_SECONDS", fetch.timeout_seconds);
+            fetch.max_size_mb = Self::parse_env_f64("FETCH_MAX_SIZE_MB", fetch.max_size_mb);
+            if let Ok(user_agent) = std::env::var("FETCH_USER_AGENT") {
+                fetch.user_agent = Some(user_agent);
+            }
+        }
+
+        // 分块提取配置的环境变量覆盖
+        if let Some(map_reduce) = &mut config.processing.map_reduce {
+            map_reduce.enable_map_reduce = Self::parse_env_bool("ENABLE_MAP_REDUCE", map_reduce.enable_map_reduce);
+            if let Ok(map_template) = std::env::var("MAP_TEMPLATE") {
+                map_reduce.map_template = Some(map_template);
+            }
+            if let Ok(reduce_template) = std::env::var("REDUCE_TEMPLATE") {
+                map_reduce.reduce_template = Some(reduce_template);
+            }
+            map_reduce.max_concurrent_chunks = Self::parse_env_usize("MAX_CONCURRENT_CHUNKS", map_reduce.max_concurrent_chunks);
+        }
+
+        // 结构化提取配置的环境变量覆盖
+        if let Ok(response_format) = std::env::var("LLM_RESPONSE_FORMAT") {
+            config.llm.response_format = Some(response_format);
+        }
+        if let Some(structured) = &mut config.processing.structured {
+            if let Ok(template) = std::env::var("STRUCTURED_TEMPLATE") {
+                structured.template = Some(template);
+            }
+            structured.max_retries = Self::parse_env_u32("STRUCTURED_MAX_RETRIES", structured.max_retries);
+        }
+
+        // 响应缓存配置的环境变量覆盖
+        let cache = config.cache.get_or_insert_with(CacheConfig::default);
+        cache.enabled = Self::parse_env_bool("CACHE_ENABLED", cache.enabled);
+        if let Ok(cache_dir) = std::env::var("CACHE_DIR") {
+            cache.cache_dir = Some(PathBuf::from(cache_dir));
+        }
+        cache.ttl_seconds = Self::parse_env_u64("CACHE_TTL_SECONDS", cache.ttl_seconds);
+        cache.max_size_mb = Self::parse_env_f64("CACHE_MAX_SIZE_MB", cache.max_size_mb);
+
+        // 分词配置的环境变量覆盖
+        let tokenizer = config.tokenizer.get_or_insert_with(TokenizerConfig::default);
+        if let Ok(encoding) = std::env::var("TOKENIZER_ENCODING") {
+            tokenizer.encoding = Some(encoding);
+        }
+        if let Ok(vocab_dir) = std::env::var("TOKENIZER_VOCAB_DIR") {
+            tokenizer.vocab_dir = Some(PathBuf::from(vocab_dir));
+        }
+        if let Ok(on_overflow) = std::env::var("TOKENIZER_ON_OVERFLOW") {
+            tokenizer.on_overflow = Some(on_overflow);
+        }
+
+        // 任务配置的环境变量覆盖
+        let jobs = config.jobs.get_or_insert_with(JobsConfig::default);
+        if let Ok(jobs_dir) = std::env::var("JOBS_DIR") {
+            jobs.jobs_dir = Some(PathBuf::from(jobs_dir));
+        }
+        jobs.max_concurrent = Self::parse_env_usize("JOBS_MAX_CONCURRENT", jobs.max_concurrent);
+        jobs.max_retained = Self::parse_env_usize("JOBS_MAX_RETAINED", jobs.max_retained);
+        jobs.max_recorded_results =
+            Self::parse_env_usize("JOBS_MAX_RECORDED_RESULTS", jobs.max_recorded_results);
+        jobs.retention_seconds =
+            Self::parse_env_u64("JOBS_RETENTION_SECONDS", jobs.retention_seconds);
+
         config
     }
 
     pub fn validate(&self) -> Result<()> {
-        Self::validate_llm_config(&self.llm)
+        Self::validate_llm_config(&self.llm)?;
+        Self::validate_tokenizer_config(&self.get_tokenizer_config())
+    }
+
+    pub fn validate_tokenizer_config(config: &TokenizerConfig) -> Result<()> {
+        if let Some(encoding) = &config.encoding {
+            let valid = matches!(encoding.as_str(), "auto" | "estimate")
+                || crate::tokenizer::Encoding::from_name(encoding).is_some();
+            if !valid {
+                return Err(SmartFetchError::ConfigError(format!(
+                    "不支持的分词编码: {}（可选 auto、estimate、{}）",
+                    encoding,
+                    crate::tokenizer::Encoding::NAMES.join("、")
+                )));
+            }
+        }
+
+        if let Some(on_overflow) = &config.on_overflow {
+            if !matches!(on_overflow.as_str(), "chunk" | "error") {
+                return Err(SmartFetchError::ConfigError(format!(
+                    "不支持的超出上下文处理方式: {}（可选 chunk、error）",
+                    on_overflow
+                )));
+            }
+        }
+
+        Ok(())
     }
 
     pub fn validate_llm_config(config: &LLMConfig) -> Result<()> {
+        Self::validate_model_settings(config)?;
+
+        if let Some(default_profile) = &config.default_profile {
+            config.profile(default_profile)?;
+        }
+        for name in config.fallback.iter().flatten() {
+            config.profile(name)?;
+        }
+        for name in config.profile_names() {
+            let profile = config.profile(&name)?;
+            Self::validate_model_settings(&profile)
+                .and_then(|_| {
+                    profile
+                        .fallback
+                        .iter()
+                        .flatten()
+                        .try_for_each(|fallback| config.profile(fallback).map(|_| ()))
+                })
+                .map_err(|e| {
+                    SmartFetchError::ConfigError(format!("模型配置 {} 无效: {}", name, e))
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// 校验单个模型的参数（不含模型配置之间的引用）
+    fn validate_model_settings(config: &LLMConfig) -> Result<()> {
+        if let Some(provider) = &config.provider {
+            if !crate::providers::PROVIDER_NAMES.contains(&provider.as_str()) {
+                return Err(SmartFetchError::ConfigError(format!(
+                    "不支持的 LLM 提供商: {}（可选 {}）",
+                    provider,
+                    crate::providers::PROVIDER_NAMES.join("、")
+                )));
+            }
+        }
+
         if config.api_endpoint.is_empty() {
             return Err(SmartFetchError::ConfigError(
                 "LLM API端点不能为空".to_string(),
@@ -278,6 +801,14 @@ impl AppConfig {
             }
         }
 
+        if let Some(context_window) = config.context_window {
+            if context_window as u64 <= u64::from(config.max_tokens.unwrap_or(0)) {
+                return Err(SmartFetchError::ConfigError(
+                    "上下文窗口必须大于最大token数".to_string(),
+                ));
+            }
+        }
+
         if let Some(temperature) = config.temperature {
             if !(0.0..=2.0).contains(&temperature) {
                 return Err(SmartFetchError::ConfigError(
@@ -286,6 +817,25 @@ impl AppConfig {
             }
         }
 
+        if let Some(response_format) = &config.response_format {
+            if !matches!(response_format.as_str(), "json_schema" | "json_object" | "none") {
+                return Err(SmartFetchError::ConfigError(format!(
+                    "不支持的输出模式: {}（可选 json_schema、json_object、none）",
+                    response_format
+                )));
+            }
+        }
+
+        if let Some(retry) = &config.retry {
+            if let (Some(initial), Some(max)) = (retry.initial_backoff_ms, retry.max_backoff_ms) {
+                if initial > max {
+                    return Err(SmartFetchError::ConfigError(
+                        "重试初始等待时间不能大于等待时间上限".to_string(),
+                    ));
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -305,6 +855,22 @@ impl AppConfig {
         &self.processing
     }
 
+    pub fn get_fetch_config(&self) -> FetchConfig {
+        self.fetch.clone().unwrap_or_default()
+    }
+
+    pub fn get_cache_config(&self) -> CacheConfig {
+        self.cache.clone().unwrap_or_default()
+    }
+
+    pub fn get_tokenizer_config(&self) -> TokenizerConfig {
+        self.tokenizer.clone().unwrap_or_default()
+    }
+
+    pub fn get_jobs_config(&self) -> JobsConfig {
+        self.jobs.clone().unwrap_or_default()
+    }
+
     /// 显示配置信息（用于调试）
     pub fn display_info(&self) -> String {
         format!(
@@ -323,16 +889,29 @@ impl AppConfig {
     /// 获取所有支持的环境变量及其说明
     pub fn get_env_variables_info() -> Vec<(&'static str, &'static str)> {
         vec![
+            ("LLM_PROVIDER", "LLM 服务提供商 (openai/anthropic/ollama/azure)"),
             ("LLM_API_ENDPOINT", "LLM API 端点 URL"),
             ("LLM_API_KEY", "LLM API 密钥"),
             ("LLM_MODEL", "LLM 模型名称"),
+            ("LLM_PROFILE", "默认使用的模型配置名称"),
+            ("LLM_FALLBACK", "失败时依次尝试的模型配置名称 (逗号分隔)"),
             ("LLM_MAX_TOKENS", "最大 token 数 (u32)"),
+            ("LLM_CONTEXT_WINDOW", "模型的上下文窗口 (usize, token 数)"),
             ("LLM_TEMPERATURE", "温度参数 (f64, 0.0-2.0)"),
             ("LLM_TIMEOUT_SECONDS", "请求超时时间 (u64, 秒)"),
+            ("LLM_STREAM", "是否以流式方式接收结果 (bool)"),
+            ("LLM_MAX_RETRIES", "可重试错误的最大重试次数 (u32)"),
+            ("LLM_RETRY_INITIAL_BACKOFF_MS", "首次重试前的等待时间 (u64, 毫秒)"),
+            ("LLM_RETRY_MAX_BACKOFF_MS", "单次重试等待时间上限 (u64, 毫秒)"),
+            ("AZURE_OPENAI_DEPLOYMENT", "Azure OpenAI 部署名称"),
+            ("AZURE_OPENAI_API_VERSION", "Azure OpenAI API 版本"),
             ("SERVER_HOST", "服务器监听地址"),
             ("SERVER_PORT", "服务器端口 (u16)"),
             ("SERVER_MAX_CONNECTIONS", "最大连接数 (u32)"),
-            ("SERVER_REQUEST_TIMEOUT_SECONDS", "请求超时时间 (u64, 秒)"),
+            ("SERVER_REQUEST_TIMEOUT_SECONDS", "HTTP/SSE 模式下的工具调用超时时间 (u64, 秒)"),
+            ("SERVER_HOT_RELOAD", "服务运行期间是否自动重新加载配置和模板 (bool)"),
+            ("SERVER_RELOAD_INTERVAL_SECONDS", "检查配置和模板变更的间隔 (u64, 秒)"),
+            ("SERVER_RESOURCE_ROOTS", "以 MCP 资源公开的文档目录 (逗号分隔)"),
             ("TEMPLATES_DIR", "模板目录路径"),
             ("DEFAULT_TEMPLATE", "默认模板名称"),
             ("MAX_DOCUMENT_SIZE_MB", "最大文档大小 (f64, MB)"),
@@ -344,6 +923,30 @@ impl AppConfig {
             ("REMOVE_HTML_TAGS", "是否移除 HTML 标签 (bool)"),
             ("NORMALIZE_WHITESPACE", "是否规范化空白字符 (bool)"),
             ("MAX_STRING_LENGTH", "最大字符串长度 (usize)"),
+            ("ENABLE_MAP_REDUCE", "是否对超长文档启用分块提取 (bool)"),
+            ("MAP_TEMPLATE", "分块提取使用的模板名称"),
+            ("REDUCE_TEMPLATE", "合并分块结果使用的模板名称"),
+            ("MAX_CONCURRENT_CHUNKS", "同时处理的分块数 (usize)"),
+            ("FETCH_TIMEOUT_SECONDS", "网页抓取超时时间 (u64, 秒)"),
+            ("FETCH_MAX_SIZE_MB", "网页最大下载大小 (f64, MB)"),
+            ("FETCH_USER_AGENT", "网页抓取使用的 User-Agent"),
+            ("HTML_KEEP_LINKS", "HTML 转 Markdown 时是否保留链接 (bool)"),
+            ("HTML_REMOVE_BOILERPLATE", "是否移除导航、页脚等页面模板内容 (bool)"),
+            ("LLM_RESPONSE_FORMAT", "结构化提取的输出模式 (json_schema/json_object/none)"),
+            ("STRUCTURED_TEMPLATE", "结构化提取使用的模板名称"),
+            ("STRUCTURED_MAX_RETRIES", "结构化输出校验失败后的最大重试次数 (u32)"),
+            ("CACHE_ENABLED", "是否启用 LLM 响应缓存 (bool)"),
+            ("CACHE_DIR", "响应缓存目录路径"),
+            ("CACHE_TTL_SECONDS", "缓存有效期 (u64, 秒, 0 表示永不过期)"),
+            ("CACHE_MAX_SIZE_MB", "缓存目录大小上限 (f64, MB)"),
+            ("TOKENIZER_ENCODING", "分词编码 (auto/cl100k_base/o200k_base/estimate)"),
+            ("TOKENIZER_VOCAB_DIR", "覆盖内置词表的 tiktoken 词表目录"),
+            ("TOKENIZER_ON_OVERFLOW", "超出上下文窗口时的处理 (chunk/error)"),
+            ("JOBS_DIR", "批量提取任务清单目录路径"),
+            ("JOBS_MAX_CONCURRENT", "同时运行的后台提取任务数 (usize)"),
+            ("JOBS_MAX_RETAINED", "最多保留的已结束后台任务数 (usize)"),
+            ("JOBS_MAX_RECORDED_RESULTS", "最多保留的同步提取结果数 (usize)"),
+            ("JOBS_RETENTION_SECONDS", "已结束后台任务的保留时间 (u64, 秒, 0 表示不限)"),
         ]
     }
 }
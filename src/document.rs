@@ -1,9 +1,14 @@
 use crate::cleaner::DocumentCleaner;
 use crate::config::ProcessingConfig;
 use crate::error::{Result, SmartFetchError};
+use crate::loaders::{LoadedContent, LoaderRegistry};
+use crate::tokenizer::{EstimateTokenCounter, TokenCounter};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use tracing_indicatif::indicatif_println;
+use walkdir::WalkDir;
 
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -22,11 +27,160 @@ pub struct DocumentMetadata {
     pub modified_at: Option<String>,
     pub word_count: usize,
     pub line_count: usize,
+    /// 分页文档（如 PDF）的总页数
+    pub page_count: Option<usize>,
+    /// 各页在 `Document::content` 中的字节范围
+    pub pages: Vec<PageSpan>,
+}
+
+/// 单页内容在文档文本中的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSpan {
+    /// 页码，从 1 开始
+    pub number: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 分页文档中每页开头插入的页码标记
+pub fn page_marker(number: usize) -> String {
+    format!("--- 第 {} 页 ---", number)
+}
+
+/// 不加载词表时估算文本的 token 数，见 [`EstimateTokenCounter`]
+pub fn estimate_token_count(content: &str) -> usize {
+    EstimateTokenCounter.count_tokens(content)
+}
+
+/// 从带页码标记的文本中截取第 `from` 页到第 `to` 页（含）的内容
+pub fn extract_page_range(content: &str, from: usize, to: usize) -> String {
+    let marker_regex = match regex::Regex::new(r"--- 第 (\d+) 页 ---") {
+        Ok(regex) => regex,
+        Err(_) => return String::new(),
+    };
+
+    let markers: Vec<(usize, usize)> = marker_regex
+        .captures_iter(content)
+        .filter_map(|captures| {
+            let start = captures.get(0)?.start();
+            let number = captures[1].parse().ok()?;
+            Some((start, number))
+        })
+        .collect();
+
+    let mut pages = Vec::new();
+    for (index, (start, number)) in markers.iter().enumerate() {
+        if *number < from || *number > to {
+            continue;
+        }
+        let end = markers
+            .get(index + 1)
+            .map(|(next_start, _)| *next_start)
+            .unwrap_or(content.len());
+        pages.push(content[*start..end].trim());
+    }
+
+    pages.join("\n\n")
+}
+
+/// 文件扩展名（不区分大小写）是否在 `supported_formats` 中
+pub fn has_supported_extension(path: &Path, supported_formats: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| supported_formats.contains(&ext.to_lowercase()))
+}
+
+/// 递归列出目录中扩展名受支持的文件（相对路径，按路径排序）
+///
+/// 位于 `skip_dir` 中的文件会被跳过
+pub fn list_documents(
+    dir: &Path,
+    supported_formats: &[String],
+    skip_dir: Option<&Path>,
+) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Err(SmartFetchError::DocumentError(format!(
+            "目录不存在: {:?}",
+            dir
+        )));
+    }
+    let skip_dir = skip_dir.and_then(|dir| dir.canonicalize().ok());
+
+    let mut documents = Vec::new();
+    let entries = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        skip_dir
+            .as_ref()
+            .is_none_or(|skip_dir| entry.path().canonicalize().ok().as_ref() != Some(skip_dir))
+    });
+    for entry in entries {
+        let entry = entry.map_err(|e| {
+            SmartFetchError::IoError(std::io::Error::other(format!("遍历目录失败: {}", e)))
+        })?;
+        if entry.file_type().is_file() && has_supported_extension(entry.path(), supported_formats) {
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            documents.push(relative.to_path_buf());
+        }
+    }
+    documents.sort();
+    Ok(documents)
+}
+
+impl DocumentMetadata {
+    /// 转换为模板可用的元数据（`metadata.title`、`metadata.page_count` 等）
+    pub fn template_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        if let Some(title) = &self.title {
+            metadata.insert("title".to_string(), title.clone());
+        }
+        if let Some(author) = &self.author {
+            metadata.insert("author".to_string(), author.clone());
+        }
+        if let Some(created_at) = &self.created_at {
+            metadata.insert("created_at".to_string(), created_at.clone());
+        }
+        if let Some(page_count) = self.page_count {
+            metadata.insert("page_count".to_string(), page_count.to_string());
+        }
+        metadata
+    }
+}
+
+impl Document {
+    /// 由加载器的转换结果构建文档，加载器未提供的元数据从文本中推断
+    pub fn from_loaded(
+        path: &Path,
+        content_type: &str,
+        size_bytes: usize,
+        loaded: LoadedContent,
+    ) -> Self {
+        let inferred = DocumentProcessor::extract_metadata(&loaded.content);
+        let metadata = DocumentMetadata {
+            title: loaded.metadata.title.or(inferred.title),
+            author: loaded.metadata.author.or(inferred.author),
+            created_at: loaded.metadata.created_at.or(inferred.created_at),
+            modified_at: loaded.metadata.modified_at.or(inferred.modified_at),
+            word_count: inferred.word_count,
+            line_count: inferred.line_count,
+            page_count: loaded.metadata.page_count,
+            pages: loaded.metadata.pages,
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            content: loaded.content,
+            content_type: content_type.to_string(),
+            size_bytes,
+            metadata,
+        }
+    }
 }
 
 pub struct DocumentProcessor {
     config: ProcessingConfig,
     cleaner: Option<DocumentCleaner>,
+    /// 未指定时在首次加载文档时创建内置格式的注册表
+    loader_registry: OnceLock<Arc<LoaderRegistry>>,
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl DocumentProcessor {
@@ -45,9 +199,28 @@ impl DocumentProcessor {
         Ok(Self {
             config,
             cleaner,
+            loader_registry: OnceLock::new(),
+            token_counter: Arc::new(EstimateTokenCounter),
         })
     }
 
+    /// 使用指定的分词器统计文档的 token 数
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    /// 使用指定的加载器注册表（例如注册了自定义格式的注册表）
+    pub fn with_loader_registry(mut self, loader_registry: Arc<LoaderRegistry>) -> Self {
+        self.loader_registry = OnceLock::from(loader_registry);
+        self
+    }
+
+    pub fn loader_registry(&self) -> &LoaderRegistry {
+        self.loader_registry
+            .get_or_init(|| Arc::new(LoaderRegistry::with_builtin(&self.config)))
+    }
+
     #[tracing::instrument(level = "info", skip(self), name = "加载文档文件")]
     pub async fn load_document(&self, path: &Path) -> Result<Document> {
         if !path.exists() {
@@ -70,39 +243,35 @@ impl DocumentProcessor {
             ));
         }
 
-        let content = fs::read_to_string(path)
+        let bytes = fs::read(path)
             .map_err(|e| SmartFetchError::DocumentError(format!("无法读取文件内容: {}", e)))?;
 
-        let content_type = Self::detect_content_type(path)?;
-        let document_metadata = Self::extract_metadata(&content);
+        let loader = self.loader_registry().detect(path, &bytes).ok_or_else(|| {
+            SmartFetchError::DocumentError(format!("不支持的文档格式: {:?}", path))
+        })?;
+        let document = loader.load(path, &bytes)?;
 
         // 添加文档加载完成提示
         indicatif_println!("✅ 文档加载成功: {} ({} 字符)",
             path.file_name().and_then(|name| name.to_str()).unwrap_or("未知文件"),
-            content.len());
+            document.content.len());
 
-        Ok(Document {
-            path: path.to_path_buf(),
-            content,
-            content_type,
-            size_bytes: metadata.len() as usize,
-            metadata: document_metadata,
-        })
+        Ok(document)
     }
 
+    /// 按扩展名检测内置格式的内容类型，无法识别时视为纯文本
     pub fn detect_content_type(path: &Path) -> Result<String> {
-        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-
-        match extension.to_lowercase().as_str() {
-            "txt" => Ok("text/plain".to_string()),
-            "md" => Ok("text/markdown".to_string()),
-            "json" => Ok("application/json".to_string()),
-            "yaml" | "yml" => Ok("text/yaml".to_string()),
-            "toml" => Ok("text/toml".to_string()),
-            "xml" => Ok("application/xml".to_string()),
-            "csv" => Ok("text/csv".to_string()),
-            _ => Ok("text/plain".to_string()),
-        }
+        // 内容类型与处理配置无关，内置注册表只需创建一次
+        static BUILTIN: OnceLock<LoaderRegistry> = OnceLock::new();
+        let registry =
+            BUILTIN.get_or_init(|| LoaderRegistry::with_builtin(&ProcessingConfig::default()));
+        let content_type = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| registry.find_by_extension(ext))
+            .map(|loader| loader.content_type().to_string())
+            .unwrap_or_else(|| "text/plain".to_string());
+        Ok(content_type)
     }
 
     pub fn extract_metadata(content: &str) -> DocumentMetadata {
@@ -122,6 +291,8 @@ impl DocumentProcessor {
             modified_at: None,
             word_count,
             line_count,
+            page_count: None,
+            pages: Vec::new(),
         }
     }
 
@@ -176,7 +347,12 @@ impl DocumentProcessor {
 
     #[tracing::instrument(level = "debug", skip(self, content), name = "分块处理文档")]
     pub fn chunk_content(&self, content: &str) -> Result<Vec<String>> {
-        let chunk_size = self.config.chunk_size.unwrap_or(4000);
+        self.chunk_content_with_size(content, self.config.chunk_size.unwrap_or(4000))
+    }
+
+    /// 按指定的分块大小（字节）切分内容
+    pub fn chunk_content_with_size(&self, content: &str, chunk_size: usize) -> Result<Vec<String>> {
+        let chunk_size = chunk_size.max(1);
 
         if content.len() <= chunk_size {
             return Ok(vec![content.to_string()]);
@@ -188,7 +364,11 @@ impl DocumentProcessor {
         let mut start = 0;
 
         while start < content.len() {
-            let end = (start + chunk_size).min(content.len());
+            let mut end = (start + chunk_size).min(content.len());
+            // 避免在多字节字符中间切分
+            while !content.is_char_boundary(end) {
+                end += 1;
+            }
 
             // 尝试在句子边界处分割
             let chunk_end = if end < content.len() {
@@ -237,7 +417,12 @@ impl DocumentProcessor {
 
         let extension = document.path.extension().and_then(|ext| ext.to_str());
         if let Some(ext) = extension {
-            if !self.config.supported_formats.contains(&ext.to_lowercase()) {
+            let supported = if self.config.supported_formats.is_empty() {
+                self.loader_registry().find_by_extension(ext).is_some()
+            } else {
+                self.config.supported_formats.contains(&ext.to_lowercase())
+            };
+            if !supported {
                 return Err(SmartFetchError::ValidationError(format!(
                     "不支持的文件格式: {}",
                     ext
@@ -259,9 +444,7 @@ impl DocumentProcessor {
     }
 
     pub fn estimate_tokens(&self, content: &str) -> usize {
-        // 简单的token估算：通常1个token ≈ 4个字符（英文）或 1-2个汉字
-        let char_count = content.chars().count();
-        char_count / 4
+        self.token_counter.count_tokens(content)
     }
 }
 
@@ -36,6 +36,31 @@ pub enum SmartFetchError {
 
     #[error("正则表达式错误: {0}")]
     RegexError(String),
+
+    /// 提示词加最大输出 token 数超出模型的上下文窗口，请求未发送
+    #[error("提示词超出模型上下文窗口: 提示词 {prompt_tokens} tokens + 最大输出 {max_tokens} tokens > {model} 的上下文窗口 {context_window} tokens")]
+    ContextWindowExceeded {
+        model: String,
+        prompt_tokens: usize,
+        max_tokens: usize,
+        context_window: usize,
+    },
+
+    /// 流式响应中途断开且无法续传，`partial` 保存已接收的内容
+    #[error("流式响应中断（已接收{}字符）: {reason}", partial.chars().count())]
+    StreamInterrupted { reason: String, partial: String },
+}
+
+impl SmartFetchError {
+    /// 流式响应中断时已接收的部分结果
+    pub fn partial_output(&self) -> Option<&str> {
+        match self {
+            SmartFetchError::StreamInterrupted { partial, .. } if !partial.is_empty() => {
+                Some(partial)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl From<serde_json::Error> for SmartFetchError {
@@ -0,0 +1,150 @@
+use crate::config::FetchConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::html::HtmlConverter;
+use std::time::Duration;
+use tracing_indicatif::indicatif_println;
+
+/// 抓取到的网页内容
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    /// 请求的地址
+    pub url: String,
+    /// 跟随重定向后的最终地址
+    pub final_url: String,
+    pub content_type: String,
+    pub title: Option<String>,
+    /// 转换后的可读文本（HTML 页面转换为 Markdown）
+    pub content: String,
+    pub size_bytes: usize,
+}
+
+/// 通过 HTTP(S) 下载网页并转换为可读文本
+#[derive(Debug)]
+pub struct UrlFetcher {
+    config: FetchConfig,
+    http_client: reqwest::Client,
+    html_converter: HtmlConverter,
+}
+
+impl UrlFetcher {
+    pub fn new(config: FetchConfig) -> Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_seconds.unwrap_or(30));
+        let redirect_policy =
+            reqwest::redirect::Policy::limited(config.max_redirects.unwrap_or(10));
+
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .redirect(redirect_policy)
+            .user_agent(
+                config
+                    .user_agent
+                    .clone()
+                    .unwrap_or_else(|| "mcp-smart-fetch/0.1.0".to_string()),
+            )
+            .build()
+            .map_err(|e| SmartFetchError::NetworkError(format!("创建HTTP客户端失败: {}", e)))?;
+
+        Ok(Self {
+            config,
+            http_client,
+            html_converter: HtmlConverter::default(),
+        })
+    }
+
+    /// 指定网页转换为 Markdown 时使用的转换器
+    pub fn with_html_converter(mut self, html_converter: HtmlConverter) -> Self {
+        self.html_converter = html_converter;
+        self
+    }
+
+    #[tracing::instrument(level = "info", skip(self), name = "抓取网页")]
+    pub async fn fetch(&self, url: &str) -> Result<FetchedPage> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| SmartFetchError::ValidationError(format!("无效的URL: {} - {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(SmartFetchError::ValidationError(format!(
+                "仅支持 http/https 协议: {}",
+                url
+            )));
+        }
+
+        let max_size_bytes = self.max_size_bytes();
+        let mut response = self.http_client.get(parsed).send().await?;
+
+        if !response.status().is_success() {
+            return Err(SmartFetchError::NetworkError(format!(
+                "抓取网页失败: {} - {}",
+                url,
+                response.status()
+            )));
+        }
+
+        if let Some(length) = response.content_length() {
+            if length as usize > max_size_bytes {
+                return Err(SmartFetchError::ValidationError(format!(
+                    "网页大小超过限制: {} 字节 > {} 字节",
+                    length, max_size_bytes
+                )));
+            }
+        }
+
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(';').next().unwrap_or("").trim().to_lowercase())
+            .unwrap_or_else(|| "text/html".to_string());
+
+        if !is_text_content_type(&content_type) {
+            return Err(SmartFetchError::DocumentError(format!(
+                "不支持的网页内容类型: {}",
+                content_type
+            )));
+        }
+
+        // 分段读取，避免服务端未声明长度时下载超大内容
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > max_size_bytes {
+                return Err(SmartFetchError::ValidationError(format!(
+                    "网页大小超过限制: > {} 字节",
+                    max_size_bytes
+                )));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let raw = String::from_utf8_lossy(&body).into_owned();
+        let (title, content) = if content_type.contains("html") {
+            (
+                HtmlConverter::extract_title(&raw),
+                self.html_converter.convert(&raw),
+            )
+        } else {
+            (None, raw)
+        };
+
+        indicatif_println!("✅ 网页抓取成功: {} ({} 字节)", final_url, body.len());
+
+        Ok(FetchedPage {
+            url: url.to_string(),
+            final_url,
+            content_type,
+            title,
+            content,
+            size_bytes: body.len(),
+        })
+    }
+
+    fn max_size_bytes(&self) -> usize {
+        (self.config.max_size_mb.unwrap_or(10.0) * 1024.0 * 1024.0) as usize
+    }
+}
+
+fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || content_type.contains("html")
+        || content_type.contains("json")
+        || content_type.contains("xml")
+}
@@ -0,0 +1,226 @@
+use crate::error::{Result, SmartFetchError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// 模板头部（front-matter）声明的元数据
+///
+/// 以 `---` 包围时按 YAML 解析，以 `+++` 包围时按 TOML 解析
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateFrontMatter {
+    /// 模板用途说明
+    pub description: Option<String>,
+    /// 模板使用的自定义变量，在模板中通过 `{{variables.<name>}}` 引用
+    pub variables: BTreeMap<String, TemplateVariable>,
+    /// 系统提示词，同样按 Handlebars 渲染
+    pub system: Option<String>,
+    /// 推荐使用的模型，请求指定了模型配置时以模型配置为准
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+}
+
+impl TemplateFrontMatter {
+    /// 是否声明了模型参数
+    pub fn has_model_overrides(&self) -> bool {
+        self.model.is_some() || self.temperature.is_some() || self.max_tokens.is_some()
+    }
+}
+
+/// 模板声明的自定义变量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateVariable {
+    #[serde(rename = "type")]
+    pub kind: VariableType,
+    /// 是否必须提供；未设置时没有默认值的变量视为必填
+    pub required: Option<bool>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+impl TemplateVariable {
+    pub fn is_required(&self) -> bool {
+        self.required.unwrap_or(self.default.is_none())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl VariableType {
+    /// 将传入的值转换为声明的类型，字符串形式的数字和布尔值（如命令行参数）会被解析
+    fn coerce(self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (VariableType::String, Value::String(_)) => Some(value.clone()),
+            (VariableType::String, Value::Number(_) | Value::Bool(_)) => {
+                Some(Value::String(value.to_string()))
+            }
+            (VariableType::Integer, Value::Number(number))
+                if number.is_i64() || number.is_u64() =>
+            {
+                Some(value.clone())
+            }
+            (VariableType::Integer, Value::String(text)) => {
+                text.trim().parse::<i64>().ok().map(Value::from)
+            }
+            (VariableType::Number, Value::Number(_)) => Some(value.clone()),
+            (VariableType::Number, Value::String(text)) => {
+                text.trim().parse::<f64>().ok().map(Value::from)
+            }
+            (VariableType::Boolean, Value::Bool(_)) => Some(value.clone()),
+            (VariableType::Boolean, Value::String(text)) => {
+                match text.trim().to_lowercase().as_str() {
+                    "true" | "1" | "yes" | "on" => Some(Value::Bool(true)),
+                    "false" | "0" | "no" | "off" => Some(Value::Bool(false)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Integer => "integer",
+            VariableType::Number => "number",
+            VariableType::Boolean => "boolean",
+        }
+    }
+}
+
+/// 拆分模板原文中的 front-matter 和 Handlebars 正文，没有 front-matter 时原样返回正文
+pub fn split_front_matter<'a>(
+    template_name: &str,
+    source: &'a str,
+) -> Result<(Option<TemplateFrontMatter>, &'a str)> {
+    let Some((delimiter, header, body)) = ["---", "+++"]
+        .into_iter()
+        .find_map(|delimiter| extract_header(source, delimiter).map(|(h, b)| (delimiter, h, b)))
+    else {
+        return Ok((None, source));
+    };
+
+    let parsed = if delimiter == "+++" {
+        toml::from_str(header).map_err(|e| e.to_string())
+    } else {
+        parse_yaml(header)
+    };
+    let front_matter = parsed.map_err(|e| {
+        SmartFetchError::TemplateError(format!("解析模板头部失败: {} - {}", template_name, e))
+    })?;
+    Ok((Some(front_matter), body))
+}
+
+/// 按模板声明检查并补全变量：缺少必填变量或类型不匹配时报错，未声明的变量原样保留
+pub fn resolve_variables(
+    template_name: &str,
+    front_matter: Option<&TemplateFrontMatter>,
+    provided: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>> {
+    let Some(front_matter) = front_matter else {
+        return Ok(provided);
+    };
+
+    let mut resolved = provided;
+    let mut missing = Vec::new();
+    for (name, variable) in &front_matter.variables {
+        match resolved.get(name) {
+            Some(value) => {
+                let coerced = variable.kind.coerce(value).ok_or_else(|| {
+                    SmartFetchError::TemplateError(format!(
+                        "模板 {} 的变量 {} 应为 {} 类型，实际为: {}",
+                        template_name,
+                        name,
+                        variable.kind.name(),
+                        value
+                    ))
+                })?;
+                resolved.insert(name.clone(), coerced);
+            }
+            None => match &variable.default {
+                Some(default) => {
+                    resolved.insert(name.clone(), default.clone());
+                }
+                None if variable.is_required() => missing.push(match &variable.description {
+                    Some(description) => format!("{}（{}）", name, description),
+                    None => name.clone(),
+                }),
+                // 可选变量以 null 填充，严格模式下模板仍可通过 `{{#if}}` 判断
+                None => {
+                    resolved.insert(name.clone(), Value::Null);
+                }
+            },
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(SmartFetchError::TemplateError(format!(
+            "模板 {} 缺少必填变量: {}",
+            template_name,
+            missing.join("、")
+        )));
+    }
+    Ok(resolved)
+}
+
+/// 模板以分隔行开头时，返回分隔行之间的头部和其后的正文
+fn extract_header<'a>(source: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let rest = source.strip_prefix(delimiter)?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn parse_yaml(header: &str) -> std::result::Result<TemplateFrontMatter, String> {
+    let documents = YamlLoader::load_from_str(header).map_err(|e| e.to_string())?;
+    let value = documents.first().map(yaml_to_json).unwrap_or(Value::Null);
+    if value.is_null() {
+        return Ok(TemplateFrontMatter::default());
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+fn yaml_to_json(yaml: &Yaml) -> Value {
+    match yaml {
+        Yaml::Real(_) => yaml.as_f64().map(Value::from).unwrap_or(Value::Null),
+        Yaml::Integer(value) => Value::from(*value),
+        Yaml::String(value) => Value::String(value.clone()),
+        Yaml::Boolean(value) => Value::Bool(*value),
+        Yaml::Array(items) => Value::Array(items.iter().map(yaml_to_json).collect()),
+        Yaml::Hash(entries) => Value::Object(
+            entries
+                .iter()
+                .filter_map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(key) => key.clone(),
+                        Yaml::Integer(key) => key.to_string(),
+                        Yaml::Boolean(key) => key.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, yaml_to_json(value)))
+                })
+                .collect(),
+        ),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Value::Null,
+    }
+}
@@ -0,0 +1,390 @@
+use crate::config::HtmlConfig;
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// 转换时整体丢弃的元素（脚本、样式、导航等页面模板内容）
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "canvas", "object",
+    "embed", "form", "button", "select", "input", "textarea",
+];
+const BOILERPLATE_TAGS: &[&str] = &["nav", "footer", "aside"];
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "contentinfo", "banner", "complementary"];
+
+/// HTML 转 Markdown 转换器
+#[derive(Debug, Clone)]
+pub struct HtmlConverter {
+    keep_links: bool,
+    remove_boilerplate: bool,
+}
+
+impl Default for HtmlConverter {
+    fn default() -> Self {
+        Self::new(HtmlConfig::default())
+    }
+}
+
+impl HtmlConverter {
+    pub fn new(config: HtmlConfig) -> Self {
+        Self {
+            keep_links: config.keep_links.unwrap_or(false),
+            remove_boilerplate: config.remove_boilerplate.unwrap_or(true),
+        }
+    }
+
+    /// 提取 `<title>` 内容
+    pub fn extract_title(html: &str) -> Option<String> {
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("title").ok()?;
+        document
+            .select(&selector)
+            .next()
+            .map(|title| collapse_whitespace(&title.text().collect::<String>()))
+            .filter(|title| !title.is_empty())
+    }
+
+    /// 将 HTML 转换为 Markdown，保留标题、列表、表格和代码块结构
+    pub fn convert(&self, html: &str) -> String {
+        let document = Html::parse_document(html);
+
+        // 页面存在 <main> 时只转换主体内容
+        let root = Selector::parse("main")
+            .ok()
+            .and_then(|selector| document.select(&selector).next())
+            .unwrap_or_else(|| document.root_element());
+
+        let mut writer = MarkdownWriter::new(self);
+        writer.walk_children(*root);
+        writer.finish()
+    }
+
+    fn is_skipped(&self, element: ElementRef<'_>) -> bool {
+        let name = element.value().name();
+        if SKIPPED_TAGS.contains(&name) {
+            return true;
+        }
+        if !self.remove_boilerplate {
+            return false;
+        }
+        if BOILERPLATE_TAGS.contains(&name) {
+            return true;
+        }
+        if element.value().attr("aria-hidden") == Some("true") {
+            return true;
+        }
+        element
+            .value()
+            .attr("role")
+            .is_some_and(|role| BOILERPLATE_ROLES.contains(&role))
+    }
+}
+
+struct MarkdownWriter<'a> {
+    converter: &'a HtmlConverter,
+    out: String,
+    pending_space: bool,
+}
+
+impl<'a> MarkdownWriter<'a> {
+    fn new(converter: &'a HtmlConverter) -> Self {
+        Self {
+            converter,
+            out: String::new(),
+            pending_space: false,
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut result = String::new();
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        result.trim().to_string()
+    }
+
+    /// 开始新的段落块
+    fn block_break(&mut self) {
+        self.pending_space = false;
+        if self.out.is_empty() {
+            return;
+        }
+        while !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.pending_space = false;
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn push_inline(&mut self, text: &str) {
+        if self.pending_space && !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+        self.out.push_str(text);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        for word_or_space in split_keep_whitespace(text) {
+            match word_or_space {
+                Segment::Space => self.pending_space = true,
+                Segment::Word(word) => self.push_inline(word),
+            }
+        }
+    }
+
+    /// 用独立的写入器渲染子元素，便于添加前缀或放入表格单元格
+    fn render_nested(&self, element: ElementRef<'_>) -> String {
+        let mut nested = MarkdownWriter::new(self.converter);
+        nested.walk_children(*element);
+        nested.finish()
+    }
+
+    fn walk_children(&mut self, node: NodeRef<'_, Node>) {
+        for child in node.children() {
+            self.walk(child);
+        }
+    }
+
+    fn walk(&mut self, node: NodeRef<'_, Node>) {
+        match node.value() {
+            Node::Text(text) => self.push_text(text),
+            Node::Element(_) => {
+                if let Some(element) = ElementRef::wrap(node) {
+                    if !self.converter.is_skipped(element) {
+                        self.walk_element(element);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_element(&mut self, element: ElementRef<'_>) {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = collapse_whitespace(&self.render_nested(element));
+                if !text.is_empty() {
+                    self.block_break();
+                    self.out.push_str(&format!("{} {}", "#".repeat(level), text));
+                    self.block_break();
+                }
+            }
+            "p" | "div" | "section" | "article" | "header" | "main" | "figure" | "figcaption"
+            | "dl" | "details" | "summary" | "address" => {
+                self.block_break();
+                self.walk_children(*element);
+                self.block_break();
+            }
+            "dt" | "dd" => {
+                self.line_break();
+                self.walk_children(*element);
+                self.line_break();
+            }
+            "br" => self.line_break(),
+            "hr" => {
+                self.block_break();
+                self.out.push_str("---");
+                self.block_break();
+            }
+            "ul" | "ol" => self.write_list(element, name == "ol"),
+            "pre" => self.write_code_block(element),
+            "code" | "kbd" | "samp" => {
+                let code = element.text().collect::<String>();
+                if !code.trim().is_empty() {
+                    self.push_inline(&format!("`{}`", code.trim()));
+                }
+            }
+            "strong" | "b" => self.write_wrapped(element, "**"),
+            "em" | "i" => self.write_wrapped(element, "*"),
+            "del" | "s" => self.write_wrapped(element, "~~"),
+            "a" => self.write_link(element),
+            "img" => {
+                if let Some(alt) = element.value().attr("alt").filter(|alt| !alt.trim().is_empty()) {
+                    match element.value().attr("src").filter(|_| self.converter.keep_links) {
+                        Some(src) => self.push_inline(&format!("![{}]({})", alt.trim(), src)),
+                        None => self.push_inline(alt.trim()),
+                    }
+                }
+            }
+            "blockquote" => {
+                let inner = self.render_nested(element);
+                if !inner.is_empty() {
+                    self.block_break();
+                    let quoted = inner
+                        .lines()
+                        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    self.out.push_str(&quoted);
+                    self.block_break();
+                }
+            }
+            "table" => self.write_table(element),
+            _ => self.walk_children(*element),
+        }
+    }
+
+    fn write_wrapped(&mut self, element: ElementRef<'_>, marker: &str) {
+        let inner = collapse_whitespace(&self.render_nested(element));
+        if !inner.is_empty() {
+            let leading_space = element
+                .text()
+                .next()
+                .is_some_and(|text| text.starts_with(char::is_whitespace));
+            if leading_space {
+                self.pending_space = true;
+            }
+            self.push_inline(&format!("{}{}{}", marker, inner, marker));
+        }
+    }
+
+    fn write_link(&mut self, element: ElementRef<'_>) {
+        let text = collapse_whitespace(&self.render_nested(element));
+        let href = element
+            .value()
+            .attr("href")
+            .filter(|href| !href.starts_with("javascript:") && !href.starts_with('#'));
+
+        match href.filter(|_| self.converter.keep_links) {
+            Some(href) if !text.is_empty() => self.push_inline(&format!("[{}]({})", text, href)),
+            _ if !text.is_empty() => self.push_inline(&text),
+            _ => {}
+        }
+    }
+
+    fn write_list(&mut self, element: ElementRef<'_>, ordered: bool) {
+        self.block_break();
+        let mut index = 1;
+        for child in element.children().filter_map(ElementRef::wrap) {
+            if child.value().name() != "li" {
+                continue;
+            }
+            let marker = if ordered {
+                format!("{}. ", index)
+            } else {
+                "- ".to_string()
+            };
+            index += 1;
+
+            let item = self.render_nested(child);
+            let indent = " ".repeat(marker.len());
+            let mut lines = item.lines().filter(|line| !line.trim().is_empty());
+            self.out.push_str(&marker);
+            self.out.push_str(lines.next().unwrap_or(""));
+            self.out.push('\n');
+            for line in lines {
+                self.out.push_str(&indent);
+                self.out.push_str(line);
+                self.out.push('\n');
+            }
+        }
+        self.block_break();
+    }
+
+    fn write_code_block(&mut self, element: ElementRef<'_>) {
+        let language = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .chain(std::iter::once(element))
+            .filter_map(|el| el.value().attr("class"))
+            .flat_map(|class| class.split_whitespace())
+            .find_map(|class| {
+                class
+                    .strip_prefix("language-")
+                    .or_else(|| class.strip_prefix("lang-"))
+            })
+            .unwrap_or("");
+        let code = element.text().collect::<String>();
+
+        self.block_break();
+        self.out.push_str(&format!(
+            "```{}\n{}\n```",
+            language,
+            code.trim_matches('\n')
+        ));
+        self.block_break();
+    }
+
+    fn write_table(&mut self, element: ElementRef<'_>) {
+        let row_selector = match Selector::parse("tr") {
+            Ok(selector) => selector,
+            Err(_) => return,
+        };
+
+        let rows: Vec<Vec<String>> = element
+            .select(&row_selector)
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "th" | "td"))
+                    .map(|cell| {
+                        collapse_whitespace(&self.render_nested(cell)).replace('|', "\\|")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect();
+
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        self.block_break();
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if index == 0 {
+                self.out
+                    .push_str(&format!("|{}\n", " --- |".repeat(columns)));
+            }
+        }
+        self.block_break();
+    }
+}
+
+enum Segment<'t> {
+    Space,
+    Word(&'t str),
+}
+
+fn split_keep_whitespace(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut start = None;
+    for (index, ch) in text.char_indices() {
+        if ch.is_whitespace() {
+            if let Some(word_start) = start.take() {
+                segments.push(Segment::Word(&text[word_start..index]));
+            }
+            segments.push(Segment::Space);
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(word_start) = start {
+        segments.push(Segment::Word(&text[word_start..]));
+    }
+    segments
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,177 @@
+use crate::config::ServerConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::mcp_server::McpSmartFetchServer;
+use axum::serve::Listener;
+use rmcp::transport::{
+    sse_server::{SseServer, SseServerConfig},
+    streamable_http_server::{
+        session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+    },
+};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Streamable HTTP 端点路径
+pub const STREAMABLE_HTTP_PATH: &str = "/mcp";
+/// 旧版 SSE 端点路径
+pub const SSE_PATH: &str = "/sse";
+/// 旧版 SSE 消息投递路径
+pub const SSE_POST_PATH: &str = "/message";
+
+impl McpSmartFetchServer {
+    /// 按配置绑定地址，以 HTTP 方式运行服务器，直到收到 Ctrl-C
+    pub async fn run_http(self, server_config: &ServerConfig) -> Result<()> {
+        let bind = format!("{}:{}", server_config.host, server_config.port);
+        let listener = TcpListener::bind(&bind)
+            .await
+            .map_err(|e| SmartFetchError::NetworkError(format!("绑定地址失败: {} - {}", bind, e)))?;
+
+        let ct = CancellationToken::new();
+        let shutdown = ct.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                info!("收到退出信号，正在关闭 HTTP 服务器");
+            }
+            shutdown.cancel();
+        });
+
+        self.serve_http(listener, server_config.max_connections, ct)
+            .await
+    }
+
+    /// 在给定监听器上同时提供 Streamable HTTP（`/mcp`）和旧版 SSE（`/sse` + `/message`）传输
+    pub async fn serve_http(
+        self,
+        listener: TcpListener,
+        max_connections: Option<u32>,
+        ct: CancellationToken,
+    ) -> Result<()> {
+        let local_addr = listener.local_addr()?;
+
+        let streamable_server = self.clone();
+        let streamable_service = StreamableHttpService::new(
+            move || Ok(streamable_server.for_session()),
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig::default(),
+        );
+
+        let (sse_server, sse_router) = SseServer::new(SseServerConfig {
+            bind: local_addr,
+            sse_path: SSE_PATH.to_string(),
+            post_path: SSE_POST_PATH.to_string(),
+            ct: ct.child_token(),
+            sse_keep_alive: None,
+        });
+        let sse_handler = self.clone();
+        sse_server.with_service(move || sse_handler.for_session());
+
+        let router = sse_router.nest_service(STREAMABLE_HTTP_PATH, streamable_service);
+
+        info!("HTTP 服务器监听于 {}", local_addr);
+        let listener = ConnectionLimitedListener::new(listener, max_connections);
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { ct.cancelled().await })
+            .await
+            .map_err(|e| SmartFetchError::NetworkError(format!("HTTP 服务器运行失败: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// 限制同时保持的 TCP 连接数，超过上限时暂停接受新连接
+struct ConnectionLimitedListener {
+    inner: TcpListener,
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl ConnectionLimitedListener {
+    fn new(inner: TcpListener, max_connections: Option<u32>) -> Self {
+        let permits = max_connections
+            .filter(|max| *max > 0)
+            .map(|max| Arc::new(Semaphore::new(max as usize)));
+        Self { inner, permits }
+    }
+}
+
+impl Listener for ConnectionLimitedListener {
+    type Io = LimitedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let permit = match &self.permits {
+            Some(permits) => {
+                if permits.available_permits() == 0 {
+                    warn!("连接数已达上限，等待已有连接释放");
+                }
+                permits.clone().acquire_owned().await.ok()
+            }
+            None => None,
+        };
+        let (stream, addr) = Listener::accept(&mut self.inner).await;
+        (
+            LimitedStream {
+                stream,
+                _permit: permit,
+            },
+            addr,
+        )
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// 持有连接许可的 TCP 流，连接关闭时释放许可
+struct LimitedStream {
+    stream: TcpStream,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl AsyncRead for LimitedStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for LimitedStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
@@ -0,0 +1,359 @@
+use crate::config::JobsConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::LlmOutput;
+use crate::progress::{ExtractionProgress, ProgressCallback};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+
+/// 后台提取任务的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuedJobStatus {
+    /// 等待空闲的执行名额
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl fmt::Display for QueuedJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QueuedJobStatus::Queued => "queued",
+            QueuedJobStatus::Running => "running",
+            QueuedJobStatus::Succeeded => "succeeded",
+            QueuedJobStatus::Failed => "failed",
+            QueuedJobStatus::Cancelled => "cancelled",
+        })
+    }
+}
+
+impl QueuedJobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            QueuedJobStatus::Succeeded | QueuedJobStatus::Failed | QueuedJobStatus::Cancelled
+        )
+    }
+}
+
+/// 后台提取任务的当前状态
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJobInfo {
+    pub id: String,
+    pub status: QueuedJobStatus,
+    /// 内容来源的说明，如文件路径或网页地址
+    pub source: String,
+    /// 完成百分比（0-100）
+    pub progress: f64,
+    /// 最近一条进度消息
+    pub message: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// 后台提取任务的结果
+#[derive(Debug, Clone)]
+pub enum QueuedJobOutcome {
+    Succeeded(LlmOutput),
+    Failed {
+        error: String,
+        /// 流式响应中断前已生成的部分结果
+        partial_output: Option<String>,
+    },
+    Cancelled,
+}
+
+struct QueuedJob {
+    info: QueuedJobInfo,
+    outcome: Option<QueuedJobOutcome>,
+    abort: Option<AbortHandle>,
+}
+
+impl fmt::Debug for QueuedJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueuedJob")
+            .field("info", &self.info)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 同步提取工具记录的结果
+#[derive(Debug, Clone)]
+pub struct RecordedResult {
+    pub id: String,
+    /// 内容来源的说明，如文件路径或网页地址
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub output: LlmOutput,
+}
+
+/// 进程内的后台提取任务队列
+///
+/// 同时运行的任务数受 `max_concurrent` 限制，其余任务排队等待；已结束的任务保留
+/// `retention_seconds` 秒，且最多保留 `max_retained` 个，超出时先删除最早结束的任务。
+/// 同步提取的结果单独保存，最多保留 `max_recorded_results` 个，不占用后台任务的名额
+#[derive(Debug)]
+pub struct JobQueue {
+    semaphore: Arc<Semaphore>,
+    max_retained: usize,
+    max_recorded: usize,
+    retention: Option<Duration>,
+    jobs: Mutex<HashMap<String, QueuedJob>>,
+    recorded: Mutex<HashMap<String, RecordedResult>>,
+}
+
+impl JobQueue {
+    pub fn new(config: &JobsConfig) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(config.max_concurrent.unwrap_or(2).max(1))),
+            max_retained: config.max_retained.unwrap_or(100),
+            max_recorded: config.max_recorded_results.unwrap_or(100),
+            retention: config
+                .retention_seconds
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs),
+            jobs: Mutex::new(HashMap::new()),
+            recorded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 提交任务，立即返回任务 ID
+    ///
+    /// `run` 在获得执行名额后调用，传入的进度回调会更新任务的完成百分比
+    pub fn submit<F, Fut>(self: &Arc<Self>, source: String, run: F) -> String
+    where
+        F: FnOnce(ProgressCallback) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<LlmOutput>> + Send + 'static,
+    {
+        let id = uuid::Uuid::new_v4().to_string();
+        {
+            let mut jobs = self.lock();
+            self.prune(&mut jobs);
+            jobs.insert(
+                id.clone(),
+                QueuedJob {
+                    info: QueuedJobInfo {
+                        id: id.clone(),
+                        status: QueuedJobStatus::Queued,
+                        source,
+                        progress: 0.0,
+                        message: None,
+                        error: None,
+                        created_at: Utc::now(),
+                        started_at: None,
+                        finished_at: None,
+                    },
+                    outcome: None,
+                    abort: None,
+                },
+            );
+        }
+
+        let queue = Arc::clone(self);
+        let job_id = id.clone();
+        let task = tokio::spawn(async move {
+            // 信号量不会被关闭，获取失败时直接运行
+            let _permit = Arc::clone(&queue.semaphore).acquire_owned().await.ok();
+            queue.update(&job_id, |job| {
+                job.info.status = QueuedJobStatus::Running;
+                job.info.started_at = Some(Utc::now());
+            });
+
+            let progress_queue = Arc::clone(&queue);
+            let progress_id = job_id.clone();
+            let progress: ProgressCallback = Arc::new(move |event: &ExtractionProgress| {
+                progress_queue.update(&progress_id, |job| {
+                    if event.total > 0 {
+                        // 完成前最多显示 99%
+                        let percent = event.progress_value() / event.total as f64 * 100.0;
+                        job.info.progress = percent.min(99.0).max(job.info.progress);
+                    }
+                    job.info.message = Some(event.message.clone());
+                });
+            });
+
+            let result = run(progress).await;
+            queue.update(&job_id, |job| {
+                job.info.finished_at = Some(Utc::now());
+                job.abort = None;
+                match result {
+                    Ok(output) => {
+                        job.info.status = QueuedJobStatus::Succeeded;
+                        job.info.progress = 100.0;
+                        job.outcome = Some(QueuedJobOutcome::Succeeded(output));
+                    }
+                    Err(e) => {
+                        job.info.status = QueuedJobStatus::Failed;
+                        job.info.error = Some(e.to_string());
+                        job.outcome = Some(QueuedJobOutcome::Failed {
+                            error: e.to_string(),
+                            partial_output: e.partial_output().map(str::to_string),
+                        });
+                    }
+                }
+            });
+        });
+
+        if let Some(job) = self.lock().get_mut(&id) {
+            if !job.info.status.is_finished() {
+                job.abort = Some(task.abort_handle());
+            }
+        }
+        id
+    }
+
+    /// 记录一次已完成的同步提取，返回结果的 ID，可通过 [`JobQueue::recorded`] 查询
+    ///
+    /// 记录的结果不是后台任务，不出现在 [`JobQueue::list`] 中，也不会挤掉未取回的任务结果
+    pub fn record(&self, source: String, output: LlmOutput) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut recorded = self.lock_recorded();
+        recorded.insert(
+            id.clone(),
+            RecordedResult {
+                id: id.clone(),
+                source,
+                created_at: Utc::now(),
+                output,
+            },
+        );
+        self.prune_recorded(&mut recorded);
+        id
+    }
+
+    /// 记录的同步提取结果
+    pub fn recorded(&self, id: &str) -> Result<RecordedResult> {
+        let mut recorded = self.lock_recorded();
+        self.prune_recorded(&mut recorded);
+        recorded.get(id).cloned().ok_or_else(|| {
+            SmartFetchError::ValidationError(format!("提取结果不存在或已过期: {}", id))
+        })
+    }
+
+    /// 所有保留中的同步提取结果，最早记录的在前
+    pub fn recorded_results(&self) -> Vec<RecordedResult> {
+        let mut recorded = self.lock_recorded();
+        self.prune_recorded(&mut recorded);
+        let mut results: Vec<RecordedResult> = recorded.values().cloned().collect();
+        results.sort_by_key(|result| result.created_at);
+        results
+    }
+
+    /// 所有保留中的任务，最早提交的在前
+    pub fn list(&self) -> Vec<QueuedJobInfo> {
+        let mut jobs = self.lock();
+        self.prune(&mut jobs);
+        let mut infos: Vec<QueuedJobInfo> = jobs.values().map(|job| job.info.clone()).collect();
+        infos.sort_by_key(|info| info.created_at);
+        infos
+    }
+
+    /// 任务的当前状态
+    pub fn status(&self, id: &str) -> Result<QueuedJobInfo> {
+        let mut jobs = self.lock();
+        self.prune(&mut jobs);
+        jobs.get(id)
+            .map(|job| job.info.clone())
+            .ok_or_else(|| unknown_job(id))
+    }
+
+    /// 任务的当前状态和结果，任务尚未结束时结果为 `None`
+    pub fn result(&self, id: &str) -> Result<(QueuedJobInfo, Option<QueuedJobOutcome>)> {
+        let mut jobs = self.lock();
+        self.prune(&mut jobs);
+        jobs.get(id)
+            .map(|job| (job.info.clone(), job.outcome.clone()))
+            .ok_or_else(|| unknown_job(id))
+    }
+
+    /// 取消排队中或运行中的任务，已结束的任务保持不变
+    pub fn cancel(&self, id: &str) -> Result<QueuedJobInfo> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(id).ok_or_else(|| unknown_job(id))?;
+        if !job.info.status.is_finished() {
+            if let Some(abort) = job.abort.take() {
+                abort.abort();
+            }
+            job.info.status = QueuedJobStatus::Cancelled;
+            job.info.finished_at = Some(Utc::now());
+            job.outcome = Some(QueuedJobOutcome::Cancelled);
+        }
+        Ok(job.info.clone())
+    }
+
+    fn update(&self, id: &str, update: impl FnOnce(&mut QueuedJob)) {
+        if let Some(job) = self.lock().get_mut(id) {
+            // 已取消的任务不再更新
+            if job.info.status != QueuedJobStatus::Cancelled {
+                update(job);
+            }
+        }
+    }
+
+    /// 删除超过保留时间或超出保留数量的已结束任务
+    fn prune(&self, jobs: &mut HashMap<String, QueuedJob>) {
+        if let Some(retention) = self.retention {
+            let now = Utc::now();
+            jobs.retain(|_, job| {
+                job.info.finished_at.is_none_or(|finished_at| {
+                    (now - finished_at).to_std().unwrap_or_default() < retention
+                })
+            });
+        }
+
+        let mut finished: Vec<(DateTime<Utc>, String)> = jobs
+            .values()
+            .filter_map(|job| Some((job.info.finished_at?, job.info.id.clone())))
+            .collect();
+        if finished.len() > self.max_retained {
+            finished.sort();
+            for (_, id) in &finished[..finished.len() - self.max_retained] {
+                jobs.remove(id);
+            }
+        }
+    }
+
+    /// 删除超过保留时间或超出保留数量的同步提取结果
+    fn prune_recorded(&self, recorded: &mut HashMap<String, RecordedResult>) {
+        if let Some(retention) = self.retention {
+            let now = Utc::now();
+            recorded.retain(|_, result| {
+                (now - result.created_at).to_std().unwrap_or_default() < retention
+            });
+        }
+
+        if recorded.len() > self.max_recorded {
+            let mut created: Vec<(DateTime<Utc>, String)> = recorded
+                .values()
+                .map(|result| (result.created_at, result.id.clone()))
+                .collect();
+            created.sort();
+            for (_, id) in &created[..created.len() - self.max_recorded] {
+                recorded.remove(id);
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, QueuedJob>> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_recorded(&self) -> MutexGuard<'_, HashMap<String, RecordedResult>> {
+        self.recorded.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn unknown_job(id: &str) -> SmartFetchError {
+    SmartFetchError::ValidationError(format!("任务不存在或已过期: {}", id))
+}
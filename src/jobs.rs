@@ -0,0 +1,249 @@
+use crate::config::JobsConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::Usage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 批量提取任务的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// 正在运行，或运行中被中断（超时、Ctrl-C）
+    Running,
+    /// 全部文件提取成功
+    Completed,
+    /// 运行结束，但有文件提取失败
+    Failed,
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JobStatus::Running => "未完成",
+            JobStatus::Completed => "已完成",
+            JobStatus::Failed => "有失败",
+        })
+    }
+}
+
+/// 任务中单个文件的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// 影响提取结果的任务选项，续跑时必须与原任务一致
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobOptions {
+    pub custom_prompt: Option<String>,
+    /// 实际使用的模板名称
+    pub template: String,
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    pub profile: Option<String>,
+    pub output_extension: String,
+}
+
+/// 任务清单中的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobFile {
+    /// 相对于输入目录的路径
+    pub input: PathBuf,
+    /// 提取时文件内容的 SHA-256，内容变化后续跑会重新提取
+    pub hash: Option<String>,
+    pub status: FileStatus,
+    /// 相对于输出目录的路径
+    pub output: Option<PathBuf>,
+    pub model: Option<String>,
+    pub usage: Option<Usage>,
+    pub error: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl JobFile {
+    pub fn pending(input: PathBuf) -> Self {
+        Self {
+            input,
+            hash: None,
+            status: FileStatus::Pending,
+            output: None,
+            model: None,
+            usage: None,
+            error: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// 批量提取任务清单，每处理完一个文件更新一次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobManifest {
+    pub id: String,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub input_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub options: JobOptions,
+    /// 按输入路径排序
+    pub files: Vec<JobFile>,
+}
+
+impl JobManifest {
+    pub fn new(input_dir: PathBuf, output_dir: PathBuf, options: JobOptions) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            status: JobStatus::Running,
+            created_at: now,
+            updated_at: now,
+            input_dir,
+            output_dir,
+            options,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn file(&self, input: &Path) -> Option<&JobFile> {
+        self.files.iter().find(|file| file.input == input)
+    }
+
+    pub fn file_mut(&mut self, input: &Path) -> Option<&mut JobFile> {
+        self.files.iter_mut().find(|file| file.input == input)
+    }
+
+    /// 将文件列表更新为本次要处理的文件，保留已有文件的记录
+    pub fn set_inputs(&mut self, inputs: &[PathBuf]) {
+        let mut previous: HashMap<PathBuf, JobFile> = self
+            .files
+            .drain(..)
+            .map(|file| (file.input.clone(), file))
+            .collect();
+        self.files = inputs
+            .iter()
+            .map(|input| {
+                previous
+                    .remove(input)
+                    .unwrap_or_else(|| JobFile::pending(input.clone()))
+            })
+            .collect();
+    }
+
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.files
+            .iter()
+            .filter(|file| file.status == status)
+            .count()
+    }
+
+    /// 根据各文件状态设置任务状态
+    pub fn finish(&mut self) {
+        self.status = if self.count(FileStatus::Succeeded) == self.files.len() {
+            JobStatus::Completed
+        } else {
+            JobStatus::Failed
+        };
+        self.updated_at = Utc::now();
+    }
+}
+
+/// 任务清单目录，每个任务保存为 `<jobs_dir>/<任务 ID>.json`
+#[derive(Debug, Clone)]
+pub struct JobStore {
+    dir: PathBuf,
+}
+
+impl JobStore {
+    pub fn new(config: &JobsConfig) -> Self {
+        Self {
+            dir: config
+                .jobs_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("jobs")),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 保存任务清单，先写临时文件再重命名，中断时不会留下写了一半的清单
+    pub fn save(&self, manifest: &JobManifest) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.json", manifest.id));
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(manifest)?)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// 按任务 ID 加载清单，也可以只提供 ID 的唯一前缀
+    pub fn load(&self, id: &str) -> Result<JobManifest> {
+        let mut matches: Vec<JobManifest> = self
+            .list()?
+            .into_iter()
+            .filter(|manifest| manifest.id.starts_with(id))
+            .collect();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(SmartFetchError::ValidationError(format!(
+                "任务不存在: {}",
+                id
+            ))),
+            n => Err(SmartFetchError::ValidationError(format!(
+                "任务 ID 前缀 {} 匹配到{}个任务，请提供更长的 ID",
+                id, n
+            ))),
+        }
+    }
+
+    /// 列出所有任务，最新的在前；无法解析的清单会被跳过
+    pub fn list(&self) -> Result<Vec<JobManifest>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut manifests = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read(&path)
+                .map_err(SmartFetchError::from)
+                .and_then(|bytes| Ok(serde_json::from_slice::<JobManifest>(&bytes)?))
+            {
+                Ok(manifest) => manifests.push(manifest),
+                Err(e) => tracing::warn!("跳过无法解析的任务清单 {:?}: {}", path, e),
+            }
+        }
+        manifests.sort_by_key(|manifest| Reverse(manifest.created_at));
+        Ok(manifests)
+    }
+
+    /// 输入、输出目录相同的最近一次任务
+    pub fn latest_for(&self, input_dir: &Path, output_dir: &Path) -> Result<Option<JobManifest>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|manifest| manifest.input_dir == input_dir && manifest.output_dir == output_dir))
+    }
+}
+
+/// 文件内容的 SHA-256 十六进制字符串
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -1,57 +1,170 @@
+pub mod batch;
+pub mod cache;
 pub mod cleaner;
 pub mod config;
 pub mod document;
 pub mod error;
+pub mod fetcher;
+pub mod front_matter;
+pub mod html;
+pub mod http_transport;
+pub mod job_queue;
+pub mod jobs;
 pub mod llm_client;
+pub mod loaders;
 pub mod mcp_server;
+pub mod progress;
 pub mod prompt_template;
+pub mod providers;
+pub mod reload;
+pub mod resources;
+pub mod structured;
+mod template_helpers;
+pub mod tokenizer;
 
+pub use batch::*;
+pub use cache::*;
 pub use cleaner::*;
 pub use config::*;
 pub use document::*;
 pub use error::*;
+pub use fetcher::*;
+pub use front_matter::*;
+pub use html::*;
+pub use http_transport::*;
+pub use job_queue::*;
+pub use jobs::*;
 pub use llm_client::*;
+pub use loaders::{DocumentLoader, LoadedContent, LoaderRegistry};
 pub use mcp_server::*;
+pub use progress::*;
 pub use prompt_template::*;
+pub use providers::*;
+pub use reload::*;
+pub use resources::*;
+pub use tokenizer::*;
 
-use std::path::PathBuf;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing_indicatif::indicatif_println;
 
 #[derive(Debug)]
 pub struct SmartFetchService {
     config: AppConfig,
     llm_client: LLMClient,
+    /// 按名称索引的模型配置客户端
+    profile_clients: HashMap<String, LLMClient>,
+    /// 模板头部声明了模型参数的模板所使用的客户端，按模板名称索引
+    template_clients: HashMap<String, LLMClient>,
     template_manager: TemplateManager,
+    url_fetcher: UrlFetcher,
+    loader_registry: Arc<LoaderRegistry>,
+    /// 未启用缓存时为 `None`
+    cache: Option<ResponseCache>,
+    tokenizers: TokenizerRegistry,
+    job_store: JobStore,
+    /// 后台提取任务队列，热重载时由新实例沿用
+    job_queue: Arc<JobQueue>,
 }
 
 impl SmartFetchService {
     pub fn new(config: AppConfig) -> Result<Self> {
         let llm_client = LLMClient::new(config.llm.clone())?;
-        let template_manager = TemplateManager::new(&config.templates_dir)?;
+        let profile_clients = config
+            .llm
+            .profile_names()
+            .into_iter()
+            .map(|name| {
+                let client = LLMClient::new(config.llm.profile(&name)?)?;
+                Ok((name, client))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        let tokenizers = TokenizerRegistry::new(config.get_tokenizer_config());
+        let template_manager = TemplateManager::new(&config.templates_dir)?
+            .with_token_counter(tokenizers.counter_for(&config.llm.model));
+        let template_clients = build_template_clients(&config, &template_manager)?;
+        let html_config = config.processing.html.clone().unwrap_or_default();
+        let url_fetcher = UrlFetcher::new(config.get_fetch_config())?
+            .with_html_converter(HtmlConverter::new(html_config));
+        let loader_registry = Arc::new(LoaderRegistry::with_builtin(&config.processing));
+        let cache_config = config.get_cache_config();
+        let cache = cache_config
+            .enabled
+            .unwrap_or(false)
+            .then(|| ResponseCache::new(&cache_config));
+        let job_store = JobStore::new(&config.get_jobs_config());
+        let job_queue = Arc::new(JobQueue::new(&config.get_jobs_config()));
 
         Ok(Self {
             config,
             llm_client,
+            profile_clients,
+            template_clients,
             template_manager,
+            url_fetcher,
+            loader_registry,
+            cache,
+            tokenizers,
+            job_store,
+            job_queue,
         })
     }
 
+    /// 沿用已有的后台任务队列，重新加载配置后排队和运行中的任务不受影响
+    pub fn with_job_queue(mut self, job_queue: Arc<JobQueue>) -> Self {
+        self.job_queue = job_queue;
+        self
+    }
+
+    /// 使用自定义的文档加载器注册表，以支持内置格式之外的文档
+    pub fn with_loader_registry(mut self, loader_registry: LoaderRegistry) -> Self {
+        self.loader_registry = Arc::new(loader_registry);
+        self
+    }
+
     #[tracing::instrument(level = "info", skip(self), name = "智能提取文档内容")]
     pub async fn extract_content(
         &self,
         document_path: &PathBuf,
         custom_prompt: Option<String>,
     ) -> Result<String> {
-        let document_processor = DocumentProcessor::new(self.config.processing.clone())?;
-        let document = document_processor.load_document(document_path).await?;
-        let template_name = self.config.default_template.as_deref().unwrap_or("default");
-        let prompt = self.template_manager.render_template(
-            template_name,
-            &document.content,
-            custom_prompt,
-        )?;
+        self.extract_content_with_progress(document_path, custom_prompt, None, None, None, None)
+            .await
+            .map(|output| output.content)
+    }
 
-        let response = self.llm_client.generate_response(&prompt).await?;
-        Ok(response)
+    /// `template` 为模板名称，为空时使用 `default_template`；`variables` 为模板头部声明的自定义变量；
+    /// `profile` 为模型配置名称，为空时使用模板头部声明的模型参数或默认配置；
+    /// 结果中记录实际应答的模型和本次提取的总用量
+    #[tracing::instrument(level = "info", skip(self, progress), name = "智能提取文档内容")]
+    pub async fn extract_content_with_progress(
+        &self,
+        document_path: &PathBuf,
+        custom_prompt: Option<String>,
+        template: Option<&str>,
+        variables: Option<HashMap<String, serde_json::Value>>,
+        profile: Option<&str>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<LlmOutput> {
+        let template = self.template_name(template)?;
+        let variables = self
+            .template_manager
+            .resolve_variables(template, variables.unwrap_or_default())?;
+        let llm_client = self.extraction_client(profile, template)?;
+        let prepared = self.prepare_document(llm_client, document_path).await?;
+        self.run_extraction(
+            llm_client,
+            prepared,
+            custom_prompt,
+            template,
+            variables,
+            progress,
+        )
+        .await
     }
 
     #[tracing::instrument(level = "info", skip(self, text), name = "智能提取文本内容")]
@@ -60,22 +173,966 @@ impl SmartFetchService {
         text: &str,
         custom_prompt: Option<String>,
     ) -> Result<String> {
-        // 使用文档处理器对文本进行预处理和清理
-        let document_processor = DocumentProcessor::new(self.config.processing.clone())?;
+        self.extract_from_text_with_progress(text, custom_prompt, None, None, None, None)
+            .await
+            .map(|output| output.content)
+    }
+
+    #[tracing::instrument(level = "info", skip(self, text, progress), name = "智能提取文本内容")]
+    pub async fn extract_from_text_with_progress(
+        &self,
+        text: &str,
+        custom_prompt: Option<String>,
+        template: Option<&str>,
+        variables: Option<HashMap<String, serde_json::Value>>,
+        profile: Option<&str>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<LlmOutput> {
+        let template = self.template_name(template)?;
+        let variables = self
+            .template_manager
+            .resolve_variables(template, variables.unwrap_or_default())?;
+        let llm_client = self.extraction_client(profile, template)?;
+        let prepared = self.prepare_text(llm_client, text)?;
+        self.run_extraction(
+            llm_client,
+            prepared,
+            custom_prompt,
+            template,
+            variables,
+            progress,
+        )
+        .await
+    }
 
-        // 预处理文本内容
-        let processed_text = document_processor.preprocess_content(text)?;
+    #[tracing::instrument(level = "info", skip(self), name = "智能提取网页内容")]
+    pub async fn extract_from_url(
+        &self,
+        url: &str,
+        custom_prompt: Option<String>,
+    ) -> Result<String> {
+        self.extract_from_url_with_progress(url, custom_prompt, None, None, None, None)
+            .await
+            .map(|output| output.content)
+    }
 
-        let template_name = self.config.default_template.as_deref().unwrap_or("default");
-        let prompt = self
+    #[tracing::instrument(level = "info", skip(self, progress), name = "智能提取网页内容")]
+    pub async fn extract_from_url_with_progress(
+        &self,
+        url: &str,
+        custom_prompt: Option<String>,
+        template: Option<&str>,
+        variables: Option<HashMap<String, serde_json::Value>>,
+        profile: Option<&str>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<LlmOutput> {
+        let template = self.template_name(template)?;
+        let variables = self
             .template_manager
-            .render_template(template_name, &processed_text, custom_prompt)?;
+            .resolve_variables(template, variables.unwrap_or_default())?;
+        let llm_client = self.extraction_client(profile, template)?;
+        let prepared = self.prepare_url(llm_client, url).await?;
+        self.run_extraction(
+            llm_client,
+            prepared,
+            custom_prompt,
+            template,
+            variables,
+            progress,
+        )
+        .await
+    }
+
+    /// 提交后台提取任务，立即返回任务 ID，通过 [`Self::job_queue`] 查询进度和结果
+    ///
+    /// 模板、模板变量或模型配置无效时直接返回错误，不创建任务
+    pub fn submit_extraction(
+        self: &Arc<Self>,
+        source: ExtractionSource,
+        custom_prompt: Option<String>,
+        template: Option<String>,
+        variables: Option<HashMap<String, serde_json::Value>>,
+        profile: Option<String>,
+    ) -> Result<String> {
+        let template_name = self.template_name(template.as_deref())?;
+        self.template_manager
+            .resolve_variables(template_name, variables.clone().unwrap_or_default())?;
+        self.extraction_client(profile.as_deref(), template_name)?;
+
+        let service = Arc::clone(self);
+        let description = source.to_string();
+        Ok(self.job_queue.submit(description, move |progress| async move {
+            let (template, profile) = (template.as_deref(), profile.as_deref());
+            let progress = Some(progress);
+            match &source {
+                ExtractionSource::File(path) => {
+                    service
+                        .extract_content_with_progress(
+                            path,
+                            custom_prompt,
+                            template,
+                            variables,
+                            profile,
+                            progress,
+                        )
+                        .await
+                }
+                ExtractionSource::Text(text) => {
+                    service
+                        .extract_from_text_with_progress(
+                            text,
+                            custom_prompt,
+                            template,
+                            variables,
+                            profile,
+                            progress,
+                        )
+                        .await
+                }
+                ExtractionSource::Url(url) => {
+                    service
+                        .extract_from_url_with_progress(
+                            url,
+                            custom_prompt,
+                            template,
+                            variables,
+                            profile,
+                            progress,
+                        )
+                        .await
+                }
+            }
+        }))
+    }
+
+    pub fn job_queue(&self) -> &Arc<JobQueue> {
+        &self.job_queue
+    }
+
+    /// 用文本渲染模板的对话消息，不调用模型
+    ///
+    /// 文本与 `extract_from_text` 一样经过预处理，但不分块，由调用方自行发送给模型
+    pub fn render_prompt(
+        &self,
+        template: &str,
+        text: &str,
+        custom_prompt: Option<String>,
+        variables: HashMap<String, serde_json::Value>,
+    ) -> Result<Vec<ChatMessage>> {
+        let template = self.template_name(Some(template))?;
+        let variables = self.template_manager.resolve_variables(template, variables)?;
+        let llm_client = self.extraction_client(None, template)?;
+        let prepared = self.prepare_text(llm_client, text)?;
+        let base = base_template_data(custom_prompt, prepared.metadata, variables);
+        self.template_manager
+            .render_messages(template, &template_data(&prepared.content, &base))
+    }
+
+    /// 按 JSON Schema 提取结构化数据
+    ///
+    /// 使用模型的结构化输出模式（如果可用），并对结果进行 Schema 校验，
+    /// 校验失败时携带错误信息重新请求，最多重试 `processing.structured.max_retries` 次
+    #[tracing::instrument(level = "info", skip(self, schema), name = "结构化提取内容")]
+    pub async fn extract_structured(
+        &self,
+        source: &ExtractionSource,
+        schema: &serde_json::Value,
+        custom_prompt: Option<String>,
+        profile: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        // 先校验 Schema，避免在无效 Schema 上浪费模型调用
+        structured::compile_schema(schema)?;
+        let llm_client = self.llm_client(profile)?;
+
+        let structured_config = self.config.processing.structured.clone().unwrap_or_default();
+        let template_name = structured_config.template.as_deref().unwrap_or("structured");
+        if !self.template_manager.template_exists(template_name) {
+            return Err(SmartFetchError::TemplateError(format!(
+                "模板不存在: {}",
+                template_name
+            )));
+        }
+
+        let prepared = match source {
+            ExtractionSource::File(path) => self.prepare_document(llm_client, path).await?,
+            ExtractionSource::Text(text) => self.prepare_text(llm_client, text)?,
+            ExtractionSource::Url(url) => self.prepare_url(llm_client, url).await?,
+        };
+
+        let chunks = self.split_chunks(&prepared)?;
+        let mut base = base_template_data(custom_prompt, prepared.metadata, HashMap::new());
+        base.metadata.insert(
+            "schema".to_string(),
+            serde_json::to_string_pretty(schema)?,
+        );
+        let default_template = self.template_name(None)?;
+        let chunks = self.fit_to_context(
+            llm_client,
+            &prepared.processor,
+            chunks,
+            template_name,
+            default_template,
+            &base,
+        )?;
+        let data = if chunks.len() <= 1 {
+            template_data(&prepared.content, &base)
+        } else {
+            // 长文档先逐块提取要点，再从合并后的结果中填写结构化字段
+            let partials: Vec<String> = self
+                .map_chunks(llm_client, chunks, default_template, &base, &None)
+                .await?
+                .into_iter()
+                .map(|partial| partial.content)
+                .collect();
+            let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+            let reduce_template = map_reduce.reduce_template.as_deref().unwrap_or("reduce");
+            self.reduce_partials(llm_client, partials, template_name, reduce_template, &base)
+                .await?
+                .0
+        };
+
+        let messages = self.template_manager.render_messages(template_name, &data)?;
+        self.check_context_window(llm_client, &messages)?;
+        structured::generate_validated_json(
+            llm_client,
+            messages,
+            schema,
+            structured_config.max_retries.unwrap_or(2),
+        )
+        .await
+    }
+
+    /// 文档处理器，按 token 计算分块大小时使用实际调用模型的 tokenizer
+    fn document_processor(&self, llm_client: &LLMClient) -> Result<DocumentProcessor> {
+        Ok(DocumentProcessor::new(self.config.processing.clone())?
+            .with_loader_registry(self.loader_registry.clone())
+            .with_token_counter(self.tokenizers.counter_for(&llm_client.get_config().model)))
+    }
+
+    /// 读取并预处理本地文档
+    async fn prepare_document(
+        &self,
+        llm_client: &LLMClient,
+        document_path: &Path,
+    ) -> Result<PreparedContent> {
+        let processor = self.document_processor(llm_client)?;
+        let document = processor.load_document(document_path).await?;
+
+        Ok(PreparedContent {
+            metadata: document.metadata.template_metadata(),
+            content: document.content,
+            processor,
+        })
+    }
+
+    /// 使用文档处理器对文本进行预处理和清理
+    fn prepare_text(&self, llm_client: &LLMClient, text: &str) -> Result<PreparedContent> {
+        let processor = self.document_processor(llm_client)?;
+        let content = processor.preprocess_content(text)?;
+
+        Ok(PreparedContent {
+            processor,
+            content,
+            metadata: HashMap::new(),
+        })
+    }
+
+    /// 抓取网页并转换为预处理后的文本
+    async fn prepare_url(&self, llm_client: &LLMClient, url: &str) -> Result<PreparedContent> {
+        let page = self.url_fetcher.fetch(url).await?;
+        if page.content.trim().is_empty() {
+            return Err(SmartFetchError::DocumentError(format!(
+                "网页没有可提取的文本内容: {}",
+                url
+            )));
+        }
+
+        let mut metadata = HashMap::new();
+        if let Some(title) = &page.title {
+            metadata.insert("title".to_string(), title.clone());
+        }
+        metadata.insert("source_url".to_string(), page.final_url.clone());
+
+        let content = match &page.title {
+            Some(title) if !page.content.starts_with(title.as_str()) => {
+                format!("# {}\n\n{}", title, page.content)
+            }
+            _ => page.content,
+        };
+
+        let mut prepared = self.prepare_text(llm_client, &content)?;
+        prepared.metadata = metadata;
+        Ok(prepared)
+    }
+
+    /// 按配置切分内容；未启用 map-reduce 时整体作为一个块
+    fn split_chunks(&self, prepared: &PreparedContent) -> Result<Vec<String>> {
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        if map_reduce.enable_map_reduce.unwrap_or(true) {
+            prepared.processor.chunk_content(&prepared.content)
+        } else {
+            Ok(vec![prepared.content.clone()])
+        }
+    }
+
+    /// 执行提取：内容超过分块大小时按 map-reduce 方式逐块提取再合并
+    async fn run_extraction(
+        &self,
+        llm_client: &LLMClient,
+        prepared: PreparedContent,
+        custom_prompt: Option<String>,
+        template_name: &str,
+        variables: HashMap<String, serde_json::Value>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<LlmOutput> {
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        let chunks = self.split_chunks(&prepared)?;
+        let PreparedContent {
+            processor,
+            content,
+            metadata,
+        } = prepared;
+        let base = base_template_data(custom_prompt, metadata, variables);
+        let chunks = self.fit_to_context(
+            llm_client,
+            &processor,
+            chunks,
+            template_name,
+            template_name,
+            &base,
+        )?;
+
+        if chunks.len() <= 1 {
+            let messages = self.template_manager.render_messages(template_name, &template_data(&content, &base))?;
+            let response = self
+                .generate_final(llm_client, messages, &progress, 0, 1)
+                .await?;
+            report_progress(&progress, ExtractionStage::Completed, 1, 1, "提取完成");
+            return Ok(response);
+        }
+
+        let reduce_template = map_reduce.reduce_template.as_deref().unwrap_or("reduce");
+        if !self.template_manager.template_exists(reduce_template) {
+            return Err(SmartFetchError::TemplateError(format!(
+                "模板不存在: {}",
+                reduce_template
+            )));
+        }
+
+        let chunk_count = chunks.len();
+        // 每个分块一步，外加一次合并
+        let total_steps = chunk_count + 1;
+        let partials = self
+            .map_chunks(llm_client, chunks, template_name, &base, &progress)
+            .await?;
+
+        report_progress(
+            &progress,
+            ExtractionStage::Reduce,
+            chunk_count,
+            total_steps,
+            "正在合并分块提取结果",
+        );
+
+        let contents: Vec<String> = partials.iter().map(|partial| partial.content.clone()).collect();
+        let (data, reduced) = self
+            .reduce_partials(llm_client, contents, reduce_template, reduce_template, &base)
+            .await?;
+        let messages = self.template_manager.render_messages(reduce_template, &data)?;
+        let mut response = self
+            .generate_final(llm_client, messages, &progress, chunk_count, total_steps)
+            .await?;
+        // 用量按所有分块和合并调用累计
+        let calls: Vec<&LlmOutput> = partials.iter().chain(&reduced).collect();
+        response.usage = total_usage(calls.iter().copied().chain([&response]));
+        response.cached = response.cached && calls.iter().all(|output| output.cached);
+
+        indicatif_println!("✅ 已合并{}个分块的提取结果", chunk_count);
+        report_progress(&progress, ExtractionStage::Completed, total_steps, total_steps, "提取完成");
 
-        let response = self.llm_client.generate_response(&prompt).await?;
         Ok(response)
     }
 
+    /// 生成最终结果；命中响应缓存时，缓存内容作为一次 `Generating` 进度事件上报
+    async fn generate_final(
+        &self,
+        llm_client: &LLMClient,
+        messages: Vec<ChatMessage>,
+        progress: &Option<ProgressCallback>,
+        completed: usize,
+        total: usize,
+    ) -> Result<LlmOutput> {
+        self.check_context_window(llm_client, &messages)?;
+        let stream = llm_client.get_config().stream.unwrap_or(true);
+        let output = if stream {
+            self.generate_stream(llm_client, messages, progress, completed, total)
+                .await?
+        } else {
+            self.cached_chat(llm_client, messages, |messages| llm_client.chat(messages, None))
+                .await?
+        };
+
+        if output.cached {
+            if let Some(callback) = progress {
+                let generated_chars = output.content.chars().count();
+                callback(&ExtractionProgress {
+                    stage: ExtractionStage::Generating,
+                    completed,
+                    total,
+                    message: format!("命中响应缓存，已读取{}字符", generated_chars),
+                    delta: stream.then(|| output.content.clone()),
+                    generated_chars,
+                });
+            }
+        }
+        Ok(output)
+    }
+
+    /// 流式生成最终结果，收到的每段文本都作为 `Generating` 进度事件上报
+    async fn generate_stream(
+        &self,
+        llm_client: &LLMClient,
+        messages: Vec<ChatMessage>,
+        progress: &Option<ProgressCallback>,
+        completed: usize,
+        total: usize,
+    ) -> Result<LlmOutput> {
+        let generated = AtomicUsize::new(0);
+        let on_delta = |delta: &str| {
+            let chars = delta.chars().count();
+            let generated_chars = generated.fetch_add(chars, Ordering::SeqCst) + chars;
+            if let Some(callback) = progress {
+                callback(&ExtractionProgress {
+                    stage: ExtractionStage::Generating,
+                    completed,
+                    total,
+                    message: format!("正在生成结果，已接收{}字符", generated_chars),
+                    delta: Some(delta.to_string()),
+                    generated_chars,
+                });
+            }
+        };
+        self.cached_chat(llm_client, messages, |messages| {
+            llm_client.chat_stream(messages, &on_delta)
+        })
+        .await
+    }
+
+    /// 启用缓存时先查询响应缓存，未命中再调用模型并写入缓存
+    async fn cached_chat<F, Fut>(
+        &self,
+        llm_client: &LLMClient,
+        messages: Vec<ChatMessage>,
+        call: F,
+    ) -> Result<LlmOutput>
+    where
+        F: FnOnce(Vec<ChatMessage>) -> Fut,
+        Fut: Future<Output = Result<LlmOutput>>,
+    {
+        let Some(cache) = &self.cache else {
+            return call(messages).await;
+        };
+
+        let key = ResponseCache::key(llm_client.get_config(), &messages, None);
+        if let Some(output) = cache.get(&key) {
+            indicatif_println!("♻️ 命中响应缓存 (模型: {})", output.model);
+            return Ok(output);
+        }
+
+        let output = call(messages).await?;
+        if let Err(e) = cache.put(&key, &output) {
+            tracing::warn!("写入响应缓存失败: {}", e);
+        }
+        Ok(output)
+    }
+
+    /// map 阶段：使用 map 模板（未配置时使用本次提取的模板）并发提取每个分块，按原顺序返回各块结果
+    async fn map_chunks(
+        &self,
+        llm_client: &LLMClient,
+        chunks: Vec<String>,
+        template_name: &str,
+        base: &TemplateData,
+        progress: &Option<ProgressCallback>,
+    ) -> Result<Vec<LlmOutput>> {
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        let map_template = map_reduce.map_template.as_deref().unwrap_or(template_name);
+        if !self.template_manager.template_exists(map_template) {
+            return Err(SmartFetchError::TemplateError(format!(
+                "模板不存在: {}",
+                map_template
+            )));
+        }
+
+        let chunk_count = chunks.len();
+        let total_steps = chunk_count + 1;
+        let completed = AtomicUsize::new(0);
+        let concurrency = map_reduce.max_concurrent_chunks.unwrap_or(1).max(1);
+
+        indicatif_println!("🧩 文档过长，分为{}个块进行提取", chunk_count);
+
+        stream::iter(chunks.into_iter().enumerate())
+            .map(|(index, chunk)| {
+                let completed = &completed;
+                async move {
+                    let data = chunk_template_data(&chunk, base, index, chunk_count);
+                    let messages = self.template_manager.render_messages(map_template, &data)?;
+                    self.check_context_window(llm_client, &messages)?;
+                    let partial = self
+                        .cached_chat(llm_client, messages, |messages| {
+                            llm_client.chat(messages, None)
+                        })
+                        .await?;
+
+                    let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                    let source = if partial.cached { "（缓存）" } else { "" };
+                    indicatif_println!("✅ 分块 {}/{} 提取完成{}", index + 1, chunk_count, source);
+                    report_progress(
+                        progress,
+                        ExtractionStage::Map,
+                        done,
+                        total_steps,
+                        &format!("分块 {}/{} 提取完成{}", index + 1, chunk_count, source),
+                    );
+                    Ok::<_, SmartFetchError>(partial)
+                }
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// 客户端的 token 预算，任一候选模型无法确定上下文窗口时为 `None`
+    ///
+    /// 配置了备用模型时取可用空间最大的候选模型：主模型放不下的请求会因超出上下文长度改用备用模型，
+    /// 所有候选模型都放不下时才在发送前报错
+    fn token_budget(&self, llm_client: &LLMClient) -> Option<TokenBudget> {
+        std::iter::once(llm_client)
+            .chain(llm_client.fallbacks())
+            .map(|client| self.model_budget(client))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .reduce(|best, budget| {
+                if budget.available() > best.available() {
+                    budget
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// 单个模型的 token 预算，无法确定上下文窗口时为 `None`
+    fn model_budget(&self, llm_client: &LLMClient) -> Option<TokenBudget> {
+        let config = llm_client.get_config();
+        let context_window = config
+            .context_window
+            .or_else(|| default_context_window(&config.model))?;
+        Some(TokenBudget {
+            model: config.model.clone(),
+            counter: self.tokenizers.counter_for(&config.model),
+            context_window,
+            max_tokens: config.max_tokens.unwrap_or(0) as usize,
+        })
+    }
+
+    /// 发送前检查提示词加最大输出 token 数是否超出模型的上下文窗口
+    fn check_context_window(&self, llm_client: &LLMClient, messages: &[ChatMessage]) -> Result<()> {
+        match self.token_budget(llm_client) {
+            Some(budget) => budget.check(budget.counter.count_messages(messages)),
+            None => Ok(()),
+        }
+    }
+
+    /// 自动分块时使用的 token 预算；`tokenizer.on_overflow = "error"`、未启用 map-reduce
+    /// 或无法确定上下文窗口时为 `None`
+    fn auto_chunk_budget(&self, llm_client: &LLMClient) -> Option<TokenBudget> {
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        let tokenizer = self.config.get_tokenizer_config();
+        let auto_chunk = tokenizer.on_overflow.as_deref().unwrap_or("chunk") == "chunk"
+            && map_reduce.enable_map_reduce.unwrap_or(true);
+        self.token_budget(llm_client).filter(|_| auto_chunk)
+    }
+
+    /// 按上下文窗口调整分块
+    ///
+    /// 整篇内容使用 `template_name` 放不下时改为分块提取，单个分块使用 map 模板（未配置时为
+    /// `map_fallback`）仍放不下时按超出比例继续细分。`tokenizer.on_overflow = "error"` 或未启用
+    /// map-reduce 时不调整，超出的请求在发送前返回 `ContextWindowExceeded`
+    fn fit_to_context(
+        &self,
+        llm_client: &LLMClient,
+        processor: &DocumentProcessor,
+        chunks: Vec<String>,
+        template_name: &str,
+        map_fallback: &str,
+        base: &TemplateData,
+    ) -> Result<Vec<String>> {
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        let Some(budget) = self.auto_chunk_budget(llm_client) else {
+            return Ok(chunks);
+        };
+
+        if let [content] = chunks.as_slice() {
+            let messages = self
+                .template_manager
+                .render_messages(template_name, &template_data(content, base))?;
+            if budget.check(budget.counter.count_messages(&messages)).is_ok() {
+                return Ok(chunks);
+            }
+        }
+
+        let map_template = map_reduce.map_template.as_deref().unwrap_or(map_fallback);
+        let mut pending = VecDeque::from(chunks);
+        let mut fitted = Vec::new();
+        while let Some(chunk) = pending.pop_front() {
+            let data = chunk_template_data(&chunk, base, fitted.len(), fitted.len() + pending.len() + 1);
+            let messages = self.template_manager.render_messages(map_template, &data)?;
+            let prompt_tokens = budget.counter.count_messages(&messages);
+            if budget.check(prompt_tokens).is_ok() {
+                fitted.push(chunk);
+                continue;
+            }
+
+            // 模板本身占用的 token 不随分块变化，按剩余空间与内容 token 数的比例缩小分块，并留出余量
+            let content_tokens = budget.counter.count_tokens(&chunk).max(1);
+            let available = budget
+                .context_window
+                .saturating_sub(budget.max_tokens + prompt_tokens.saturating_sub(content_tokens));
+            let chunk_size = chunk.len() * available / content_tokens * 9 / 10;
+            if chunk_size == 0 {
+                return Err(budget.exceeded(prompt_tokens));
+            }
+            let pieces = processor.chunk_content_with_size(&chunk, chunk_size)?;
+            if pieces.len() <= 1 {
+                return Err(budget.exceeded(prompt_tokens));
+            }
+            for piece in pieces.into_iter().rev() {
+                pending.push_front(piece);
+            }
+        }
+
+        if fitted.len() > 1 {
+            indicatif_println!(
+                "📏 内容超出 {} 的上下文窗口（{} tokens），分为{}个块提取",
+                budget.model,
+                budget.context_window,
+                fitted.len()
+            );
+        }
+        Ok(fitted)
+    }
+
+    /// 合并分块结果，返回用于渲染 `final_template` 的模板数据和分批合并时的模型输出
+    ///
+    /// 全部结果合并后的提示词超出上下文窗口时，按顺序把结果分成使用 `batch_template` 放得下的
+    /// 若干批，每批合并为一个中间结果，逐轮重复直到一次放得下；只有一个结果的批次原样保留。
+    /// 未启用自动分块时不调整，与 [`Self::fit_to_context`] 一致
+    async fn reduce_partials(
+        &self,
+        llm_client: &LLMClient,
+        mut partials: Vec<String>,
+        final_template: &str,
+        batch_template: &str,
+        base: &TemplateData,
+    ) -> Result<(TemplateData, Vec<LlmOutput>)> {
+        let mut reduced = Vec::new();
+        let Some(budget) = self.auto_chunk_budget(llm_client) else {
+            return Ok((reduce_template_data(&partials, base), reduced));
+        };
+        let map_reduce = self.config.processing.map_reduce.clone().unwrap_or_default();
+        let concurrency = map_reduce.max_concurrent_chunks.unwrap_or(1).max(1);
+
+        loop {
+            let data = reduce_template_data(&partials, base);
+            let messages = self.template_manager.render_messages(final_template, &data)?;
+            let prompt_tokens = budget.counter.count_messages(&messages);
+            if budget.check(prompt_tokens).is_ok() {
+                return Ok((data, reduced));
+            }
+
+            let batches = self.reduce_batches(&budget, &partials, batch_template, base)?;
+            // 没有两个结果能放进同一批时无法继续缩小
+            if batches.len() == partials.len() {
+                return Err(budget.exceeded(prompt_tokens));
+            }
+            indicatif_println!(
+                "📏 合并{}个分块结果超出 {} 的上下文窗口，分为{}批合并",
+                partials.len(),
+                budget.model,
+                batches.len()
+            );
+
+            let merged: Vec<(String, Option<LlmOutput>)> = stream::iter(batches)
+                .map(|batch| async move {
+                    if let [partial] = batch.as_slice() {
+                        return Ok((partial.clone(), None));
+                    }
+                    let data = reduce_template_data(&batch, base);
+                    let messages = self.template_manager.render_messages(batch_template, &data)?;
+                    let output = self
+                        .cached_chat(llm_client, messages, |messages| {
+                            llm_client.chat(messages, None)
+                        })
+                        .await?;
+                    Ok::<_, SmartFetchError>((output.content.clone(), Some(output)))
+                })
+                .buffered(concurrency)
+                .try_collect()
+                .await?;
+            partials = Vec::with_capacity(merged.len());
+            for (content, output) in merged {
+                partials.push(content);
+                reduced.extend(output);
+            }
+        }
+    }
+
+    /// 按顺序把分块结果分批，每批使用 `batch_template` 合并时都不超出上下文窗口
+    fn reduce_batches(
+        &self,
+        budget: &TokenBudget,
+        partials: &[String],
+        batch_template: &str,
+        base: &TemplateData,
+    ) -> Result<Vec<Vec<String>>> {
+        let mut batches = Vec::new();
+        let mut current: Vec<String> = Vec::new();
+        for partial in partials {
+            let mut candidate = current.clone();
+            candidate.push(partial.clone());
+            let messages = self
+                .template_manager
+                .render_messages(batch_template, &reduce_template_data(&candidate, base))?;
+            let prompt_tokens = budget.counter.count_messages(&messages);
+            if current.is_empty() || budget.check(prompt_tokens).is_ok() {
+                current = candidate;
+            } else {
+                batches.push(std::mem::replace(&mut current, vec![partial.clone()]));
+            }
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+        Ok(batches)
+    }
+
+    /// 按名称选择模型配置对应的客户端，未指定时使用 `llm.default_profile`，再退回 `[llm]` 基础配置
+    pub fn llm_client(&self, profile: Option<&str>) -> Result<&LLMClient> {
+        let Some(name) = profile.or(self.config.llm.default_profile.as_deref()) else {
+            return Ok(&self.llm_client);
+        };
+        self.profile_clients
+            .get(name)
+            .ok_or_else(|| self.config.llm.unknown_profile(name))
+    }
+
+    /// 选择本次提取的客户端：显式指定的模型配置优先，其次是模板头部声明的模型参数，最后是默认配置
+    fn extraction_client(&self, profile: Option<&str>, template_name: &str) -> Result<&LLMClient> {
+        match self.template_clients.get(template_name) {
+            Some(client) if profile.is_none() => Ok(client),
+            _ => self.llm_client(profile),
+        }
+    }
+
+    /// 解析本次提取使用的模板，未指定时使用 `default_template`
+    fn template_name<'a>(&'a self, template: Option<&'a str>) -> Result<&'a str> {
+        let name = template
+            .or(self.config.default_template.as_deref())
+            .unwrap_or("default");
+        self.template_manager.ensure_template(name)?;
+        Ok(name)
+    }
+
     pub fn config(&self) -> &AppConfig {
         &self.config
     }
+
+    pub fn template_manager(&self) -> &TemplateManager {
+        &self.template_manager
+    }
+
+    pub fn loader_registry(&self) -> &LoaderRegistry {
+        &self.loader_registry
+    }
+
+    /// 批处理和资源目录接受的文件扩展名，`processing.supported_formats` 为空时为加载器注册表支持的所有格式
+    pub fn supported_formats(&self) -> Vec<String> {
+        if self.config.processing.supported_formats.is_empty() {
+            self.loader_registry.supported_formats()
+        } else {
+            self.config.processing.supported_formats.clone()
+        }
+    }
+}
+
+/// 预处理后待提取的内容
+struct PreparedContent {
+    processor: DocumentProcessor,
+    content: String,
+    metadata: HashMap<String, String>,
+}
+
+/// 一个模型的 token 预算
+struct TokenBudget {
+    model: String,
+    counter: Arc<dyn TokenCounter>,
+    context_window: usize,
+    max_tokens: usize,
+}
+
+impl TokenBudget {
+    /// 扣除最大输出 token 数后留给提示词的空间
+    fn available(&self) -> usize {
+        self.context_window.saturating_sub(self.max_tokens)
+    }
+
+    fn check(&self, prompt_tokens: usize) -> Result<()> {
+        if prompt_tokens + self.max_tokens > self.context_window {
+            return Err(self.exceeded(prompt_tokens));
+        }
+        Ok(())
+    }
+
+    fn exceeded(&self, prompt_tokens: usize) -> SmartFetchError {
+        SmartFetchError::ContextWindowExceeded {
+            model: self.model.clone(),
+            prompt_tokens,
+            max_tokens: self.max_tokens,
+            context_window: self.context_window,
+        }
+    }
+}
+
+/// 结构化提取和后台提取任务的内容来源
+#[derive(Debug, Clone)]
+pub enum ExtractionSource {
+    File(PathBuf),
+    Text(String),
+    Url(String),
+}
+
+impl std::fmt::Display for ExtractionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractionSource::File(path) => write!(f, "文件 {}", path.display()),
+            ExtractionSource::Text(text) => write!(f, "文本（{} 个字符）", text.chars().count()),
+            ExtractionSource::Url(url) => write!(f, "网页 {}", url),
+        }
+    }
+}
+
+fn merge_partials(partials: &[String]) -> String {
+    let chunk_count = partials.len();
+    partials
+        .iter()
+        .enumerate()
+        .map(|(index, partial)| format!("## 分块 {}/{}\n\n{}", index + 1, chunk_count, partial))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 累计多次模型调用的用量，所有调用都没有返回用量时为 `None`
+fn total_usage<'a>(outputs: impl Iterator<Item = &'a LlmOutput>) -> Option<Usage> {
+    outputs
+        .filter_map(|output| output.usage.as_ref())
+        .fold(None, |total: Option<Usage>, usage| {
+            let mut total = total.unwrap_or_default();
+            total.add(usage);
+            Some(total)
+        })
+}
+
+/// 为声明了模型参数的模板创建客户端，在默认模型配置的基础上覆盖模型、温度和最大 token 数
+fn build_template_clients(
+    config: &AppConfig,
+    template_manager: &TemplateManager,
+) -> Result<HashMap<String, LLMClient>> {
+    let mut clients = HashMap::new();
+    for name in template_manager.get_available_templates() {
+        let Some(front_matter) = template_manager
+            .front_matter(&name)
+            .filter(|front_matter| front_matter.has_model_overrides())
+        else {
+            continue;
+        };
+
+        let mut llm_config = match config.llm.default_profile.as_deref() {
+            Some(profile) => config.llm.profile(profile)?,
+            None => config.llm.clone(),
+        };
+        if let Some(model) = &front_matter.model {
+            llm_config.model = model.clone();
+        }
+        if front_matter.temperature.is_some() {
+            llm_config.temperature = front_matter.temperature;
+        }
+        if front_matter.max_tokens.is_some() {
+            llm_config.max_tokens = front_matter.max_tokens;
+        }
+        clients.insert(name, LLMClient::new(llm_config)?);
+    }
+    Ok(clients)
+}
+
+/// 同一次提取中各次模板渲染共用的数据（自定义提示词、文档元数据和模板变量），内容为空
+fn base_template_data(
+    custom_prompt: Option<String>,
+    metadata: HashMap<String, String>,
+    variables: HashMap<String, serde_json::Value>,
+) -> TemplateData {
+    TemplateData {
+        content: String::new(),
+        custom_prompt,
+        metadata,
+        variables,
+    }
+}
+
+/// 以共用数据为基础，为指定内容构建模板数据
+fn template_data(content: &str, base: &TemplateData) -> TemplateData {
+    let mut data = TemplateManager::build_template_data(content, base.custom_prompt.clone());
+    data.metadata.extend(base.metadata.clone());
+    data.variables = base.variables.clone();
+    data
+}
+
+/// 合并分块结果的模板数据，`chunk_count` 为合并的结果数
+fn reduce_template_data(partials: &[String], base: &TemplateData) -> TemplateData {
+    let mut data = template_data(&merge_partials(partials), base);
+    data.metadata
+        .insert("chunk_count".to_string(), partials.len().to_string());
+    data
+}
+
+fn chunk_template_data(
+    content: &str,
+    base: &TemplateData,
+    chunk_index: usize,
+    chunk_count: usize,
+) -> TemplateData {
+    let mut data = template_data(content, base);
+    data.metadata
+        .insert("chunk_index".to_string(), (chunk_index + 1).to_string());
+    data.metadata
+        .insert("chunk_count".to_string(), chunk_count.to_string());
+    data
+}
+
+fn report_progress(
+    progress: &Option<ProgressCallback>,
+    stage: ExtractionStage,
+    completed: usize,
+    total: usize,
+    message: &str,
+) {
+    if let Some(callback) = progress {
+        callback(&ExtractionProgress {
+            stage,
+            completed,
+            total,
+            message: message.to_string(),
+            delta: None,
+            generated_chars: 0,
+        });
+    }
 }
@@ -1,53 +1,80 @@
-use crate::config::LLMConfig;
+use crate::config::{LLMConfig, RetryConfig};
 use crate::error::{Result, SmartFetchError};
+use crate::providers::{create_provider, ChatRequest, ChatResponse, LlmProvider};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
 use std::time::Duration;
 use tracing_indicatif::indicatif_println;
 
-#[derive(Debug, Serialize)]
-pub struct ChatCompletionRequest {
-    pub model: String,
-    pub messages: Vec<ChatMessage>,
-    pub max_tokens: Option<u32>,
-    pub temperature: Option<f64>,
-    pub stream: Option<bool>,
+/// OpenAI 兼容接口的 `response_format` 参数
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// 仅要求输出合法 JSON
+    JsonObject,
+    /// 要求输出符合给定 JSON Schema 的 JSON
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    pub strict: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: String,
     pub content: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ChatCompletionResponse {
-    pub id: String,
-    pub object: String,
-    pub created: u64,
-    pub model: String,
-    pub choices: Vec<Choice>,
-    pub usage: Option<Usage>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct Choice {
-    pub index: u32,
-    pub message: ChatMessage,
-    pub finish_reason: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
 }
 
+impl Usage {
+    /// 合并流式响应中分多次给出的用量（如 Anthropic 先给输入、后给输出 token 数）
+    pub fn merge(&mut self, other: &Usage) {
+        self.prompt_tokens = self.prompt_tokens.max(other.prompt_tokens);
+        self.completion_tokens = self.completion_tokens.max(other.completion_tokens);
+        self.total_tokens = other
+            .total_tokens
+            .max(self.prompt_tokens + self.completion_tokens);
+    }
+
+    /// 累加另一次调用的用量
+    pub fn add(&mut self, other: &Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// 一次模型调用的结果
+#[derive(Debug, Clone)]
+pub struct LlmOutput {
+    pub content: String,
+    /// 实际给出结果的模型，发生降级时为备用模型
+    pub model: String,
+    pub usage: Option<Usage>,
+    /// 是否来自响应缓存
+    pub cached: bool,
+}
+
 #[derive(Debug)]
 pub struct LLMClient {
     config: LLMConfig,
     http_client: reqwest::Client,
+    provider: Box<dyn LlmProvider>,
+    /// 按 `llm.fallback` 顺序尝试的备用模型
+    fallbacks: Vec<LLMClient>,
 }
 
 impl LLMClient {
@@ -77,39 +104,111 @@ impl LLMClient {
             .build()
             .map_err(|e| SmartFetchError::NetworkError(format!("创建HTTP客户端失败: {}", e)))?;
 
+        let provider = create_provider(&config)?;
+
+        // 备用模型自身不再降级，避免模型配置之间循环引用
+        let fallbacks = config
+            .fallback
+            .iter()
+            .flatten()
+            .map(|name| {
+                let mut fallback_config = config.profile(name)?;
+                fallback_config.fallback = None;
+                LLMClient::new(fallback_config)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         Ok(Self {
             config,
             http_client,
+            provider,
+            fallbacks,
         })
     }
 
     #[tracing::instrument(level = "info", skip(self, prompt), name = "调用LLM API")]
     pub async fn generate_response(&self, prompt: &str) -> Result<String> {
-        let request = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
-            stream: Some(false),
-        };
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        }];
+        self.generate_chat(messages, None).await
+    }
 
-        // 直接发送请求，不显示进度条（由调用者控制）
-        let response = self.send_request(request).await?;
+    /// 发送多轮对话，可指定输出格式（用于结构化提取）
+    #[tracing::instrument(level = "info", skip(self, messages, response_format), name = "调用LLM API")]
+    pub async fn generate_chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        response_format: Option<ResponseFormat>,
+    ) -> Result<String> {
+        self.chat(messages, response_format)
+            .await
+            .map(|output| output.content)
+    }
 
-        if response.choices.is_empty() {
-            return Err(SmartFetchError::LlmApiError(
-                "API返回了空的选择列表".to_string(),
-            ));
+    /// 发送多轮对话，返回结果及实际应答的模型
+    ///
+    /// 主模型不可用、过载或超出上下文长度时，依次改用 `llm.fallback` 中的备用模型
+    pub async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        response_format: Option<ResponseFormat>,
+    ) -> Result<LlmOutput> {
+        self.with_fallback(|client| client.single_chat(messages.clone(), response_format.clone()))
+            .await
+    }
+
+    /// 依次尝试主模型和备用模型，只有可通过换模型解决的错误才会切换
+    async fn with_fallback<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+    where
+        F: Fn(&'a LLMClient) -> Fut,
+        Fut: Future<Output = std::result::Result<T, AttemptError>>,
+    {
+        let mut client = self;
+        let mut fallbacks = self.fallbacks.iter();
+        loop {
+            let failure = match call(client).await {
+                Ok(output) => {
+                    if !std::ptr::eq(client, self) {
+                        indicatif_println!("✅ 已由备用模型 {} 完成请求", client.config.model);
+                    }
+                    return Ok(output);
+                }
+                Err(failure) => failure,
+            };
+
+            match fallbacks.next() {
+                Some(next) if failure.kind.triggers_fallback() => {
+                    indicatif_println!(
+                        "⚠️ 模型 {} {}: {}，改用备用模型 {}",
+                        client.config.model,
+                        failure.kind,
+                        failure.error,
+                        next.config.model
+                    );
+                    client = next;
+                }
+                _ => return Err(failure.error),
+            }
         }
+    }
+
+    /// 使用当前模型发送一次对话（含重试），不进行降级
+    async fn single_chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        response_format: Option<ResponseFormat>,
+    ) -> std::result::Result<LlmOutput, AttemptError> {
+        let request = self.chat_request(messages, false, response_format);
+
+        // 直接发送请求，不显示进度条（由调用者控制）
+        let response = self.send_with_retry(&request).await?;
 
-        let choice = &response.choices[0];
-        if choice.message.content.is_empty() {
-            return Err(SmartFetchError::LlmApiError(
+        if response.content.is_empty() {
+            return Err(AttemptError::fatal(SmartFetchError::LlmApiError(
                 "API返回了空的内容".to_string(),
-            ));
+            )));
         }
 
         // 添加LLM API调用完成提示
@@ -121,45 +220,259 @@ impl LLMClient {
             indicatif_println!("✅ LLM API调用成功");
         }
 
-        Ok(choice.message.content.clone())
+        Ok(LlmOutput {
+            content: response.content,
+            model: self.config.model.clone(),
+            usage: response.usage,
+            cached: false,
+        })
     }
 
+    /// 按当前配置构造与提供商无关的请求
+    fn chat_request(
+        &self,
+        messages: Vec<ChatMessage>,
+        stream: bool,
+        response_format: Option<ResponseFormat>,
+    ) -> ChatRequest {
+        ChatRequest {
+            model: self.config.model.clone(),
+            messages,
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            stream,
+            response_format,
+        }
+    }
+
+    /// 发送请求，遇到限流、服务端错误或网络错误时按指数退避重试
+    ///
+    /// 优先使用响应中的 `Retry-After` 作为等待时间；认证失败、请求参数错误等不可重试的错误直接返回
     #[tracing::instrument(level = "debug", skip(self, request), name = "发送HTTP请求")]
-    pub async fn send_request(
+    pub async fn send_request(&self, request: ChatRequest) -> Result<ChatResponse> {
+        self.send_with_retry(&request)
+            .await
+            .map_err(|failure| failure.error)
+    }
+
+    async fn send_with_retry(
         &self,
-        request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse> {
-        let mut request_builder = self
-            .http_client
-            .post(&self.config.api_endpoint)
-            .header("Content-Type", "application/json");
-
-        // 添加API密钥头部
-        if let Some(api_key) = &self.config.api_key {
-            request_builder =
-                request_builder.header("Authorization", format!("Bearer {}", api_key));
+        request: &ChatRequest,
+    ) -> std::result::Result<ChatResponse, AttemptError> {
+        let retry = self.config.retry.clone().unwrap_or_default();
+        let max_attempts = retry.max_retries.unwrap_or(3) + 1;
+
+        let mut attempt = 1;
+        loop {
+            match self.send_once(request).await {
+                Ok(response) => {
+                    if attempt > 1 {
+                        indicatif_println!("✅ LLM API请求在第{}次尝试时成功", attempt);
+                    }
+                    return Ok(response);
+                }
+                Err(failure) if failure.retryable && attempt < max_attempts => {
+                    let delay = retry_delay(&retry, failure.retry_after, attempt);
+                    indicatif_println!(
+                        "⚠️ LLM API请求失败 (第{}/{}次尝试): {}，{:.1}秒后重试",
+                        attempt,
+                        max_attempts,
+                        failure.error,
+                        delay.as_secs_f64()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(failure) => {
+                    if attempt > 1 {
+                        indicatif_println!("❌ LLM API请求在{}次尝试后仍失败", attempt);
+                    }
+                    return Err(failure);
+                }
+            }
         }
+    }
 
-        let response = request_builder.json(&request).send().await?;
+    /// 发送一次请求并解析完整响应，失败时判断是否值得重试
+    async fn send_once(
+        &self,
+        request: &ChatRequest,
+    ) -> std::result::Result<ChatResponse, AttemptError> {
+        let response = self.post_once(request).await?;
+        let body = response.bytes().await.map_err(AttemptError::from_network)?;
+        self.provider.parse_response(&body).map_err(AttemptError::fatal)
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
+    /// 发送一次请求，返回状态码为成功的响应
+    async fn post_once(
+        &self,
+        request: &ChatRequest,
+    ) -> std::result::Result<reqwest::Response, AttemptError> {
+        // 地址、认证头和请求体由提供商决定
+        let response = self
+            .provider
+            .build_request(&self.http_client, request)
+            .send()
+            .await
+            .map_err(AttemptError::from_network)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(SmartFetchError::LlmApiError(format!(
-                "API请求失败: {} - {}",
-                status, error_text
-            )));
+            return Err(AttemptError {
+                kind: classify_failure(status, &error_text),
+                error: SmartFetchError::LlmApiError(format!(
+                    "API请求失败: {} - {}",
+                    status, error_text
+                )),
+                retryable: is_retryable_status(status),
+                retry_after,
+            });
         }
 
-        let completion_response: ChatCompletionResponse = response
-            .json()
+        Ok(response)
+    }
+
+    /// 以流式方式生成回复，每收到一段文本调用一次 `on_delta`
+    #[tracing::instrument(level = "info", skip(self, prompt, on_delta), name = "流式调用LLM API")]
+    pub async fn generate_response_stream(
+        &self,
+        prompt: &str,
+        on_delta: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<String> {
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        }];
+        self.generate_chat_stream(messages, on_delta).await
+    }
+
+    /// 以流式方式发送多轮对话
+    ///
+    /// 流在中途断开时，携带已接收的内容请求模型从断点继续输出（计入重试次数）；
+    /// 重试用尽后返回 [`SmartFetchError::StreamInterrupted`]，其中保存已接收的部分结果
+    pub async fn generate_chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        on_delta: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<String> {
+        self.chat_stream(messages, on_delta)
             .await
-            .map_err(|e| SmartFetchError::SerializationError(format!("解析API响应失败: {}", e)))?;
+            .map(|output| output.content)
+    }
 
-        Ok(completion_response)
+    /// 以流式方式发送多轮对话，返回结果及实际应答的模型
+    ///
+    /// 尚未收到任何内容时才会改用备用模型，已输出的部分不会被另一个模型重复生成
+    pub async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        on_delta: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<LlmOutput> {
+        self.with_fallback(|client| client.single_chat_stream(messages.clone(), on_delta))
+            .await
+    }
+
+    async fn single_chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        on_delta: &(dyn Fn(&str) + Send + Sync),
+    ) -> std::result::Result<LlmOutput, AttemptError> {
+        let retry = self.config.retry.clone().unwrap_or_default();
+        let max_attempts = retry.max_retries.unwrap_or(3) + 1;
+        let mut output = String::new();
+        let mut usage: Option<Usage> = None;
+
+        let mut attempt = 1;
+        loop {
+            let mut request_messages = messages.clone();
+            if !output.is_empty() {
+                request_messages.push(ChatMessage {
+                    role: "assistant".to_string(),
+                    content: output.clone(),
+                });
+                request_messages.push(ChatMessage {
+                    role: "user".to_string(),
+                    content: CONTINUE_PROMPT.to_string(),
+                });
+            }
+            let request = self.chat_request(request_messages, true, None);
+
+            let result = match self.post_once(&request).await {
+                Ok(response) => {
+                    read_stream(self.provider.as_ref(), response, &mut output, on_delta).await
+                }
+                Err(failure) => Err(failure),
+            };
+            // 续传的每次请求都会重新计费，用量累加
+            if let Ok(Some(attempt_usage)) = &result {
+                usage.get_or_insert_with(Usage::default).add(attempt_usage);
+            }
+            let failure = match result {
+                Ok(_) if output.is_empty() => AttemptError::fatal(SmartFetchError::LlmApiError(
+                    "API返回了空的内容".to_string(),
+                )),
+                Ok(_) => {
+                    match &usage {
+                        Some(usage) => indicatif_println!(
+                            "✅ LLM API流式调用成功 (接收{}字符，输入{}token，输出{}token)",
+                            output.chars().count(),
+                            usage.prompt_tokens,
+                            usage.completion_tokens
+                        ),
+                        None => indicatif_println!(
+                            "✅ LLM API流式调用成功 (接收{}字符)",
+                            output.chars().count()
+                        ),
+                    }
+                    return Ok(LlmOutput {
+                        content: output,
+                        model: self.config.model.clone(),
+                        usage,
+                        cached: false,
+                    });
+                }
+                Err(failure) => failure,
+            };
+
+            if !failure.retryable || attempt >= max_attempts {
+                if attempt > 1 {
+                    indicatif_println!("❌ LLM API请求在{}次尝试后仍失败", attempt);
+                }
+                if output.is_empty() {
+                    return Err(failure);
+                }
+                return Err(AttemptError::fatal(SmartFetchError::StreamInterrupted {
+                    reason: failure.error.to_string(),
+                    partial: output,
+                }));
+            }
+
+            let delay = retry_delay(&retry, failure.retry_after, attempt);
+            let action = if output.is_empty() {
+                "重试"
+            } else {
+                "从断点继续"
+            };
+            indicatif_println!(
+                "⚠️ LLM API流式请求失败 (第{}/{}次尝试): {}，{:.1}秒后{}",
+                attempt,
+                max_attempts,
+                failure.error,
+                delay.as_secs_f64(),
+                action
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
     #[tracing::instrument(level = "debug", skip(self, system_prompt, user_prompt), name = "生成带上下文的响应")]
@@ -168,39 +481,22 @@ impl LLMClient {
         system_prompt: &str,
         user_prompt: &str,
     ) -> Result<String> {
-        let request = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt.to_string(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: user_prompt.to_string(),
-                },
-            ],
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
-            stream: Some(false),
-        };
-
-        // 直接发送请求，不显示进度条（由调用者控制）
-        let response = self.send_request(request).await?;
-
-        if response.choices.is_empty() {
-            return Err(SmartFetchError::LlmApiError(
-                "API返回了空的选择列表".to_string(),
-            ));
-        }
-
-        let choice = &response.choices[0];
-        Ok(choice.message.content.clone())
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt.to_string(),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: user_prompt.to_string(),
+            },
+        ];
+        self.generate_chat(messages, None).await
     }
 
     #[tracing::instrument(level = "debug", skip(self), name = "LLM健康检查")]
     pub async fn health_check(&self) -> Result<bool> {
-        let test_request = ChatCompletionRequest {
+        let test_request = ChatRequest {
             model: self.config.model.clone(),
             messages: vec![ChatMessage {
                 role: "user".to_string(),
@@ -208,19 +504,18 @@ impl LLMClient {
             }],
             max_tokens: Some(1),
             temperature: Some(0.0),
-            stream: Some(false),
+            stream: false,
+            response_format: None,
         };
 
-        match self.send_request(test_request).await
-        {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
-        }
+        // 健康检查只尝试一次，不进行重试
+        Ok(self.send_once(&test_request).await.is_ok())
     }
 
     #[tracing::instrument(level = "debug", skip(self), name = "获取模型信息")]
     pub async fn get_model_info(&self) -> Result<HashMap<String, String>> {
         let mut info = HashMap::new();
+        info.insert("provider".to_string(), self.provider.name().to_string());
         info.insert("model".to_string(), self.config.model.clone());
         info.insert("api_endpoint".to_string(), self.config.api_endpoint.clone());
         info.insert(
@@ -231,11 +526,255 @@ impl LLMClient {
             "temperature".to_string(),
             self.config.temperature.unwrap_or(0.0).to_string(),
         );
+        if !self.fallbacks.is_empty() {
+            let models: Vec<&str> = self
+                .fallbacks
+                .iter()
+                .map(|client| client.config.model.as_str())
+                .collect();
+            info.insert("fallback".to_string(), models.join(","));
+        }
 
         Ok(info)
     }
 
+    /// 按 `llm.response_format` 配置为给定 Schema 选择输出格式，`none` 时只依靠提示词约束
+    pub fn response_format_for_schema(&self, schema: &serde_json::Value) -> Option<ResponseFormat> {
+        match self.config.response_format.as_deref().unwrap_or("json_schema") {
+            "json_schema" => Some(ResponseFormat::JsonSchema {
+                json_schema: JsonSchemaFormat {
+                    name: "extraction".to_string(),
+                    schema: schema.clone(),
+                    // 严格模式要求 Schema 满足额外限制，这里由本地校验兜底
+                    strict: false,
+                },
+            }),
+            "json_object" => Some(ResponseFormat::JsonObject),
+            _ => None,
+        }
+    }
+
     pub fn get_config(&self) -> &LLMConfig {
         &self.config
     }
+
+    /// 按 `llm.fallback` 顺序排列的备用模型客户端
+    pub fn fallbacks(&self) -> &[LLMClient] {
+        &self.fallbacks
+    }
+
+    pub fn provider(&self) -> &dyn LlmProvider {
+        self.provider.as_ref()
+    }
+}
+
+/// 流式响应中断后请求模型续写的提示词
+const CONTINUE_PROMPT: &str =
+    "你的上一条回复在传输中被截断了。请从截断处继续输出剩余内容，不要重复已经输出的部分，也不要添加任何说明。";
+
+/// 读取流式响应，把收到的文本追加到 `output`，读到结束标记时返回合并后的用量
+///
+/// 服务端忽略 `stream` 参数返回普通 JSON 时按完整响应处理
+async fn read_stream(
+    provider: &dyn LlmProvider,
+    response: reqwest::Response,
+    output: &mut String,
+    on_delta: &(dyn Fn(&str) + Send + Sync),
+) -> std::result::Result<Option<Usage>, AttemptError> {
+    let is_stream = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| provider.is_stream_response(value));
+
+    if !is_stream {
+        let body = response.bytes().await.map_err(AttemptError::from_network)?;
+        let completion = provider.parse_response(&body).map_err(AttemptError::fatal)?;
+        if !completion.content.is_empty() {
+            on_delta(&completion.content);
+            output.push_str(&completion.content);
+        }
+        return Ok(completion.usage);
+    }
+
+    let mut usage: Option<Usage> = None;
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(bytes) = stream.next().await {
+        buffer.extend_from_slice(&bytes.map_err(AttemptError::from_network)?);
+
+        // 按行处理，未结束的行（可能截断在 UTF-8 字符中间）留到下一次
+        while let Some(newline) = buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            // 流中返回的错误多为服务端暂时性故障，允许重试
+            let event = match provider.parse_stream_line(line.trim_end()) {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(error) => {
+                    return Err(AttemptError {
+                        error,
+                        retryable: true,
+                        retry_after: None,
+                        kind: FailureKind::Unavailable,
+                    })
+                }
+            };
+
+            if let Some(delta) = event.delta.filter(|delta| !delta.is_empty()) {
+                on_delta(&delta);
+                output.push_str(&delta);
+            }
+            if let Some(event_usage) = &event.usage {
+                usage.get_or_insert_with(Usage::default).merge(event_usage);
+            }
+            if event.finished {
+                return Ok(usage);
+            }
+        }
+    }
+
+    Err(AttemptError {
+        error: SmartFetchError::NetworkError("流式响应在结束前断开".to_string()),
+        retryable: true,
+        retry_after: None,
+        kind: FailureKind::Unavailable,
+    })
+}
+
+/// 单次请求失败的原因、是否可以重试以及是否值得改用备用模型
+struct AttemptError {
+    error: SmartFetchError,
+    retryable: bool,
+    retry_after: Option<Duration>,
+    kind: FailureKind,
+}
+
+impl AttemptError {
+    /// 不可重试的错误（如响应格式无法解析）
+    fn fatal(error: SmartFetchError) -> Self {
+        Self {
+            error,
+            retryable: false,
+            retry_after: None,
+            kind: FailureKind::Fatal,
+        }
+    }
+
+    fn from_network(err: reqwest::Error) -> Self {
+        // 构建请求失败（如无效 URL）重试也无济于事，其余网络错误视为暂时性故障
+        let retryable = !err.is_builder();
+        Self {
+            error: err.into(),
+            retryable,
+            retry_after: None,
+            kind: if retryable {
+                FailureKind::Unavailable
+            } else {
+                FailureKind::Fatal
+            },
+        }
+    }
+}
+
+/// 请求失败的类别，决定是否改用备用模型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// 服务不可用：网络错误、超时或服务端错误
+    Unavailable,
+    /// 服务过载或限流
+    Overloaded,
+    /// 输入超出模型的上下文长度
+    ContextTooLong,
+    /// 认证失败、参数错误等换模型也无法解决的错误
+    Fatal,
+}
+
+impl FailureKind {
+    pub fn triggers_fallback(self) -> bool {
+        self != FailureKind::Fatal
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            FailureKind::Unavailable => "不可用",
+            FailureKind::Overloaded => "过载",
+            FailureKind::ContextTooLong => "超出上下文长度",
+            FailureKind::Fatal => "请求失败",
+        };
+        f.write_str(description)
+    }
+}
+
+/// 各提供商表示上下文超长的错误信息片段（小写）
+const CONTEXT_LENGTH_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "context length",
+    "context window",
+    "prompt is too long",
+    "too many tokens",
+    "input is too long",
+    "maximum context",
+    "上下文长度",
+];
+
+/// 根据状态码和响应内容判断失败类别
+pub fn classify_failure(status: reqwest::StatusCode, body: &str) -> FailureKind {
+    match status.as_u16() {
+        // 529 为 Anthropic 的过载状态码
+        429 | 529 => FailureKind::Overloaded,
+        400 | 413 | 422 => {
+            let body = body.to_lowercase();
+            if CONTEXT_LENGTH_MARKERS
+                .iter()
+                .any(|marker| body.contains(marker))
+            {
+                FailureKind::ContextTooLong
+            } else {
+                FailureKind::Fatal
+            }
+        }
+        408 => FailureKind::Unavailable,
+        _ if status.is_server_error() => FailureKind::Unavailable,
+        _ => FailureKind::Fatal,
+    }
+}
+
+/// 请求超时、冲突、限流和服务端错误可以重试；认证失败、参数错误等 4xx 直接失败
+pub fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 409 | 429) || status.is_server_error()
+}
+
+/// 解析 `Retry-After` 响应头，支持秒数和 HTTP 日期两种格式
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 重试前的等待时间：优先遵循服务端的 `Retry-After`，但不超过 `max_backoff_ms`，
+/// 避免异常的超大值让请求长时间挂起
+pub fn retry_delay(config: &RetryConfig, retry_after: Option<Duration>, attempt: u32) -> Duration {
+    let max = Duration::from_millis(config.max_backoff_ms.unwrap_or(30000));
+    match retry_after {
+        Some(delay) => delay.min(max),
+        None => retry_backoff(config, attempt),
+    }
+}
+
+/// 第 `attempt` 次失败后的等待时间：指数增长并封顶，再在后一半区间内随机抖动，避免并发请求同时重试
+pub fn retry_backoff(config: &RetryConfig, attempt: u32) -> Duration {
+    let initial = config.initial_backoff_ms.unwrap_or(1000);
+    let max = config.max_backoff_ms.unwrap_or(30000);
+    let exponent = attempt.saturating_sub(1).min(31);
+    let base = initial.saturating_mul(1u64 << exponent).min(max);
+
+    Duration::from_millis(base / 2 + fastrand::u64(0..=base / 2))
 }
@@ -0,0 +1,334 @@
+use super::markdown::{MarkdownBuilder, TableCollector};
+use super::{
+    attribute, open_archive, read_entry, read_package_metadata, read_required_entry, xml_error,
+    DocumentLoader, LoadedContent, ZIP_SIGNATURE,
+};
+use crate::document::{Document, DocumentMetadata};
+use crate::error::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+const FORMAT: &str = "DOCX";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DocxLoader;
+
+impl DocumentLoader for DocxLoader {
+    fn name(&self) -> &str {
+        "Word 文档"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["docx"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/vnd.openxmlformats-officedocument.wordprocessingml.document"]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        // 压缩包内的文件名以明文保存在文件头和中央目录中
+        bytes.starts_with(ZIP_SIGNATURE)
+            && bytes
+                .windows(b"word/document.xml".len())
+                .any(|window| window == b"word/document.xml")
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let loaded = load_docx(bytes)?;
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+/// 将 Word 文档（Office Open XML）转换为 Markdown
+///
+/// 标题样式转换为 Markdown 标题，编号段落转换为列表，表格转换为 Markdown 表格；
+/// 标题、作者、创建时间从 `docProps/core.xml` 读取
+#[tracing::instrument(level = "debug", skip(bytes), name = "提取DOCX文本")]
+pub fn load_docx(bytes: &[u8]) -> Result<LoadedContent> {
+    let mut archive = open_archive(bytes, FORMAT)?;
+    let document_xml = read_required_entry(&mut archive, "word/document.xml", FORMAT)?;
+
+    let headings = match read_entry(&mut archive, "word/styles.xml")? {
+        Some(xml) => read_heading_styles(&xml)?,
+        None => HashMap::new(),
+    };
+    let numbering = match read_entry(&mut archive, "word/numbering.xml")? {
+        Some(xml) => read_numbering(&xml)?,
+        None => Numbering::default(),
+    };
+    let metadata = match read_entry(&mut archive, "docProps/core.xml")? {
+        Some(xml) => read_package_metadata(&xml, FORMAT)?,
+        None => DocumentMetadata::default(),
+    };
+
+    let content = convert_body(&document_xml, &headings, &numbering)?;
+    Ok(LoadedContent { content, metadata })
+}
+
+#[derive(Default)]
+struct Paragraph {
+    style: Option<String>,
+    num_id: Option<String>,
+    level: usize,
+    text: String,
+}
+
+fn convert_body(
+    xml: &str,
+    headings: &HashMap<String, usize>,
+    numbering: &Numbering,
+) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut builder = MarkdownBuilder::default();
+    // 文本框中的段落嵌套在外层段落内，因此用栈保存
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    let mut table = TableCollector::default();
+    let mut in_run = false;
+    let mut in_text = false;
+    let mut current_list: Option<String> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"p" => paragraphs.push(Paragraph::default()),
+                // 兼容性备用内容与主体内容重复，直接跳过
+                b"Fallback" => {
+                    reader
+                        .read_to_end(element.name())
+                        .map_err(|e| xml_error(FORMAT, e))?;
+                }
+                b"r" => in_run = true,
+                b"t" => in_text = in_run,
+                b"tbl" => table.start_table(),
+                b"tr" => table.start_row(),
+                b"tc" => table.start_cell(),
+                _ => {}
+            },
+            Event::Empty(element) => {
+                let Some(current) = paragraphs.last_mut() else {
+                    continue;
+                };
+                match element.local_name().as_ref() {
+                    b"pStyle" => current.style = attribute(&element, b"val"),
+                    b"numId" => current.num_id = attribute(&element, b"val"),
+                    b"ilvl" => {
+                        current.level = attribute(&element, b"val")
+                            .and_then(|value| value.parse().ok())
+                            .unwrap_or(0)
+                    }
+                    b"tab" if in_run => current.text.push('\t'),
+                    b"br" | b"cr" if in_run => current.text.push('\n'),
+                    _ => {}
+                }
+            }
+            Event::Text(text) if in_text => {
+                if let Some(current) = paragraphs.last_mut() {
+                    current
+                        .text
+                        .push_str(&text.unescape().map_err(|e| xml_error(FORMAT, e))?);
+                }
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"r" => in_run = false,
+                b"p" => {
+                    if let Some(finished) = paragraphs.pop() {
+                        if table.is_active() {
+                            table.push_text(&finished.text);
+                        } else {
+                            write_paragraph(
+                                &mut builder,
+                                finished,
+                                &mut current_list,
+                                headings,
+                                numbering,
+                            );
+                        }
+                    }
+                }
+                b"tc" => table.end_cell(),
+                b"tr" => table.end_row(),
+                b"tbl" => {
+                    if let Some(rows) = table.end_table() {
+                        builder.table(&rows);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(builder.finish())
+}
+
+fn write_paragraph(
+    builder: &mut MarkdownBuilder,
+    paragraph: Paragraph,
+    current_list: &mut Option<String>,
+    headings: &HashMap<String, usize>,
+    numbering: &Numbering,
+) {
+    let heading_level = paragraph.style.as_deref().and_then(|style| {
+        headings
+            .get(style)
+            .copied()
+            .or_else(|| heading_from_id(style))
+    });
+
+    match (heading_level, paragraph.num_id) {
+        // numId 为 0 表示显式取消编号
+        (None, Some(num_id)) if num_id != "0" => {
+            // 换用另一个编号定义时开始新的列表
+            if current_list.as_deref() != Some(num_id.as_str()) {
+                builder.end_list();
+            }
+            builder.list_item(
+                paragraph.level,
+                numbering.is_ordered(&num_id, paragraph.level),
+                &paragraph.text,
+            );
+            *current_list = Some(num_id);
+        }
+        (Some(level), _) => {
+            *current_list = None;
+            builder.heading(level, &paragraph.text);
+        }
+        _ => {
+            *current_list = None;
+            builder.paragraph(&paragraph.text);
+        }
+    }
+}
+
+/// 内置样式 ID 形如 `Heading1`、`Title`
+fn heading_from_id(style: &str) -> Option<usize> {
+    if style.eq_ignore_ascii_case("title") {
+        return Some(1);
+    }
+    style
+        .strip_prefix("Heading")
+        .and_then(|level| level.parse().ok())
+}
+
+/// 从 `word/styles.xml` 读取标题样式：样式名为 `heading N` / `Title`，或设置了大纲级别
+fn read_heading_styles(xml: &str) -> Result<HashMap<String, usize>> {
+    let mut reader = Reader::from_str(xml);
+    let mut headings = HashMap::new();
+    let mut style_id: Option<String> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) if element.local_name().as_ref() == b"style" => {
+                style_id = attribute(&element, b"styleId");
+            }
+            Event::End(element) if element.local_name().as_ref() == b"style" => {
+                style_id = None;
+            }
+            Event::Empty(element) => {
+                let Some(id) = style_id.as_ref() else {
+                    continue;
+                };
+                let level = match element.local_name().as_ref() {
+                    b"name" => attribute(&element, b"val").and_then(|name| {
+                        let name = name.to_lowercase();
+                        if name == "title" {
+                            Some(1)
+                        } else {
+                            name.strip_prefix("heading ")?.parse().ok()
+                        }
+                    }),
+                    b"outlineLvl" => attribute(&element, b"val")
+                        .and_then(|level| level.parse::<usize>().ok())
+                        .map(|level| level + 1),
+                    _ => None,
+                };
+                if let Some(level) = level {
+                    headings.entry(id.clone()).or_insert(level);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(headings)
+}
+
+/// `word/numbering.xml` 中各编号定义在每一级是否为有序列表
+#[derive(Default)]
+struct Numbering {
+    /// numId -> abstractNumId
+    instances: HashMap<String, String>,
+    /// (abstractNumId, 级别) -> 是否有序
+    levels: HashMap<(String, usize), bool>,
+}
+
+impl Numbering {
+    fn is_ordered(&self, num_id: &str, level: usize) -> bool {
+        self.instances
+            .get(num_id)
+            .and_then(|abstract_id| self.levels.get(&(abstract_id.clone(), level)))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+fn read_numbering(xml: &str) -> Result<Numbering> {
+    let mut reader = Reader::from_str(xml);
+    let mut numbering = Numbering::default();
+    let mut abstract_id: Option<String> = None;
+    let mut level: Option<usize> = None;
+    let mut num_id: Option<String> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"abstractNum" => abstract_id = attribute(&element, b"abstractNumId"),
+                b"lvl" => level = attribute(&element, b"ilvl").and_then(|value| value.parse().ok()),
+                b"num" => num_id = attribute(&element, b"numId"),
+                _ => {}
+            },
+            Event::Empty(element) => match element.local_name().as_ref() {
+                b"numFmt" => {
+                    if let (Some(abstract_id), Some(level)) = (abstract_id.as_ref(), level) {
+                        let ordered = !matches!(
+                            attribute(&element, b"val").as_deref(),
+                            Some("bullet") | Some("none") | None
+                        );
+                        numbering
+                            .levels
+                            .insert((abstract_id.clone(), level), ordered);
+                    }
+                }
+                b"abstractNumId" => {
+                    if let (Some(num_id), Some(value)) =
+                        (num_id.as_ref(), attribute(&element, b"val"))
+                    {
+                        numbering.instances.insert(num_id.clone(), value);
+                    }
+                }
+                _ => {}
+            },
+            Event::End(element) => match element.local_name().as_ref() {
+                b"abstractNum" => abstract_id = None,
+                b"lvl" => level = None,
+                b"num" => num_id = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(numbering)
+}
@@ -0,0 +1,189 @@
+use super::{
+    attribute, open_archive, read_entry, read_package_metadata, read_required_entry, xml_error,
+    zip_mimetype, DocumentLoader, LoadedContent,
+};
+use crate::document::Document;
+use crate::error::{Result, SmartFetchError};
+use crate::html::HtmlConverter;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+const FORMAT: &str = "EPUB";
+const MIME_TYPE: &str = "application/epub+zip";
+
+#[derive(Debug, Clone)]
+pub struct EpubLoader {
+    html_converter: HtmlConverter,
+}
+
+impl EpubLoader {
+    pub fn new(html_converter: HtmlConverter) -> Self {
+        Self { html_converter }
+    }
+}
+
+impl DocumentLoader for EpubLoader {
+    fn name(&self) -> &str {
+        "EPUB 电子书"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["epub"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &[MIME_TYPE]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        zip_mimetype(bytes) == Some(MIME_TYPE.as_bytes())
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let loaded = load_epub(bytes, &self.html_converter)?;
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+/// 将 EPUB 电子书按阅读顺序（spine）转换为 Markdown
+///
+/// 各章节为 XHTML，使用 [`HtmlConverter`] 转换；标题、作者、日期从 OPF 包文件读取
+#[tracing::instrument(level = "debug", skip(bytes, html_converter), name = "提取EPUB文本")]
+pub fn load_epub(bytes: &[u8], html_converter: &HtmlConverter) -> Result<LoadedContent> {
+    let mut archive = open_archive(bytes, FORMAT)?;
+    let container = read_required_entry(&mut archive, "META-INF/container.xml", FORMAT)?;
+    let package_path = read_rootfile_path(&container)?;
+    let package = read_required_entry(&mut archive, &package_path, FORMAT)?;
+
+    let metadata = read_package_metadata(&package, FORMAT)?;
+    let base_dir = package_path
+        .rsplit_once('/')
+        .map(|(dir, _)| format!("{}/", dir))
+        .unwrap_or_default();
+
+    let mut chapters = Vec::new();
+    for href in read_spine(&package)? {
+        let path = resolve_path(&base_dir, &href);
+        let Some(xhtml) = read_entry(&mut archive, &path)? else {
+            tracing::warn!("EPUB 章节不存在: {}", path);
+            continue;
+        };
+        let chapter = html_converter.convert(&xhtml);
+        if !chapter.trim().is_empty() {
+            chapters.push(chapter);
+        }
+    }
+
+    Ok(LoadedContent {
+        content: chapters.join("\n\n"),
+        metadata,
+    })
+}
+
+/// 从 `META-INF/container.xml` 中找到 OPF 包文件路径
+fn read_rootfile_path(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == b"rootfile" =>
+            {
+                if let Some(path) = attribute(&element, b"full-path") {
+                    return Ok(path);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Err(SmartFetchError::DocumentError(
+        "不是有效的 EPUB 文件: container.xml 中缺少 rootfile".to_string(),
+    ))
+}
+
+/// 按 spine 顺序返回 XHTML 章节的路径（相对于 OPF 文件）
+fn read_spine(xml: &str) -> Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut manifest = HashMap::new();
+    let mut spine = Vec::new();
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) | Event::Empty(element) => match element.local_name().as_ref() {
+                b"item" => {
+                    let media_type = attribute(&element, b"media-type").unwrap_or_default();
+                    if !media_type.contains("html") {
+                        continue;
+                    }
+                    if let (Some(id), Some(href)) =
+                        (attribute(&element, b"id"), attribute(&element, b"href"))
+                    {
+                        manifest.insert(id, href);
+                    }
+                }
+                b"itemref" => {
+                    // linear="no" 的条目（如封面、附加页）不在正文阅读顺序中
+                    if attribute(&element, b"linear").as_deref() == Some("no") {
+                        continue;
+                    }
+                    if let Some(idref) = attribute(&element, b"idref") {
+                        spine.push(idref);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(spine
+        .into_iter()
+        .filter_map(|idref| manifest.get(&idref).cloned())
+        .collect())
+}
+
+/// 将 OPF 中的相对 href 解析为压缩包内的路径
+fn resolve_path(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    let mut parts: Vec<String> = Vec::new();
+    for segment in format!("{}{}", base_dir, percent_decode(href)).split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            other => parts.push(other.to_string()),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(byte) = text
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
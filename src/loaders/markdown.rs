@@ -0,0 +1,166 @@
+/// 按块构建 Markdown 文本，供 DOCX / ODT 等基于 XML 的格式共用
+///
+/// 列表项之间不留空行，其余块之间以空行分隔；有序列表按层级自动编号
+#[derive(Debug, Default)]
+pub(crate) struct MarkdownBuilder {
+    out: String,
+    in_list: bool,
+    counters: Vec<usize>,
+}
+
+impl MarkdownBuilder {
+    pub(crate) fn heading(&mut self, level: usize, text: &str) {
+        let text = collapse_whitespace(text);
+        if text.is_empty() {
+            return;
+        }
+        self.start_block();
+        self.out
+            .push_str(&format!("{} {}", "#".repeat(level.clamp(1, 6)), text));
+    }
+
+    pub(crate) fn paragraph(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        self.start_block();
+        self.out.push_str(text);
+    }
+
+    /// 添加列表项，`depth` 从 0 开始
+    pub(crate) fn list_item(&mut self, depth: usize, ordered: bool, text: &str) {
+        let text = collapse_whitespace(text);
+        if text.is_empty() {
+            return;
+        }
+
+        if self.in_list {
+            self.out.push('\n');
+        } else {
+            self.start_block();
+            self.in_list = true;
+        }
+
+        // 回到上层时，丢弃更深层级的编号
+        self.counters.resize(depth + 1, 0);
+        self.counters[depth] += 1;
+
+        let marker = if ordered {
+            format!("{}.", self.counters[depth])
+        } else {
+            "-".to_string()
+        };
+        self.out
+            .push_str(&format!("{}{} {}", "  ".repeat(depth), marker, text));
+    }
+
+    /// 结束当前列表，之后的列表项重新编号
+    pub(crate) fn end_list(&mut self) {
+        self.in_list = false;
+        self.counters.clear();
+    }
+
+    pub(crate) fn table(&mut self, rows: &[Vec<String>]) {
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        self.start_block();
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells: Vec<String> = row
+                .iter()
+                .map(|cell| collapse_whitespace(cell).replace('|', "\\|"))
+                .collect();
+            cells.resize(columns, String::new());
+            if index > 0 {
+                self.out.push('\n');
+            }
+            self.out.push_str(&format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                self.out
+                    .push_str(&format!("\n|{}", " --- |".repeat(columns)));
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+
+    fn start_block(&mut self) {
+        self.end_list();
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 收集 XML 表格的行和单元格；嵌套表格的文本并入外层单元格
+#[derive(Debug, Default)]
+pub(crate) struct TableCollector {
+    depth: usize,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: String,
+}
+
+impl TableCollector {
+    pub(crate) fn is_active(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub(crate) fn start_table(&mut self) {
+        self.depth += 1;
+        if self.depth == 1 {
+            self.rows.clear();
+        }
+    }
+
+    pub(crate) fn start_row(&mut self) {
+        if self.depth == 1 {
+            self.row.clear();
+        }
+    }
+
+    pub(crate) fn start_cell(&mut self) {
+        if self.depth == 1 {
+            self.cell.clear();
+        }
+    }
+
+    /// 单元格中的多个段落以空格连接
+    pub(crate) fn push_text(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        if !self.cell.is_empty() {
+            self.cell.push(' ');
+        }
+        self.cell.push_str(text);
+    }
+
+    pub(crate) fn end_cell(&mut self) {
+        if self.depth == 1 {
+            self.row.push(std::mem::take(&mut self.cell));
+        }
+    }
+
+    pub(crate) fn end_row(&mut self) {
+        if self.depth == 1 {
+            self.rows.push(std::mem::take(&mut self.row));
+        }
+    }
+
+    /// 最外层表格结束时返回收集到的所有行
+    pub(crate) fn end_table(&mut self) -> Option<Vec<Vec<String>>> {
+        self.depth = self.depth.saturating_sub(1);
+        (self.depth == 0).then(|| std::mem::take(&mut self.rows))
+    }
+}
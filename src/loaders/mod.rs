@@ -0,0 +1,132 @@
+pub mod docx;
+pub mod epub;
+mod markdown;
+pub mod odt;
+pub mod pdf;
+mod registry;
+pub mod text;
+
+pub use registry::{DocumentLoader, LoaderRegistry};
+
+use crate::document::DocumentMetadata;
+use crate::error::{Result, SmartFetchError};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// 二进制文档加载后的结果：转换得到的文本及从文档包中读取的元数据
+#[derive(Debug, Clone, Default)]
+pub struct LoadedContent {
+    pub content: String,
+    pub metadata: DocumentMetadata,
+}
+
+type Archive<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+fn open_archive<'a>(bytes: &'a [u8], format: &str) -> Result<Archive<'a>> {
+    ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| SmartFetchError::DocumentError(format!("{} 文件解压失败: {}", format, e)))
+}
+
+/// 读取压缩包中的文本文件，不存在时返回 `None`
+fn read_entry(archive: &mut Archive<'_>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => {
+            return Err(SmartFetchError::DocumentError(format!(
+                "无法读取文档内容 {}: {}",
+                name, e
+            )))
+        }
+    };
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| SmartFetchError::DocumentError(format!("无法读取文档内容 {}: {}", name, e)))?;
+    Ok(Some(content))
+}
+
+fn read_required_entry(archive: &mut Archive<'_>, name: &str, format: &str) -> Result<String> {
+    read_entry(archive, name)?.ok_or_else(|| {
+        SmartFetchError::DocumentError(format!("不是有效的 {} 文件: 缺少 {}", format, name))
+    })
+}
+
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+/// OpenDocument 和 EPUB 要求压缩包的第一个文件是未压缩的 `mimetype`，返回其内容
+fn zip_mimetype(bytes: &[u8]) -> Option<&[u8]> {
+    if !bytes.starts_with(ZIP_SIGNATURE) || bytes.get(30..38)? != b"mimetype" {
+        return None;
+    }
+    let size = u32::from_le_bytes(bytes.get(18..22)?.try_into().ok()?) as usize;
+    bytes.get(38..38 + size)
+}
+
+fn xml_error(format: &str, error: impl std::fmt::Display) -> SmartFetchError {
+    SmartFetchError::DocumentError(format!("{} 文档 XML 解析失败: {}", format, error))
+}
+
+/// 按本地名称（忽略命名空间前缀）读取属性值
+fn attribute(element: &BytesStart<'_>, local_name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == local_name)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// 读取 Dublin Core 风格的包元数据
+///
+/// DOCX 的 `docProps/core.xml`、ODT 的 `meta.xml` 和 EPUB 的 OPF 文件都使用
+/// `dc:title`、`dc:creator` 等元素，只是创建时间的元素名不同。`dc:date` 在
+/// ODT 中表示修改时间、在 EPUB 中表示出版时间，因此仅在没有明确创建时间时作为创建时间
+fn read_package_metadata(xml: &str, format: &str) -> Result<DocumentMetadata> {
+    let mut reader = Reader::from_str(xml);
+    let mut metadata = DocumentMetadata::default();
+    let mut dc_date = None;
+    let mut current: Option<Vec<u8>> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(format, e))? {
+            Event::Start(element) => {
+                current = Some(element.local_name().as_ref().to_vec());
+                text.clear();
+            }
+            Event::Text(content) if current.is_some() => {
+                text.push_str(&content.unescape().map_err(|e| xml_error(format, e))?);
+            }
+            Event::End(_) => {
+                let value = text.trim().to_string();
+                if let (Some(name), false) = (current.take(), value.is_empty()) {
+                    let field = match name.as_slice() {
+                        b"title" => &mut metadata.title,
+                        b"creator" | b"initial-creator" => &mut metadata.author,
+                        b"created" | b"creation-date" => &mut metadata.created_at,
+                        b"modified" => &mut metadata.modified_at,
+                        b"date" => &mut dc_date,
+                        _ => continue,
+                    };
+                    // 同名元素出现多次时（如多位作者）保留第一个
+                    if field.is_none() {
+                        *field = Some(value);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if metadata.created_at.is_none() {
+        metadata.created_at = dc_date;
+    } else if metadata.modified_at.is_none() {
+        metadata.modified_at = dc_date;
+    }
+
+    Ok(metadata)
+}
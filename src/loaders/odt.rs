@@ -0,0 +1,279 @@
+use super::markdown::{MarkdownBuilder, TableCollector};
+use super::{
+    attribute, open_archive, read_entry, read_package_metadata, read_required_entry, xml_error,
+    zip_mimetype, DocumentLoader, LoadedContent,
+};
+use crate::document::{Document, DocumentMetadata};
+use crate::error::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+const FORMAT: &str = "ODT";
+const MIME_TYPE: &str = "application/vnd.oasis.opendocument.text";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OdtLoader;
+
+impl DocumentLoader for OdtLoader {
+    fn name(&self) -> &str {
+        "OpenDocument 文本"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["odt"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &[MIME_TYPE]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        zip_mimetype(bytes) == Some(MIME_TYPE.as_bytes())
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let loaded = load_odt(bytes)?;
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+/// 将 OpenDocument 文本转换为 Markdown
+///
+/// `text:h` 按大纲级别转换为标题，`text:list` 转换为（嵌套）列表，表格转换为 Markdown 表格；
+/// 标题、作者、创建时间从 `meta.xml` 读取
+#[tracing::instrument(level = "debug", skip(bytes), name = "提取ODT文本")]
+pub fn load_odt(bytes: &[u8]) -> Result<LoadedContent> {
+    let mut archive = open_archive(bytes, FORMAT)?;
+    let content_xml = read_required_entry(&mut archive, "content.xml", FORMAT)?;
+
+    // 列表样式可能定义在 content.xml 的自动样式或 styles.xml 的公共样式中
+    let mut list_styles = read_list_styles(&content_xml)?;
+    if let Some(styles_xml) = read_entry(&mut archive, "styles.xml")? {
+        for (key, ordered) in read_list_styles(&styles_xml)? {
+            list_styles.entry(key).or_insert(ordered);
+        }
+    }
+    let metadata = match read_entry(&mut archive, "meta.xml")? {
+        Some(xml) => read_package_metadata(&xml, FORMAT)?,
+        None => DocumentMetadata::default(),
+    };
+
+    let content = convert_body(&content_xml, &list_styles)?;
+    Ok(LoadedContent { content, metadata })
+}
+
+struct TextBlock {
+    heading_level: Option<usize>,
+    text: String,
+}
+
+struct ListState {
+    style: Option<String>,
+    /// 当前列表项尚未输出的文本
+    item: Option<String>,
+}
+
+struct BodyConverter<'a> {
+    list_styles: &'a HashMap<(String, usize), bool>,
+    builder: MarkdownBuilder,
+    table: TableCollector,
+    blocks: Vec<TextBlock>,
+    lists: Vec<ListState>,
+}
+
+fn convert_body(xml: &str, list_styles: &HashMap<(String, usize), bool>) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut converter = BodyConverter {
+        list_styles,
+        builder: MarkdownBuilder::default(),
+        table: TableCollector::default(),
+        blocks: Vec::new(),
+        lists: Vec::new(),
+    };
+    let mut in_body = false;
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"text" if element.name().as_ref() == b"office:text" => in_body = true,
+                // 脚注、批注和修订记录不属于正文
+                b"note" | b"annotation" | b"tracked-changes" => {
+                    reader
+                        .read_to_end(element.name())
+                        .map_err(|e| xml_error(FORMAT, e))?;
+                }
+                _ if in_body => converter.start(&element),
+                _ => {}
+            },
+            Event::Empty(element) if in_body => converter.empty(&element),
+            Event::Text(text) if in_body => {
+                if let Some(block) = converter.blocks.last_mut() {
+                    block
+                        .text
+                        .push_str(&text.unescape().map_err(|e| xml_error(FORMAT, e))?);
+                }
+            }
+            Event::End(element) if in_body => {
+                if element.name().as_ref() == b"office:text" {
+                    break;
+                }
+                converter.end(element.local_name().as_ref());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(converter.builder.finish())
+}
+
+impl BodyConverter<'_> {
+    fn start(&mut self, element: &BytesStart<'_>) {
+        match element.local_name().as_ref() {
+            b"h" => self.blocks.push(TextBlock {
+                heading_level: Some(
+                    attribute(element, b"outline-level")
+                        .and_then(|level| level.parse().ok())
+                        .unwrap_or(1),
+                ),
+                text: String::new(),
+            }),
+            b"p" => self.blocks.push(TextBlock {
+                heading_level: None,
+                text: String::new(),
+            }),
+            b"list" => {
+                // 先输出父级列表项，保证嵌套列表出现在其后
+                self.flush_item();
+                let style = attribute(element, b"style-name")
+                    .or_else(|| self.lists.last().and_then(|list| list.style.clone()));
+                self.lists.push(ListState { style, item: None });
+            }
+            b"list-item" | b"list-header" => {
+                if let Some(list) = self.lists.last_mut() {
+                    list.item = Some(String::new());
+                }
+            }
+            b"table" => self.table.start_table(),
+            b"table-row" => self.table.start_row(),
+            b"table-cell" => self.table.start_cell(),
+            _ => {}
+        }
+    }
+
+    fn empty(&mut self, element: &BytesStart<'_>) {
+        let Some(block) = self.blocks.last_mut() else {
+            return;
+        };
+        match element.local_name().as_ref() {
+            b"s" => {
+                let count = attribute(element, b"c")
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(1);
+                block.text.push_str(&" ".repeat(count));
+            }
+            b"tab" => block.text.push('\t'),
+            b"line-break" => block.text.push('\n'),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, local_name: &[u8]) {
+        match local_name {
+            b"h" | b"p" => {
+                if let Some(block) = self.blocks.pop() {
+                    self.finish_block(block);
+                }
+            }
+            b"list-item" | b"list-header" => self.flush_item(),
+            b"list" => {
+                self.lists.pop();
+            }
+            b"table-cell" => self.table.end_cell(),
+            b"table-row" => self.table.end_row(),
+            b"table" => {
+                if let Some(rows) = self.table.end_table() {
+                    self.builder.table(&rows);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish_block(&mut self, block: TextBlock) {
+        if self.table.is_active() {
+            self.table.push_text(&block.text);
+        } else if let Some(level) = block.heading_level {
+            // 带大纲编号的标题也包裹在 text:list 中，仍按标题输出
+            self.builder.heading(level, &block.text);
+        } else if let Some(item) = self.lists.last_mut().and_then(|list| list.item.as_mut()) {
+            if !item.is_empty() {
+                item.push(' ');
+            }
+            item.push_str(block.text.trim());
+        } else {
+            self.builder.paragraph(&block.text);
+        }
+    }
+
+    fn flush_item(&mut self) {
+        let depth = self.lists.len().saturating_sub(1);
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        let Some(text) = list.item.take() else {
+            return;
+        };
+
+        // ODF 的列表级别从 1 开始
+        let ordered = list
+            .style
+            .as_ref()
+            .and_then(|style| self.list_styles.get(&(style.clone(), depth + 1)))
+            .copied()
+            .unwrap_or(false);
+        self.builder.list_item(depth, ordered, &text);
+    }
+}
+
+/// 读取列表样式：(样式名, 级别) -> 是否为编号列表
+fn read_list_styles(xml: &str) -> Result<HashMap<(String, usize), bool>> {
+    let mut reader = Reader::from_str(xml);
+    let mut styles = HashMap::new();
+    let mut current: Option<String> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(FORMAT, e))? {
+            Event::Start(element) if element.local_name().as_ref() == b"list-style" => {
+                current = attribute(&element, b"name");
+            }
+            Event::End(element) if element.local_name().as_ref() == b"list-style" => {
+                current = None;
+            }
+            Event::Start(element) | Event::Empty(element) => {
+                let Some(name) = current.as_ref() else {
+                    continue;
+                };
+                let ordered = match element.local_name().as_ref() {
+                    b"list-level-style-number" => true,
+                    b"list-level-style-bullet" | b"list-level-style-image" => false,
+                    _ => continue,
+                };
+                if let Some(level) = attribute(&element, b"level").and_then(|l| l.parse().ok()) {
+                    styles.insert((name.clone(), level), ordered);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(styles)
+}
@@ -0,0 +1,143 @@
+use super::{DocumentLoader, LoadedContent};
+use crate::document::{page_marker, Document, DocumentMetadata, PageSpan};
+use crate::error::{Result, SmartFetchError};
+use pdf_extract::{Dictionary, Document as PdfDocument, Object};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfLoader;
+
+impl DocumentLoader for PdfLoader {
+    fn name(&self) -> &str {
+        "PDF"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/pdf"]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"%PDF-")
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let loaded = load_pdf(bytes)?;
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+/// 从内存中的 PDF 数据逐页提取文本
+///
+/// 每一页前插入页码标记（见 [`page_marker`]），并在元数据中记录各页在文本中的位置
+#[tracing::instrument(level = "debug", skip(bytes), name = "提取PDF文本")]
+pub fn load_pdf(bytes: &[u8]) -> Result<LoadedContent> {
+    // pdf-extract 在遇到损坏或不常见的字体编码时可能 panic，这里转换为普通错误
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| SmartFetchError::DocumentError("PDF 解析失败: 文档结构无法识别".to_string()))?
+        .map_err(|e| SmartFetchError::DocumentError(format!("PDF 解析失败: {}", e)))?;
+
+    if pages.is_empty() {
+        return Err(SmartFetchError::DocumentError(
+            "PDF 文档没有任何页面".to_string(),
+        ));
+    }
+
+    let mut content = String::new();
+    let mut spans = Vec::with_capacity(pages.len());
+    for (index, page_text) in pages.iter().enumerate() {
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        let start = content.len();
+        content.push_str(&page_marker(index + 1));
+        content.push_str("\n\n");
+        content.push_str(page_text.trim());
+        spans.push(PageSpan {
+            number: index + 1,
+            start,
+            end: content.len(),
+        });
+    }
+
+    let mut metadata = DocumentMetadata {
+        page_count: Some(pages.len()),
+        pages: spans,
+        ..Default::default()
+    };
+    if let Some(info) = read_info_dictionary(bytes) {
+        metadata.title = info_string(&info, b"Title");
+        metadata.author = info_string(&info, b"Author");
+        metadata.created_at =
+            info_string(&info, b"CreationDate").map(|date| format_pdf_date(&date));
+        metadata.modified_at = info_string(&info, b"ModDate").map(|date| format_pdf_date(&date));
+    }
+
+    Ok(LoadedContent { content, metadata })
+}
+
+fn read_info_dictionary(bytes: &[u8]) -> Option<Dictionary> {
+    let document = PdfDocument::load_mem(bytes).ok()?;
+    let info = document.trailer.get(b"Info").ok()?;
+    let info = match info {
+        Object::Reference(id) => document.get_object(*id).ok()?,
+        other => other,
+    };
+    info.as_dict().ok().cloned()
+}
+
+fn info_string(info: &Dictionary, key: &[u8]) -> Option<String> {
+    let bytes = match info.get(key).ok()? {
+        Object::String(bytes, _) => bytes,
+        _ => return None,
+    };
+    let text = decode_pdf_string(bytes);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// PDF 文本字符串为带 BOM 的 UTF-16BE 或 PDFDocEncoding（按 Latin-1 处理）
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+/// 将 `D:YYYYMMDDHHmmSS` 格式的 PDF 日期转换为 `YYYY-MM-DD HH:mm:SS`
+fn format_pdf_date(raw: &str) -> String {
+    let digits: String = raw
+        .trim_start_matches("D:")
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    if digits.len() < 8 {
+        return raw.to_string();
+    }
+
+    let mut formatted = format!("{}-{}-{}", &digits[0..4], &digits[4..6], &digits[6..8]);
+    if digits.len() >= 14 {
+        formatted.push_str(&format!(
+            " {}:{}:{}",
+            &digits[8..10],
+            &digits[10..12],
+            &digits[12..14]
+        ));
+    }
+    formatted
+}
@@ -0,0 +1,150 @@
+use super::docx::DocxLoader;
+use super::epub::EpubLoader;
+use super::odt::OdtLoader;
+use super::pdf::PdfLoader;
+use super::text::{HtmlLoader, TextLoader};
+use crate::config::ProcessingConfig;
+use crate::document::Document;
+use crate::error::Result;
+use crate::html::HtmlConverter;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 文档加载器：识别一种文档格式并将其转换为 [`Document`]
+///
+/// 库的使用者可以实现该 trait 并注册到 [`LoaderRegistry`]，无需修改本 crate 即可支持新格式
+pub trait DocumentLoader: Send + Sync {
+    /// 加载器名称，用于日志和格式列表
+    fn name(&self) -> &str;
+
+    /// 支持的文件扩展名（小写，不含点）
+    fn extensions(&self) -> &[&str];
+
+    /// 支持的 MIME 类型，第一个作为加载后文档的 `content_type`
+    fn mime_types(&self) -> &[&str];
+
+    /// 根据文件内容（魔数）判断是否为该格式，用于扩展名缺失或无法识别的文件
+    fn matches_content(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document>;
+
+    fn content_type(&self) -> &str {
+        self.mime_types().first().copied().unwrap_or("text/plain")
+    }
+}
+
+/// 已注册的文档加载器集合
+///
+/// 查找时后注册的加载器优先，因此可以用自定义加载器覆盖内置格式
+#[derive(Clone, Default)]
+pub struct LoaderRegistry {
+    loaders: Vec<Arc<dyn DocumentLoader>>,
+}
+
+impl LoaderRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建包含所有内置格式的注册表
+    pub fn with_builtin(config: &ProcessingConfig) -> Self {
+        let html_converter = HtmlConverter::new(config.html.clone().unwrap_or_default());
+
+        let mut registry = Self::new();
+        registry
+            .register(TextLoader::new("纯文本", &["txt"], &["text/plain"]))
+            .register(TextLoader::new(
+                "Markdown",
+                &["md", "markdown"],
+                &["text/markdown"],
+            ))
+            .register(TextLoader::new("JSON", &["json"], &["application/json"]))
+            .register(TextLoader::new(
+                "YAML",
+                &["yaml", "yml"],
+                &["text/yaml", "application/yaml"],
+            ))
+            .register(TextLoader::new(
+                "TOML",
+                &["toml"],
+                &["text/toml", "application/toml"],
+            ))
+            .register(TextLoader::new(
+                "XML",
+                &["xml"],
+                &["application/xml", "text/xml"],
+            ))
+            .register(TextLoader::new("CSV", &["csv"], &["text/csv"]))
+            .register(HtmlLoader::new(html_converter.clone()))
+            .register(PdfLoader)
+            .register(DocxLoader)
+            .register(OdtLoader)
+            .register(EpubLoader::new(html_converter));
+        registry
+    }
+
+    pub fn register(&mut self, loader: impl DocumentLoader + 'static) -> &mut Self {
+        self.loaders.push(Arc::new(loader));
+        self
+    }
+
+    /// 按优先级（后注册优先）遍历加载器
+    pub fn loaders(&self) -> impl Iterator<Item = &dyn DocumentLoader> {
+        self.loaders.iter().rev().map(|loader| loader.as_ref())
+    }
+
+    pub fn find_by_extension(&self, extension: &str) -> Option<&dyn DocumentLoader> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.loaders()
+            .find(|loader| loader.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn find_by_mime(&self, mime_type: &str) -> Option<&dyn DocumentLoader> {
+        // 忽略 `; charset=utf-8` 等参数
+        let mime_type = mime_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_lowercase();
+        self.loaders()
+            .find(|loader| loader.mime_types().contains(&mime_type.as_str()))
+    }
+
+    pub fn find_by_content(&self, bytes: &[u8]) -> Option<&dyn DocumentLoader> {
+        self.loaders().find(|loader| loader.matches_content(bytes))
+    }
+
+    /// 为文件选择加载器：先按扩展名，再按文件内容，最后退回到纯文本
+    pub fn detect(&self, path: &Path, bytes: &[u8]) -> Option<&dyn DocumentLoader> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.find_by_extension(ext))
+            .or_else(|| self.find_by_content(bytes))
+            .or_else(|| self.find_by_mime("text/plain"))
+    }
+
+    /// 所有已注册加载器支持的扩展名（去重并排序）
+    pub fn supported_formats(&self) -> Vec<String> {
+        let mut formats: Vec<String> = self
+            .loaders
+            .iter()
+            .flat_map(|loader| loader.extensions().iter().map(|ext| ext.to_string()))
+            .collect();
+        formats.sort();
+        formats.dedup();
+        formats
+    }
+}
+
+impl fmt::Debug for LoaderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.loaders.iter().map(|loader| loader.name()))
+            .finish()
+    }
+}
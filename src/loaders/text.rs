@@ -0,0 +1,113 @@
+use super::registry::DocumentLoader;
+use super::LoadedContent;
+use crate::document::{Document, DocumentMetadata};
+use crate::error::{Result, SmartFetchError};
+use crate::html::HtmlConverter;
+use std::path::Path;
+
+/// 按 UTF-8 读取的纯文本类格式（TXT、Markdown、JSON 等）
+#[derive(Debug, Clone)]
+pub struct TextLoader {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    mime_types: &'static [&'static str],
+}
+
+impl TextLoader {
+    pub fn new(
+        name: &'static str,
+        extensions: &'static [&'static str],
+        mime_types: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            extensions,
+            mime_types,
+        }
+    }
+}
+
+impl DocumentLoader for TextLoader {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        self.mime_types
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let loaded = LoadedContent {
+            content: decode_text(path, bytes)?,
+            metadata: DocumentMetadata::default(),
+        };
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+/// HTML 页面，转换为 Markdown 并读取 `<title>`
+#[derive(Debug, Clone)]
+pub struct HtmlLoader {
+    html_converter: HtmlConverter,
+}
+
+impl HtmlLoader {
+    pub fn new(html_converter: HtmlConverter) -> Self {
+        Self { html_converter }
+    }
+}
+
+impl DocumentLoader for HtmlLoader {
+    fn name(&self) -> &str {
+        "HTML"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["text/html", "application/xhtml+xml"]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+        let head = head.trim_start_matches('\u{FEFF}').trim_start();
+        head.starts_with("<!doctype html") || head.starts_with("<html")
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let raw_content = decode_text(path, bytes)?;
+        let loaded = LoadedContent {
+            content: self.html_converter.convert(&raw_content),
+            metadata: DocumentMetadata {
+                title: HtmlConverter::extract_title(&raw_content),
+                ..Default::default()
+            },
+        };
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+fn decode_text(path: &Path, bytes: &[u8]) -> Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| {
+        SmartFetchError::DocumentError(format!(
+            "无法读取文件内容: {:?} 不是有效的 UTF-8 文本，可能是不支持的二进制格式",
+            path
+        ))
+    })
+}
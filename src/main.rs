@@ -1,8 +1,15 @@
-use clap::{Parser, Subcommand};
-use mcp_smart_fetch::{AppConfig, McpSmartFetchServer, SmartFetchService};
-use std::path::PathBuf;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use mcp_smart_fetch::{
+    AppConfig, BatchOptions, ExtractionProgress, ExtractionSource, FileStatus, HotReloader,
+    LlmOutput, McpSmartFetchServer, ProgressCallback, ResponseCache, ServiceHandle,
+    SmartFetchService, BATCH_REPORT_FILE, SSE_PATH, STREAMABLE_HTTP_PATH,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::info;
-use tracing_indicatif::{IndicatifLayer, indicatif_println};
+use tracing_indicatif::{IndicatifLayer, indicatif_println, suspend_tracing_indicatif};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
@@ -19,6 +26,10 @@ struct Args {
     /// 详细输出
     #[arg(short, long)]
     verbose: bool,
+
+    /// 不读取也不写入响应缓存
+    #[arg(long, global = true)]
+    no_cache: bool,
 }
 
 #[derive(Subcommand)]
@@ -33,6 +44,47 @@ enum Commands {
         /// 输出文件路径
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// 模板名称（默认使用 default_template）
+        #[arg(long)]
+        template: Option<String>,
+        /// 模板变量，格式为 key=value，可重复指定
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, serde_json::Value)>,
+        /// 模型配置名称（对应 llm.profiles）
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// 批量提取目录中的文档，每个文件的结果按目录结构写入输出目录
+    ExtractBatch {
+        /// 输入目录
+        input: PathBuf,
+        /// 输出目录
+        #[arg(short, long)]
+        output: PathBuf,
+        /// 只处理匹配的文件，如 "reports/**/*.pdf"；不含 / 时只匹配文件名，可重复指定
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+        /// 跳过匹配的文件，格式同 --include，可重复指定
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// 同时处理的文件数
+        #[arg(short = 'j', long, default_value_t = 4)]
+        concurrency: usize,
+        /// 自定义提示词
+        #[arg(short, long)]
+        prompt: Option<String>,
+        /// 模板名称（默认使用 default_template）
+        #[arg(long)]
+        template: Option<String>,
+        /// 模板变量，格式为 key=value，可重复指定
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, serde_json::Value)>,
+        /// 模型配置名称（对应 llm.profiles）
+        #[arg(long)]
+        profile: Option<String>,
+        /// 续跑输入、输出目录相同的最近一次任务，只处理未完成、失败或内容有变化的文件
+        #[arg(long)]
+        resume: bool,
     },
     /// 从文本提取内容
     ExtractText {
@@ -45,17 +97,113 @@ enum Commands {
         /// 输出文件路径
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// 模板名称（默认使用 default_template）
+        #[arg(long)]
+        template: Option<String>,
+        /// 模板变量，格式为 key=value，可重复指定
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, serde_json::Value)>,
+        /// 模型配置名称（对应 llm.profiles）
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// 抓取网页并提取内容
+    ExtractUrl {
+        /// 网页地址 (http/https)
+        url: String,
+        /// 自定义提示词
+        #[arg(short, long)]
+        prompt: Option<String>,
+        /// 输出文件路径
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 模板名称（默认使用 default_template）
+        #[arg(long)]
+        template: Option<String>,
+        /// 模板变量，格式为 key=value，可重复指定
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, serde_json::Value)>,
+        /// 模型配置名称（对应 llm.profiles）
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// 按 JSON Schema 提取结构化数据
+    #[command(group(ArgGroup::new("source").required(true).args(["input", "text", "url"])))]
+    ExtractStructured {
+        /// JSON Schema 文件路径
+        #[arg(short, long)]
+        schema: PathBuf,
+        /// 输入文件路径
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// 输入文本
+        #[arg(short, long)]
+        text: Option<String>,
+        /// 网页地址 (http/https)
+        #[arg(short, long)]
+        url: Option<String>,
+        /// 自定义提示词
+        #[arg(short, long)]
+        prompt: Option<String>,
+        /// 输出文件路径
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 模型配置名称（对应 llm.profiles）
+        #[arg(long)]
+        profile: Option<String>,
     },
     /// 启动服务器模式
     Serve {
-        /// 监听端口
-        #[arg(short, long, default_value = "8080")]
-        port: u16,
+        /// 监听端口（默认使用配置文件中的 server.port）
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// 传输方式
+        #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+        transport: Transport,
+    },
+    /// 查看或清理响应缓存
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// 查看批量提取任务
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
     },
     /// 显示支持的环境变量
     EnvVars,
 }
 
+#[derive(Subcommand)]
+enum CacheAction {
+    /// 显示缓存条目数量和占用空间
+    Stats,
+    /// 删除全部缓存条目
+    Clear,
+    /// 删除过期的缓存条目
+    Prune,
+}
+
+#[derive(Subcommand)]
+enum JobsAction {
+    /// 列出批量提取任务，最新的在前
+    List,
+    /// 显示任务中每个文件的状态
+    Show {
+        /// 任务 ID（可以只提供前几位）
+        id: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Transport {
+    /// 标准输入/输出
+    Stdio,
+    /// Streamable HTTP 与旧版 SSE
+    Http,
+}
+
 #[tokio::main]
 #[tracing::instrument(level = "info")]
 async fn main() -> anyhow::Result<()> {
@@ -78,9 +226,11 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     // 加载配置
-    let config = AppConfig::load(&args.config)?;
+    let mut config = AppConfig::load(&args.config)?;
     info!("配置加载成功");
 
+    apply_cli_overrides(&mut config, args.no_cache);
+
     // 显示配置信息 (在 verbose 模式下)
     if args.verbose {
         info!("{}", config.display_info());
@@ -95,41 +245,143 @@ async fn main() -> anyhow::Result<()> {
             input,
             prompt,
             output,
+            template,
+            variables,
+            profile,
         } => {
             info!("开始提取文件内容: {:?}", input);
 
             // 直接调用服务，tracing会自动显示进度条
-            let result = service.extract_content(&input, prompt).await;
+            let streamed = streams_to_terminal(&service, &output, &profile);
+            let result = service
+                .extract_content_with_progress(
+                    &input,
+                    prompt,
+                    template.as_deref(),
+                    template_variables(variables),
+                    profile.as_deref(),
+                    token_printer(streamed),
+                )
+                .await;
+            handle_extraction_result(result, output, streamed).await?;
+        }
+        Commands::ExtractBatch {
+            input,
+            output,
+            include,
+            exclude,
+            concurrency,
+            prompt,
+            template,
+            variables,
+            profile,
+            resume,
+        } => {
+            info!("开始批量提取目录: {:?}", input);
 
-            match result {
-                Ok(result) => {
-                    indicatif_println!("✅ 内容提取成功");
-                    if let Some(output_path) = output {
-                        tokio::fs::write(&output_path, result).await?;
-                        indicatif_println!("✅ 结果已保存到: {:?}", output_path);
-                    } else {
-                        indicatif_println!("📋 提取结果:\n{}", result);
-                    }
-                }
-                Err(e) => {
-                    indicatif_println!("❌ 内容提取失败: {}", e);
-                    return Err(e.into());
-                }
+            let options = BatchOptions {
+                include,
+                exclude,
+                custom_prompt: prompt,
+                template,
+                variables: template_variables(variables),
+                profile,
+                concurrency,
+                resume,
+                ..Default::default()
+            };
+            let report = service.extract_batch(&input, &output, &options).await?;
+            indicatif_println!("📊 批量提取完成: {}", report);
+            for item in report.failures() {
+                indicatif_println!(
+                    "   ❌ {:?}: {}",
+                    item.input,
+                    item.error.as_deref().unwrap_or_default()
+                );
+            }
+            indicatif_println!("📄 报告已保存到: {:?}", output.join(BATCH_REPORT_FILE));
+            if report.failed > 0 {
+                indicatif_println!(
+                    "💡 使用相同参数加 --resume 可只重试失败的文件（任务 {}）",
+                    report.job_id
+                );
+                anyhow::bail!("{}个文件提取失败", report.failed);
             }
         }
         Commands::ExtractText {
             text,
             prompt,
             output,
+            template,
+            variables,
+            profile,
         } => {
             info!("开始提取文本内容");
 
             // 直接调用服务，tracing会自动显示进度条
-            let result = service.extract_from_text(&text, prompt).await;
+            let streamed = streams_to_terminal(&service, &output, &profile);
+            let result = service
+                .extract_from_text_with_progress(
+                    &text,
+                    prompt,
+                    template.as_deref(),
+                    template_variables(variables),
+                    profile.as_deref(),
+                    token_printer(streamed),
+                )
+                .await;
+            handle_extraction_result(result, output, streamed).await?;
+        }
+        Commands::ExtractUrl {
+            url,
+            prompt,
+            output,
+            template,
+            variables,
+            profile,
+        } => {
+            info!("开始提取网页内容: {}", url);
+
+            let streamed = streams_to_terminal(&service, &output, &profile);
+            let result = service
+                .extract_from_url_with_progress(
+                    &url,
+                    prompt,
+                    template.as_deref(),
+                    template_variables(variables),
+                    profile.as_deref(),
+                    token_printer(streamed),
+                )
+                .await;
+            handle_extraction_result(result, output, streamed).await?;
+        }
+        Commands::ExtractStructured {
+            schema,
+            input,
+            text,
+            url,
+            prompt,
+            output,
+            profile,
+        } => {
+            let schema: serde_json::Value =
+                serde_json::from_str(&tokio::fs::read_to_string(&schema).await?)?;
+            let source = match (input, text, url) {
+                (Some(input), _, _) => ExtractionSource::File(input),
+                (_, Some(text), _) => ExtractionSource::Text(text),
+                (_, _, Some(url)) => ExtractionSource::Url(url),
+                _ => unreachable!("clap 保证至少提供一个输入来源"),
+            };
+            info!("开始结构化提取: {:?}", source);
+
+            let result = service
+                .extract_structured(&source, &schema, prompt, profile.as_deref())
+                .await;
 
             match result {
-                Ok(result) => {
-                    indicatif_println!("✅ 内容提取成功");
+                Ok(value) => {
+                    let result = serde_json::to_string_pretty(&value)?;
+                    indicatif_println!("✅ 结构化提取成功");
                     if let Some(output_path) = output {
                         tokio::fs::write(&output_path, result).await?;
                         indicatif_println!("✅ 结果已保存到: {:?}", output_path);
@@ -138,14 +390,20 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
                 Err(e) => {
-                    indicatif_println!("❌ 内容提取失败: {}", e);
+                    indicatif_println!("❌ 结构化提取失败: {}", e);
                     return Err(e.into());
                 }
             }
         }
-        Commands::Serve { port: _ } => {
+        Commands::Serve { port, transport } => {
             info!("启动 MCP 服务器模式");
-            run_mcp_server(service).await?;
+            run_mcp_server(service, &args.config, args.no_cache, port, transport).await?;
+        }
+        Commands::Cache { action } => {
+            manage_cache(&service, action)?;
+        }
+        Commands::Jobs { action } => {
+            manage_jobs(&service, action)?;
         }
         Commands::EnvVars => {
             show_env_variables();
@@ -155,6 +413,193 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 解析 `--var key=value` 参数，值按模板头部声明的类型转换
+fn parse_variable(arg: &str) -> Result<(String, serde_json::Value), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("模板变量格式应为 key=value: {}", arg))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("模板变量名不能为空: {}", arg));
+    }
+    Ok((key.to_string(), serde_json::Value::String(value.to_string())))
+}
+
+fn template_variables(
+    variables: Vec<(String, serde_json::Value)>,
+) -> Option<HashMap<String, serde_json::Value>> {
+    (!variables.is_empty()).then(|| variables.into_iter().collect())
+}
+
+/// 结果直接输出到终端且所选模型配置启用了流式输出时，边接收边打印
+fn streams_to_terminal(
+    service: &SmartFetchService,
+    output: &Option<PathBuf>,
+    profile: &Option<String>,
+) -> bool {
+    output.is_none()
+        && service
+            .llm_client(profile.as_deref())
+            .is_ok_and(|client| client.get_config().stream.unwrap_or(true))
+}
+
+/// 将流式生成的文本片段实时打印到终端
+fn token_printer(enabled: bool) -> Option<ProgressCallback> {
+    if !enabled {
+        return None;
+    }
+    Some(Arc::new(|event: &ExtractionProgress| {
+        if let Some(delta) = &event.delta {
+            suspend_tracing_indicatif(|| {
+                print!("{}", delta);
+                let _ = std::io::stdout().flush();
+            });
+        }
+    }))
+}
+
+/// 输出提取结果；流式响应中断时保留已接收的部分结果
+async fn handle_extraction_result(
+    result: mcp_smart_fetch::Result<LlmOutput>,
+    output: Option<PathBuf>,
+    streamed: bool,
+) -> anyhow::Result<()> {
+    if streamed {
+        println!();
+    }
+
+    match result {
+        Ok(LlmOutput {
+            content: result,
+            model,
+            cached,
+            ..
+        }) => {
+            if cached {
+                indicatif_println!("✅ 内容提取成功 (模型: {}，来自缓存)", model);
+            } else {
+                indicatif_println!("✅ 内容提取成功 (模型: {})", model);
+            }
+            if let Some(output_path) = output {
+                tokio::fs::write(&output_path, result).await?;
+                indicatif_println!("✅ 结果已保存到: {:?}", output_path);
+            } else if !streamed {
+                indicatif_println!("📋 提取结果:\n{}", result);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            indicatif_println!("❌ 内容提取失败: {}", e);
+            if let Some(partial) = e.partial_output() {
+                if let Some(output_path) = &output {
+                    tokio::fs::write(output_path, partial).await?;
+                    indicatif_println!("💾 中断前的部分结果已保存到: {:?}", output_path);
+                } else if !streamed {
+                    indicatif_println!("📋 中断前的部分结果:\n{}", partial);
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// 执行 `cache` 子命令
+fn manage_cache(service: &SmartFetchService, action: CacheAction) -> anyhow::Result<()> {
+    let cache_config = service.config().get_cache_config();
+    let cache = ResponseCache::new(&cache_config);
+
+    match action {
+        CacheAction::Stats => {
+            let stats = cache.stats()?;
+            println!("💾 响应缓存: {:?}", cache.dir());
+            println!(
+                "   状态: {}",
+                if cache_config.enabled.unwrap_or(false) { "已启用" } else { "未启用" }
+            );
+            println!("   条目数: {} (已过期 {})", stats.entries, stats.expired);
+            println!("   占用空间: {:.2} MB", stats.size_bytes as f64 / 1024.0 / 1024.0);
+            if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                println!("   最早写入: {}", oldest.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"));
+                println!("   最近写入: {}", newest.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"));
+            }
+        }
+        CacheAction::Clear => {
+            let removed = cache.clear()?;
+            println!("🧹 已删除{}个缓存条目", removed);
+        }
+        CacheAction::Prune => {
+            let removed = cache.prune()?;
+            println!("🧹 已删除{}个过期缓存条目", removed);
+        }
+    }
+    Ok(())
+}
+
+fn manage_jobs(service: &SmartFetchService, action: JobsAction) -> anyhow::Result<()> {
+    let store = service.job_store();
+    let format_time = |time: chrono::DateTime<chrono::Utc>| {
+        time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+    };
+
+    match action {
+        JobsAction::List => {
+            let jobs = store.list()?;
+            if jobs.is_empty() {
+                println!("📦 没有批量提取任务: {:?}", store.dir());
+            }
+            for job in jobs {
+                println!(
+                    "{}  {}  {}  成功 {} / 失败 {} / 待处理 {}  {:?} → {:?}",
+                    &job.id[..8],
+                    format_time(job.created_at),
+                    job.status,
+                    job.count(FileStatus::Succeeded),
+                    job.count(FileStatus::Failed),
+                    job.count(FileStatus::Pending),
+                    job.input_dir,
+                    job.output_dir
+                );
+            }
+        }
+        JobsAction::Show { id } => {
+            let job = store.load(&id)?;
+            println!("📦 任务 {}", job.id);
+            println!("   状态: {}", job.status);
+            println!("   创建时间: {}", format_time(job.created_at));
+            println!("   更新时间: {}", format_time(job.updated_at));
+            println!("   输入目录: {:?}", job.input_dir);
+            println!("   输出目录: {:?}", job.output_dir);
+            println!("   模板: {}", job.options.template);
+            if let Some(profile) = &job.options.profile {
+                println!("   模型配置: {}", profile);
+            }
+            println!(
+                "   文件: 共 {}，成功 {}，失败 {}，待处理 {}",
+                job.files.len(),
+                job.count(FileStatus::Succeeded),
+                job.count(FileStatus::Failed),
+                job.count(FileStatus::Pending)
+            );
+            for file in &job.files {
+                match file.status {
+                    FileStatus::Succeeded => println!(
+                        "   ✅ {:?} → {:?}",
+                        file.input,
+                        file.output.as_deref().unwrap_or(Path::new(""))
+                    ),
+                    FileStatus::Failed => println!(
+                        "   ❌ {:?}: {}",
+                        file.input,
+                        file.error.as_deref().unwrap_or_default()
+                    ),
+                    FileStatus::Pending => println!("   ⏳ {:?}", file.input),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn show_env_variables() {
     use mcp_smart_fetch::AppConfig;
 
@@ -164,22 +609,52 @@ fn show_env_variables() {
     let env_vars = AppConfig::get_env_variables_info();
 
     println!("\n🔧 LLM 配置:");
-    for (var, desc) in env_vars.iter().take(6) {
+    for (var, desc) in env_vars.iter().take(16) {
         println!("   {:<30} - {}", var, desc);
     }
 
     println!("\n🌐 服务器配置:");
-    for (var, desc) in env_vars.iter().skip(6).take(4) {
+    for (var, desc) in env_vars.iter().skip(16).take(7) {
         println!("   {:<30} - {}", var, desc);
     }
 
     println!("\n📄 处理配置:");
-    for (var, desc) in env_vars.iter().skip(10).take(5) {
+    for (var, desc) in env_vars.iter().skip(23).take(5) {
         println!("   {:<30} - {}", var, desc);
     }
 
     println!("\n🧹 清理配置:");
-    for (var, desc) in env_vars.iter().skip(15) {
+    for (var, desc) in env_vars.iter().skip(28).take(6) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n🧩 分块提取配置:");
+    for (var, desc) in env_vars.iter().skip(34).take(4) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n🌍 网页抓取与 HTML 配置:");
+    for (var, desc) in env_vars.iter().skip(38).take(5) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n🧾 结构化提取配置:");
+    for (var, desc) in env_vars.iter().skip(43).take(3) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n💾 响应缓存配置:");
+    for (var, desc) in env_vars.iter().skip(46).take(4) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n🔢 Token 计数配置:");
+    for (var, desc) in env_vars.iter().skip(50).take(3) {
+        println!("   {:<30} - {}", var, desc);
+    }
+
+    println!("\n📦 任务配置:");
+    for (var, desc) in env_vars.iter().skip(53) {
         println!("   {:<30} - {}", var, desc);
     }
 
@@ -198,21 +673,67 @@ fn show_env_variables() {
     println!("\n📖 更多信息请参考 .env.example 文件");
 }
 
-async fn run_mcp_server(service: SmartFetchService) -> anyhow::Result<()> {
+/// 命令行参数对配置的调整，热重载后会再次应用
+fn apply_cli_overrides(config: &mut AppConfig, no_cache: bool) {
+    if no_cache {
+        config.cache.get_or_insert_default().enabled = Some(false);
+    }
+}
+
+async fn run_mcp_server(
+    service: SmartFetchService,
+    config_path: &Path,
+    no_cache: bool,
+    port: Option<u16>,
+    transport: Transport,
+) -> anyhow::Result<()> {
     info!("初始化 MCP 服务器...");
 
-    let mcp_server = McpSmartFetchServer::new(service);
+    let mut server_config = service.config().server.clone();
+    if let Some(port) = port {
+        server_config.port = port;
+    }
+
+    let handle = ServiceHandle::new(service);
+    if server_config.hot_reload.unwrap_or(true) {
+        HotReloader::new(config_path, handle.clone())
+            .with_config_override(move |config| apply_cli_overrides(config, no_cache))
+            .spawn();
+        indicatif_println!("👀 已启用热重载: 配置文件 {:?} 和模板目录变更后自动生效", config_path);
+    }
+    let mcp_server = McpSmartFetchServer::with_service_handle(handle);
 
-    info!("启动 MCP 服务器 (stdio 模式)...");
     indicatif_println!("✅ MCP 服务器启动成功");
     indicatif_println!("📋 可用工具:");
     indicatif_println!("   - extract_from_file: 从文件提取智能内容");
     indicatif_println!("   - extract_from_text: 从文本提取智能内容");
+    indicatif_println!("   - extract_from_url: 抓取网页并提取智能内容");
+    indicatif_println!("   - extract_structured: 按 JSON Schema 提取结构化数据");
+    indicatif_println!("   - submit_extraction: 提交后台提取任务，立即返回任务 ID");
+    indicatif_println!("   - get_job_status: 查询后台任务状态和进度");
+    indicatif_println!("   - get_job_result: 获取已完成后台任务的结果");
+    indicatif_println!("   - cancel_job: 取消排队中或运行中的后台任务");
+    indicatif_println!("   - list_templates: 列出可用的提示词模板");
+    indicatif_println!("   - get_template: 获取提示词模板的说明和内容");
     indicatif_println!("   - get_config: 获取服务器配置信息");
     indicatif_println!("   - list_supported_formats: 列出支持的文档格式");
-    indicatif_println!("🔌 使用标准输入/输出通信，等待客户端连接...");
+    indicatif_println!("📚 资源: template://（提示词模板）、file://（允许目录下的文档）、result://（提取结果）");
+    indicatif_println!("💬 提示词: 每个模板都可通过 prompts/get 渲染为对话消息");
 
-    mcp_server.run_stdio().await?;
+    match transport {
+        Transport::Stdio => {
+            info!("启动 MCP 服务器 (stdio 模式)...");
+            indicatif_println!("🔌 使用标准输入/输出通信，等待客户端连接...");
+            mcp_server.run_stdio().await?;
+        }
+        Transport::Http => {
+            info!("启动 MCP 服务器 (HTTP 模式)...");
+            let base_url = format!("http://{}:{}", server_config.host, server_config.port);
+            indicatif_println!("🌐 Streamable HTTP 端点: {}{}", base_url, STREAMABLE_HTTP_PATH);
+            indicatif_println!("🌐 SSE 端点: {}{}", base_url, SSE_PATH);
+            mcp_server.run_http(&server_config).await?;
+        }
+    }
 
     Ok(())
 }
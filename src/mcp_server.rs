@@ -1,19 +1,52 @@
-use crate::SmartFetchService;
+use crate::resources::{
+    file_uri, file_uri_path, result_uri, template_uri, FILE_URI_PREFIX, RESULT_URI_PREFIX,
+    TEMPLATE_URI_PREFIX,
+};
+use crate::{
+    ChatMessage, ExtractionProgress, ExtractionSource, ExtractionStage, LlmOutput,
+    ProgressCallback, QueuedJobOutcome, QueuedJobStatus, ServiceHandle, SmartFetchError,
+    SmartFetchService, TemplateInfo,
+};
 use rmcp::{
     handler::server::{router::tool::ToolRouter},
     model::{ErrorData as McpError, *},
-    schemars, tool, tool_handler, tool_router, ServerHandler, ServiceExt,
+    schemars, tool, tool_handler, tool_router, Peer, RoleServer, ServerHandler, ServiceExt,
+    service::{NotificationContext, RequestContext},
     transport::stdio,
     handler::server::wrapper::Parameters,
 };
 use serde::Deserialize;
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
 
 type McpResult<T> = std::result::Result<T, McpError>;
 
+/// 流式生成阶段两次进度通知之间至少新增的字符数，避免逐个 token 发送通知
+const GENERATING_NOTIFY_CHARS: usize = 200;
+
+const TEMPLATE_MIME_TYPE: &str = "text/x-handlebars-template";
+/// 文档资源返回加载并预处理后的文本
+const DOCUMENT_MIME_TYPE: &str = "text/plain";
+const RESULT_MIME_TYPE: &str = "text/markdown";
+
+/// 模板提示词中传入文档内容的参数
+const CONTENT_ARGUMENT: &str = "content";
+/// 模板提示词中传入自定义提示词的参数，对应提取工具的 `prompt` 参数
+const PROMPT_ARGUMENT: &str = "prompt";
+
 #[derive(Debug, Clone)]
 pub struct McpSmartFetchServer {
-    service: Arc<SmartFetchService>,
+    /// 热重载时整体替换的服务实例
+    service: ServiceHandle,
+    request_timeout: Option<Duration>,
+    /// 当前会话订阅的资源 URI，每个会话各自一份，见 [`McpSmartFetchServer::for_session`]
+    subscriptions: Arc<Mutex<HashSet<String>>>,
     tool_router: ToolRouter<McpSmartFetchServer>,
 }
 
@@ -23,6 +56,12 @@ pub struct ExtractFromFileRequest {
     pub file_path: String,
     #[schemars(description = "自定义提示词")]
     pub prompt: Option<String>,
+    #[schemars(description = "模板名称（默认使用 default_template，可用模板见 list_templates）")]
+    pub template: Option<String>,
+    #[schemars(description = "模板变量（模板头部声明的自定义变量，见 get_template）")]
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    #[schemars(description = "模型配置名称（对应 llm.profiles，默认使用 llm.default_profile）")]
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -31,29 +70,224 @@ pub struct ExtractFromTextRequest {
     pub text: String,
     #[schemars(description = "自定义提示词")]
     pub prompt: Option<String>,
+    #[schemars(description = "模板名称（默认使用 default_template，可用模板见 list_templates）")]
+    pub template: Option<String>,
+    #[schemars(description = "模板变量（模板头部声明的自定义变量，见 get_template）")]
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    #[schemars(description = "模型配置名称（对应 llm.profiles，默认使用 llm.default_profile）")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExtractFromUrlRequest {
+    #[schemars(description = "网页地址 (http/https)")]
+    pub url: String,
+    #[schemars(description = "自定义提示词")]
+    pub prompt: Option<String>,
+    #[schemars(description = "模板名称（默认使用 default_template，可用模板见 list_templates）")]
+    pub template: Option<String>,
+    #[schemars(description = "模板变量（模板头部声明的自定义变量，见 get_template）")]
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    #[schemars(description = "模型配置名称（对应 llm.profiles，默认使用 llm.default_profile）")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExtractStructuredRequest {
+    #[schemars(description = "文件路径（与 text、url 三选一）")]
+    pub file_path: Option<String>,
+    #[schemars(description = "输入文本（与 file_path、url 三选一）")]
+    pub text: Option<String>,
+    #[schemars(description = "网页地址（与 file_path、text 三选一）")]
+    pub url: Option<String>,
+    #[schemars(description = "输出需要符合的 JSON Schema")]
+    pub schema: serde_json::Value,
+    #[schemars(description = "自定义提示词")]
+    pub prompt: Option<String>,
+    #[schemars(description = "模型配置名称（对应 llm.profiles，默认使用 llm.default_profile）")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SubmitExtractionRequest {
+    #[schemars(description = "文件路径（与 text、url 三选一）")]
+    pub file_path: Option<String>,
+    #[schemars(description = "输入文本（与 file_path、url 三选一）")]
+    pub text: Option<String>,
+    #[schemars(description = "网页地址（与 file_path、text 三选一）")]
+    pub url: Option<String>,
+    #[schemars(description = "自定义提示词")]
+    pub prompt: Option<String>,
+    #[schemars(description = "模板名称（默认使用 default_template，可用模板见 list_templates）")]
+    pub template: Option<String>,
+    #[schemars(description = "模板变量（模板头部声明的自定义变量，见 get_template）")]
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    #[schemars(description = "模型配置名称（对应 llm.profiles，默认使用 llm.default_profile）")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct JobIdRequest {
+    #[schemars(description = "submit_extraction 返回的任务 ID")]
+    pub job_id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetTemplateRequest {
+    #[schemars(description = "模板名称")]
+    pub name: String,
+}
+
+impl ExtractStructuredRequest {
+    fn source(&self) -> Result<ExtractionSource, String> {
+        extraction_source(&self.file_path, &self.text, &self.url)
+    }
+}
+
+impl SubmitExtractionRequest {
+    fn source(&self) -> Result<ExtractionSource, String> {
+        extraction_source(&self.file_path, &self.text, &self.url)
+    }
+}
+
+fn extraction_source(
+    file_path: &Option<String>,
+    text: &Option<String>,
+    url: &Option<String>,
+) -> Result<ExtractionSource, String> {
+    match (file_path, text, url) {
+        (Some(path), None, None) => Ok(ExtractionSource::File(PathBuf::from(path))),
+        (None, Some(text), None) => Ok(ExtractionSource::Text(text.clone())),
+        (None, None, Some(url)) => Ok(ExtractionSource::Url(url.clone())),
+        _ => Err("file_path、text、url 必须且只能提供一个".to_string()),
+    }
 }
 
 #[tool_router]
 impl McpSmartFetchServer {
     pub fn new(service: SmartFetchService) -> Self {
+        Self::with_service_handle(ServiceHandle::new(service))
+    }
+
+    /// 使用可替换的服务实例，配合 [`crate::HotReloader`] 在运行中重新加载配置和模板
+    pub fn with_service_handle(service: ServiceHandle) -> Self {
+        // 服务器设置不参与热重载
+        let request_timeout = service
+            .current()
+            .config()
+            .server
+            .request_timeout_seconds
+            .map(Duration::from_secs);
+
         Self {
-            service: Arc::new(service),
+            service,
+            request_timeout,
+            subscriptions: Arc::default(),
             tool_router: Self::tool_router(),
         }
     }
 
+    /// 为新的客户端会话创建服务器实例，共享服务实例但不共享资源订阅
+    pub fn for_session(&self) -> Self {
+        Self {
+            subscriptions: Arc::default(),
+            ..self.clone()
+        }
+    }
+
     #[tool(description = "从文件提取智能内容")]
     async fn extract_from_file(
         &self,
         Parameters(request): Parameters<ExtractFromFileRequest>,
+        meta: Meta,
+        peer: Peer<RoleServer>,
     ) -> McpResult<CallToolResult> {
         let path = PathBuf::from(request.file_path);
+        let progress = progress_notifier(&meta, peer);
 
-        match self.service.extract_content(&path, request.prompt).await {
-            Ok(result) => {
-                let content = Content::text(result);
-                Ok(CallToolResult::success(vec![content]))
+        let service = self.service.current();
+        let result = self
+            .with_request_timeout(service.extract_content_with_progress(
+                &path,
+                request.prompt,
+                request.template.as_deref(),
+                request.variables,
+                request.profile.as_deref(),
+                progress,
+            ))
+            .await;
+        Ok(recorded_result(&service, ExtractionSource::File(path), result))
+    }
+
+    #[tool(description = "从文本提取智能内容")]
+    async fn extract_from_text(
+        &self,
+        Parameters(request): Parameters<ExtractFromTextRequest>,
+        meta: Meta,
+        peer: Peer<RoleServer>,
+    ) -> McpResult<CallToolResult> {
+        let progress = progress_notifier(&meta, peer);
+
+        let service = self.service.current();
+        let result = self
+            .with_request_timeout(service.extract_from_text_with_progress(
+                &request.text,
+                request.prompt,
+                request.template.as_deref(),
+                request.variables,
+                request.profile.as_deref(),
+                progress,
+            ))
+            .await;
+        Ok(recorded_result(&service, ExtractionSource::Text(request.text), result))
+    }
+
+    #[tool(description = "抓取网页并提取智能内容")]
+    async fn extract_from_url(
+        &self,
+        Parameters(request): Parameters<ExtractFromUrlRequest>,
+        meta: Meta,
+        peer: Peer<RoleServer>,
+    ) -> McpResult<CallToolResult> {
+        let progress = progress_notifier(&meta, peer);
+
+        let service = self.service.current();
+        let result = self
+            .with_request_timeout(service.extract_from_url_with_progress(
+                &request.url,
+                request.prompt,
+                request.template.as_deref(),
+                request.variables,
+                request.profile.as_deref(),
+                progress,
+            ))
+            .await;
+        Ok(recorded_result(&service, ExtractionSource::Url(request.url), result))
+    }
+
+    #[tool(description = "按 JSON Schema 提取结构化数据，结果经过 Schema 校验")]
+    async fn extract_structured(
+        &self,
+        Parameters(request): Parameters<ExtractStructuredRequest>,
+    ) -> McpResult<CallToolResult> {
+        let source = match request.source() {
+            Ok(source) => source,
+            Err(message) => {
+                let error_content = Content::text(format!("参数错误: {}", message));
+                return Ok(CallToolResult::error(vec![error_content]));
             }
+        };
+
+        match self
+            .with_request_timeout(self.service.current().extract_structured(
+                &source,
+                &request.schema,
+                request.prompt,
+                request.profile.as_deref(),
+            ))
+            .await
+        {
+            Ok(value) => Ok(CallToolResult::structured(value)),
             Err(e) => {
                 let error_content = Content::text(format!("提取失败: {}", e));
                 Ok(CallToolResult::error(vec![error_content]))
@@ -61,39 +295,133 @@ impl McpSmartFetchServer {
         }
     }
 
-    #[tool(description = "从文本提取智能内容")]
-    async fn extract_from_text(
+    #[tool(description = "提交后台提取任务并立即返回任务 ID，适合耗时较长的文档；用 get_job_status 查询进度，用 get_job_result 获取结果")]
+    async fn submit_extraction(
         &self,
-        Parameters(request): Parameters<ExtractFromTextRequest>,
+        Parameters(request): Parameters<SubmitExtractionRequest>,
     ) -> McpResult<CallToolResult> {
-        match self.service.extract_from_text(&request.text, request.prompt).await {
-            Ok(result) => {
-                let content = Content::text(result);
-                Ok(CallToolResult::success(vec![content]))
+        let source = match request.source() {
+            Ok(source) => source,
+            Err(message) => {
+                let error_content = Content::text(format!("参数错误: {}", message));
+                return Ok(CallToolResult::error(vec![error_content]));
+            }
+        };
+
+        let service = self.service.current();
+        match service.submit_extraction(
+            source,
+            request.prompt,
+            request.template,
+            request.variables,
+            request.profile,
+        ) {
+            Ok(job_id) => {
+                let job = service.job_queue().status(&job_id);
+                let job_json = serde_json::to_value(job.ok()).unwrap_or_default();
+                Ok(CallToolResult::success(vec![Content::text(job_json.to_string())]))
             }
             Err(e) => {
-                let error_content = Content::text(format!("提取失败: {}", e));
+                let error_content = Content::text(format!("提交失败: {}", e));
                 Ok(CallToolResult::error(vec![error_content]))
             }
         }
     }
 
+    #[tool(description = "查询后台提取任务的状态和完成百分比")]
+    async fn get_job_status(
+        &self,
+        Parameters(request): Parameters<JobIdRequest>,
+    ) -> McpResult<CallToolResult> {
+        match self.service.current().job_queue().status(&request.job_id) {
+            Ok(job) => {
+                let job_json = serde_json::to_value(job).unwrap_or_default();
+                Ok(CallToolResult::success(vec![Content::text(job_json.to_string())]))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        }
+    }
+
+    #[tool(description = "获取已完成的后台提取任务的结果")]
+    async fn get_job_result(
+        &self,
+        Parameters(request): Parameters<JobIdRequest>,
+    ) -> McpResult<CallToolResult> {
+        let (job, outcome) = match self.service.current().job_queue().result(&request.job_id) {
+            Ok(result) => result,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+
+        let result = match outcome {
+            Some(QueuedJobOutcome::Succeeded(output)) => {
+                with_result_uri(extraction_result(Ok(output)), &job.id)
+            }
+            Some(QueuedJobOutcome::Failed {
+                error,
+                partial_output,
+            }) => extraction_error(&error, partial_output.as_deref()),
+            Some(QueuedJobOutcome::Cancelled) => {
+                CallToolResult::error(vec![Content::text("任务已取消".to_string())])
+            }
+            None => CallToolResult::error(vec![Content::text(format!(
+                "任务尚未完成，当前状态: {}（{:.0}%）",
+                job.status, job.progress
+            ))]),
+        };
+        Ok(result)
+    }
+
+    #[tool(description = "取消排队中或运行中的后台提取任务")]
+    async fn cancel_job(
+        &self,
+        Parameters(request): Parameters<JobIdRequest>,
+    ) -> McpResult<CallToolResult> {
+        match self.service.current().job_queue().cancel(&request.job_id) {
+            Ok(job) if job.status == QueuedJobStatus::Cancelled => {
+                let job_json = serde_json::to_value(job).unwrap_or_default();
+                Ok(CallToolResult::success(vec![Content::text(job_json.to_string())]))
+            }
+            Ok(job) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "任务已结束，无法取消，当前状态: {}",
+                job.status
+            ))])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        }
+    }
+
     #[tool(description = "获取服务器配置信息")]
     async fn get_config(&self) -> McpResult<CallToolResult> {
-        let config = self.service.config();
+        let service = self.service.current();
+        let config = service.config();
         let config_json = serde_json::json!({
             "llm": {
+                "provider": config.llm.provider.as_deref().unwrap_or("openai"),
                 "model": config.llm.model,
                 "api_endpoint": config.llm.api_endpoint,
                 "max_tokens": config.llm.max_tokens,
                 "temperature": config.llm.temperature,
+                "default_profile": config.llm.default_profile,
+                // 只列出模型参数，不包含 API 密钥等敏感信息
+                "profiles": config.llm.profile_names().iter().filter_map(|name| {
+                    let profile = config.llm.profile(name).ok()?;
+                    Some(serde_json::json!({
+                        "name": name,
+                        "provider": profile.provider.as_deref().unwrap_or("openai"),
+                        "model": profile.model,
+                        "max_tokens": profile.max_tokens,
+                        "temperature": profile.temperature,
+                        "stream": profile.stream,
+                    }))
+                }).collect::<Vec<_>>(),
             },
             "processing": {
                 "max_document_size_mb": config.processing.max_document_size_mb,
                 "chunk_size": config.processing.chunk_size,
-                "supported_formats": config.processing.supported_formats,
+                "supported_formats": service.supported_formats(),
                 "enable_preprocessing": config.processing.enable_preprocessing,
             },
+            "fetch": config.get_fetch_config(),
+            "cache": config.get_cache_config(),
             "templates_dir": config.templates_dir.to_string_lossy().to_string(),
             "default_template": config.default_template,
         });
@@ -102,11 +430,60 @@ impl McpSmartFetchServer {
         Ok(CallToolResult::success(vec![content]))
     }
 
+    #[tool(description = "列出可用的提示词模板及其说明")]
+    async fn list_templates(&self) -> McpResult<CallToolResult> {
+        let service = self.service.current();
+        let templates_json = serde_json::json!({
+            "default_template": service.config().default_template,
+            "templates": service.template_manager().list_templates(),
+        });
+
+        let content = Content::text(templates_json.to_string());
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    #[tool(description = "获取指定提示词模板的说明和内容")]
+    async fn get_template(
+        &self,
+        Parameters(request): Parameters<GetTemplateRequest>,
+    ) -> McpResult<CallToolResult> {
+        let service = self.service.current();
+        let template_manager = service.template_manager();
+        let (Some(info), Some(source)) = (
+            template_manager.template_info(&request.name),
+            template_manager.template_source(&request.name),
+        ) else {
+            let error = template_manager.unknown_template(&request.name);
+            return Ok(CallToolResult::error(vec![Content::text(error.to_string())]));
+        };
+
+        let template_json = serde_json::json!({
+            "name": info.name,
+            "description": info.description,
+            "variables": info.variables,
+            "content": source,
+        });
+        let content = Content::text(template_json.to_string());
+        Ok(CallToolResult::success(vec![content]))
+    }
+
     #[tool(description = "列出支持的文档格式")]
     async fn list_supported_formats(&self) -> McpResult<CallToolResult> {
-        let formats = self.service.config().processing.supported_formats.clone();
+        let service = self.service.current();
+        let registry = service.loader_registry();
+        let loaders: Vec<_> = registry
+            .loaders()
+            .map(|loader| {
+                serde_json::json!({
+                    "name": loader.name(),
+                    "extensions": loader.extensions(),
+                    "mime_types": loader.mime_types(),
+                })
+            })
+            .collect();
         let formats_json = serde_json::json!({
-            "supported_formats": formats
+            "supported_formats": registry.supported_formats(),
+            "loaders": loaders
         });
 
         let content = Content::text(formats_json.to_string());
@@ -119,7 +496,14 @@ impl ServerHandler for McpSmartFetchServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_prompts_list_changed()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
             server_info: Implementation {
                 name: "mcp-smart-fetch".to_string(),
                 version: "0.1.0".to_string(),
@@ -127,15 +511,456 @@ impl ServerHandler for McpSmartFetchServer {
                 website_url: None,
                 icons: None,
             },
-            instructions: Some("智能文档内容提取服务，支持多种文档格式的智能内容提取。使用 extract_from_file 工具从文件提取内容，或使用 extract_from_text 工具从文本提取内容。".to_string()),
+            instructions: Some("智能文档内容提取服务，支持多种文档格式的智能内容提取。使用 extract_from_file 工具从文件提取内容，使用 extract_from_url 工具从网页提取内容，或使用 extract_from_text 工具从文本提取内容；需要符合 JSON Schema 的结构化结果时使用 extract_structured 工具。处理耗时较长的文档时，可用 submit_extraction 提交后台任务，再通过 get_job_status 查询进度、get_job_result 获取结果，cancel_job 取消任务。提示词模板（template://）、允许目录下的文档（file://）和之前的提取结果（result://）也以资源形式提供，可订阅模板资源以在模板变更时收到通知。每个模板同时作为提示词提供，可通过 prompts/get 传入文档内容渲染为对话消息，不调用模型。可通过 template 参数选择提示词模板（可用模板见 list_templates），模板头部声明的自定义变量通过 variables 参数传入，通过 profile 参数选择模型配置（可用配置见 get_config）。".to_string()),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<ListPromptsResult> {
+        let service = self.service.current();
+        let prompts = service
+            .template_manager()
+            .list_templates()
+            .into_iter()
+            .map(template_prompt)
+            .collect();
+        Ok(ListPromptsResult::with_all_items(prompts))
+    }
+
+    /// 用传入的文档内容和变量渲染模板，返回对话消息，不调用模型
+    async fn get_prompt(
+        &self,
+        GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<GetPromptResult> {
+        let service = self.service.current();
+        let template_manager = service.template_manager();
+        let Some(info) = template_manager.template_info(&name) else {
+            let error = template_manager.unknown_template(&name);
+            return Err(McpError::invalid_params(error.to_string(), None));
+        };
+
+        let mut arguments = arguments.unwrap_or_default();
+        let Some(content) = arguments
+            .remove(CONTENT_ARGUMENT)
+            .and_then(|value| value.as_str().map(str::to_string))
+        else {
+            return Err(McpError::invalid_params(
+                format!("缺少参数: {}", CONTENT_ARGUMENT),
+                None,
+            ));
+        };
+        let custom_prompt = arguments
+            .remove(PROMPT_ARGUMENT)
+            .and_then(|value| value.as_str().map(str::to_string))
+            .filter(|prompt| !prompt.trim().is_empty());
+        let variables: HashMap<String, serde_json::Value> = arguments.into_iter().collect();
+
+        let messages = match service.render_prompt(&name, &content, custom_prompt, variables) {
+            Ok(messages) => messages,
+            Err(e @ (SmartFetchError::TemplateError(_) | SmartFetchError::ValidationError(_))) => {
+                return Err(McpError::invalid_params(e.to_string(), None))
+            }
+            Err(e) => return Err(McpError::internal_error(e.to_string(), None)),
+        };
+        Ok(GetPromptResult {
+            description: Some(info.description),
+            messages: messages.into_iter().map(prompt_message).collect(),
+        })
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<ListResourcesResult> {
+        let service = self.service.current();
+        let template_manager = service.template_manager();
+        let mut resources: Vec<Resource> = template_manager
+            .list_templates()
+            .into_iter()
+            .map(|info| {
+                let mut resource = RawResource::new(template_uri(&info.name), info.name.clone());
+                resource.description = Some(info.description);
+                resource.mime_type = Some(TEMPLATE_MIME_TYPE.to_string());
+                resource.size = template_manager
+                    .template_source(&info.name)
+                    .map(|source| source.len() as u32);
+                resource.no_annotation()
+            })
+            .collect();
+
+        let supported_formats = service.supported_formats();
+        for path in service.resource_roots().list_files(&supported_formats) {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut resource = RawResource::new(file_uri(&path), name);
+            resource.description = Some(path.to_string_lossy().to_string());
+            resource.mime_type = Some(DOCUMENT_MIME_TYPE.to_string());
+            resources.push(resource.no_annotation());
+        }
+
+        for job in service.job_queue().list() {
+            if job.status != QueuedJobStatus::Succeeded {
+                continue;
+            }
+            let mut resource =
+                RawResource::new(result_uri(&job.id), format!("提取结果 {}", job.id));
+            resource.description = Some(job.source);
+            resource.mime_type = Some(RESULT_MIME_TYPE.to_string());
+            resources.push(resource.no_annotation());
+        }
+
+        for result in service.job_queue().recorded_results() {
+            let mut resource =
+                RawResource::new(result_uri(&result.id), format!("提取结果 {}", result.id));
+            resource.description = Some(result.source);
+            resource.mime_type = Some(RESULT_MIME_TYPE.to_string());
+            resources.push(resource.no_annotation());
+        }
+
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<ListResourceTemplatesResult> {
+        let resource_template =
+            |uri_template: String, name: &str, description: &str, mime_type: &str| {
+                RawResourceTemplate {
+                    uri_template,
+                    name: name.to_string(),
+                    title: None,
+                    description: Some(description.to_string()),
+                    mime_type: Some(mime_type.to_string()),
+                }
+                .no_annotation()
+            };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            resource_template(
+                format!("{}{{name}}", TEMPLATE_URI_PREFIX),
+                "template",
+                "提示词模板原文",
+                TEMPLATE_MIME_TYPE,
+            ),
+            resource_template(
+                format!("{}{{path}}", FILE_URI_PREFIX),
+                "file",
+                "允许目录下的文档，返回加载并预处理后的文本",
+                DOCUMENT_MIME_TYPE,
+            ),
+            resource_template(
+                format!("{}{{id}}", RESULT_URI_PREFIX),
+                "result",
+                "之前的提取结果，ID 见提取工具结果的 _meta.result_uri",
+                RESULT_MIME_TYPE,
+            ),
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<ReadResourceResult> {
+        let service = self.service.current();
+        let (text, mime_type) = if let Some(name) = uri.strip_prefix(TEMPLATE_URI_PREFIX) {
+            let template_manager = service.template_manager();
+            let source = template_manager.template_source(name).ok_or_else(|| {
+                McpError::resource_not_found(
+                    template_manager.unknown_template(name).to_string(),
+                    None,
+                )
+            })?;
+            (source.to_string(), TEMPLATE_MIME_TYPE)
+        } else if let Some(id) = uri.strip_prefix(RESULT_URI_PREFIX) {
+            // 同步提取记录的结果和后台任务的结果共用 result:// 前缀
+            let content = match service.job_queue().recorded(id) {
+                Ok(result) => result.output.content,
+                Err(_) => match service.job_queue().result(id) {
+                    Ok((_, Some(QueuedJobOutcome::Succeeded(output)))) => output.content,
+                    Ok((job, _)) => {
+                        let message = format!("任务没有可用的结果，当前状态: {}", job.status);
+                        return Err(McpError::resource_not_found(message, None));
+                    }
+                    Err(e) => return Err(McpError::resource_not_found(e.to_string(), None)),
+                },
+            };
+            (content, RESULT_MIME_TYPE)
+        } else if let Some(path) = file_uri_path(&uri) {
+            match service.read_document(&path).await {
+                Ok(text) => (text, DOCUMENT_MIME_TYPE),
+                Err(e @ SmartFetchError::ValidationError(_)) => {
+                    return Err(McpError::resource_not_found(e.to_string(), None))
+                }
+                Err(e) => return Err(McpError::internal_error(e.to_string(), None)),
+            }
+        } else {
+            return Err(McpError::invalid_params(
+                format!("不支持的资源 URI: {}", uri),
+                None,
+            ));
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri,
+                mime_type: Some(mime_type.to_string()),
+                text,
+                meta: None,
+            }],
+        })
+    }
+
+    /// 只支持订阅模板资源，模板修改或删除后发送 `notifications/resources/updated`
+    async fn subscribe(
+        &self,
+        SubscribeRequestParam { uri }: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<()> {
+        let Some(name) = uri.strip_prefix(TEMPLATE_URI_PREFIX) else {
+            return Err(McpError::invalid_params(
+                format!("只能订阅模板资源: {}", uri),
+                None,
+            ));
+        };
+        let service = self.service.current();
+        let template_manager = service.template_manager();
+        if template_manager.template_source(name).is_none() {
+            return Err(McpError::resource_not_found(
+                template_manager.unknown_template(name).to_string(),
+                None,
+            ));
         }
+        self.lock_subscriptions().insert(uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParam { uri }: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> McpResult<()> {
+        self.lock_subscriptions().remove(&uri);
+        Ok(())
+    }
+
+    /// 会话建立后监听热重载，把模板变更转发为资源通知
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        let mut reloads = self.service.subscribe_reloads();
+        let subscriptions = self.subscriptions.clone();
+        let peer = context.peer;
+
+        tokio::spawn(async move {
+            loop {
+                let summary = match reloads.recv().await {
+                    Ok(summary) => summary,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!("跳过了 {} 次重新加载事件", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                // 会话结束后的第一次重新加载时退出
+                if peer.is_transport_closed() {
+                    return;
+                }
+
+                if !summary.templates_added.is_empty() || !summary.templates_removed.is_empty() {
+                    if let Err(e) = peer.notify_resource_list_changed().await {
+                        tracing::debug!("发送资源列表变更通知失败: {}", e);
+                        return;
+                    }
+                }
+                // 修改模板可能改变提示词的说明和参数
+                if !summary.templates_added.is_empty()
+                    || !summary.templates_removed.is_empty()
+                    || !summary.templates_changed.is_empty()
+                {
+                    if let Err(e) = peer.notify_prompt_list_changed().await {
+                        tracing::debug!("发送提示词列表变更通知失败: {}", e);
+                        return;
+                    }
+                }
+
+                let updated: Vec<String> = summary
+                    .templates_changed
+                    .iter()
+                    .chain(&summary.templates_removed)
+                    .map(|name| template_uri(name))
+                    .filter(|uri| {
+                        subscriptions
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .contains(uri)
+                    })
+                    .collect();
+                for uri in updated {
+                    if let Err(e) = peer
+                        .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                        .await
+                    {
+                        tracing::debug!("发送资源更新通知失败: {}", e);
+                        return;
+                    }
+                }
+            }
+        });
     }
 }
 
 impl McpSmartFetchServer {
+    fn lock_subscriptions(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 按 `server.request_timeout_seconds` 限制单次工具调用的执行时间
+    async fn with_request_timeout<T>(
+        &self,
+        future: impl Future<Output = crate::error::Result<T>>,
+    ) -> crate::error::Result<T> {
+        match self.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, future).await?,
+            None => future.await,
+        }
+    }
+
+    /// 以 stdio 模式运行，工具调用不受 `server.request_timeout_seconds` 限制，由客户端自行决定等待多久
     pub async fn run_stdio(self) -> crate::error::Result<()> {
-        let service = self.serve(stdio()).await.map_err(|e| crate::error::SmartFetchError::Unknown(format!("服务器初始化失败: {}", e)))?;
+        let server = Self {
+            request_timeout: None,
+            ..self
+        };
+        let service = server.serve(stdio()).await.map_err(|e| crate::error::SmartFetchError::Unknown(format!("服务器初始化失败: {}", e)))?;
         service.waiting().await.map_err(|e| crate::error::SmartFetchError::Unknown(format!("服务器运行失败: {}", e)))?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+/// 提取结果转为工具结果，实际应答的模型和 token 用量放在 `_meta` 中；流式响应中断时附上已生成的部分结果
+fn extraction_result(result: crate::error::Result<LlmOutput>) -> CallToolResult {
+    match result {
+        Ok(output) => {
+            let mut meta = Meta::new();
+            meta.0.insert("model".to_string(), output.model.into());
+            meta.0.insert("cached".to_string(), output.cached.into());
+            if let Some(usage) = output.usage {
+                meta.0
+                    .insert("usage".to_string(), serde_json::to_value(usage).unwrap_or_default());
+            }
+            let mut result = CallToolResult::success(vec![Content::text(output.content)]);
+            result.meta = Some(meta);
+            result
+        }
+        Err(e) => extraction_error(&e.to_string(), e.partial_output()),
+    }
+}
+
+/// 模板转为 MCP 提示词：文档内容和自定义提示词之外，模板头部声明的变量也作为参数
+fn template_prompt(info: TemplateInfo) -> Prompt {
+    let mut arguments = vec![
+        PromptArgument {
+            name: CONTENT_ARGUMENT.to_string(),
+            title: None,
+            description: Some("要处理的文档内容".to_string()),
+            required: Some(true),
+        },
+        PromptArgument {
+            name: PROMPT_ARGUMENT.to_string(),
+            title: None,
+            description: Some("附加的自定义提示词".to_string()),
+            required: Some(false),
+        },
+    ];
+    arguments.extend(info.variables.iter().map(|(name, variable)| PromptArgument {
+        name: name.clone(),
+        title: None,
+        description: variable.description.clone(),
+        required: Some(variable.is_required()),
+    }));
+    Prompt::new(info.name, Some(info.description), Some(arguments))
+}
+
+/// MCP 提示词消息只有用户和助手两种角色，系统消息作为用户消息返回
+fn prompt_message(message: ChatMessage) -> PromptMessage {
+    let role = match message.role.as_str() {
+        "assistant" => PromptMessageRole::Assistant,
+        _ => PromptMessageRole::User,
+    };
+    PromptMessage::new_text(role, message.content)
+}
+
+/// 记录成功的提取结果，并在 `_meta.result_uri` 中返回可通过 resources/read 读取的结果资源
+fn recorded_result(
+    service: &SmartFetchService,
+    source: ExtractionSource,
+    result: crate::error::Result<LlmOutput>,
+) -> CallToolResult {
+    match result {
+        Ok(output) => {
+            let id = service.job_queue().record(source.to_string(), output.clone());
+            with_result_uri(extraction_result(Ok(output)), &id)
+        }
+        Err(e) => extraction_result(Err(e)),
+    }
+}
+
+fn with_result_uri(mut result: CallToolResult, id: &str) -> CallToolResult {
+    result
+        .meta
+        .get_or_insert_with(Meta::new)
+        .0
+        .insert("result_uri".to_string(), result_uri(id).into());
+    result
+}
+
+fn extraction_error(error: &str, partial_output: Option<&str>) -> CallToolResult {
+    let mut contents = vec![Content::text(format!("提取失败: {}", error))];
+    if let Some(partial) = partial_output {
+        contents.push(Content::text(format!("中断前的部分结果:\n{}", partial)));
+    }
+    CallToolResult::error(contents)
+}
+
+/// 客户端在请求中提供了 progressToken 时，把提取进度转发为 MCP 进度通知
+///
+/// 回调在提取流程中同步调用，通知经由通道按顺序异步发送
+fn progress_notifier(meta: &Meta, peer: Peer<RoleServer>) -> Option<ProgressCallback> {
+    let progress_token = meta.get_progress_token()?;
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<ExtractionProgress>();
+
+    tokio::spawn(async move {
+        let mut notified_chars = 0;
+        while let Some(event) = receiver.recv().await {
+            if event.stage == ExtractionStage::Generating {
+                if event.generated_chars < notified_chars + GENERATING_NOTIFY_CHARS {
+                    continue;
+                }
+                notified_chars = event.generated_chars;
+            }
+
+            let notification = ProgressNotificationParam {
+                progress_token: progress_token.clone(),
+                progress: event.progress_value(),
+                total: Some(event.total as f64),
+                message: Some(event.message),
+            };
+            if let Err(e) = peer.notify_progress(notification).await {
+                tracing::debug!("发送进度通知失败: {}", e);
+                break;
+            }
+        }
+    });
+
+    Some(Arc::new(move |event: &ExtractionProgress| {
+        let _ = sender.send(event.clone());
+    }))
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// 提取流程所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractionStage {
+    /// 正在对分块逐个调用模板提取
+    Map,
+    /// 正在合并各分块的提取结果
+    Reduce,
+    /// 正在流式接收最终结果
+    Generating,
+    /// 提取完成
+    Completed,
+}
+
+impl fmt::Display for ExtractionStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExtractionStage::Map => "map",
+            ExtractionStage::Reduce => "reduce",
+            ExtractionStage::Generating => "generating",
+            ExtractionStage::Completed => "completed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 提取进度事件
+#[derive(Debug, Clone)]
+pub struct ExtractionProgress {
+    pub stage: ExtractionStage,
+    /// 已完成的步骤数
+    pub completed: usize,
+    /// 总步骤数（分块数，多分块时额外加上一次合并）
+    pub total: usize,
+    pub message: String,
+    /// 流式生成阶段本次新收到的文本片段
+    pub delta: Option<String>,
+    /// 流式生成阶段累计收到的字符数
+    pub generated_chars: usize,
+}
+
+impl ExtractionProgress {
+    /// 完成百分比（0.0-100.0）
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.completed as f64 / self.total as f64 * 100.0).min(100.0)
+    }
+
+    /// 单调递增的进度值（以步骤为单位）
+    ///
+    /// 流式生成时输出长度未知，按已接收字符数在当前步骤内逐渐逼近下一步
+    pub fn progress_value(&self) -> f64 {
+        let step = self.completed as f64;
+        if self.stage != ExtractionStage::Generating {
+            return step;
+        }
+        let chars = self.generated_chars as f64;
+        step + chars / (chars + GENERATING_HALF_CHARS)
+    }
+}
+
+/// 流式生成阶段进度达到当前步骤一半时对应的字符数
+const GENERATING_HALF_CHARS: f64 = 2000.0;
+
+/// 进度回调，在分块处理过程中被调用
+pub type ProgressCallback = Arc<dyn Fn(&ExtractionProgress) + Send + Sync>;
@@ -1,36 +1,74 @@
 use crate::error::{Result, SmartFetchError};
-use handlebars::Handlebars;
+use crate::front_matter::{self, TemplateFrontMatter, TemplateVariable};
+use crate::llm_client::ChatMessage;
+use crate::template_helpers;
+use crate::tokenizer::{EstimateTokenCounter, TokenCounter};
+use handlebars::{Handlebars, RenderErrorReason, Renderable};
 use serde::Serialize;
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 模板中可用的角色块：`{{#system}}`、`{{#user}}`、`{{#assistant}}`
+pub const MESSAGE_ROLES: [&str; 3] = ["system", "user", "assistant"];
+
+/// 角色块在渲染结果中的标记：`ROLE_START 角色 ROLE_NAME_END 内容 ROLE_END`，
+/// 使用 Unicode 私用区字符以免与文档内容冲突
+const ROLE_START: char = '\u{E000}';
+const ROLE_NAME_END: char = '\u{E001}';
+const ROLE_END: char = '\u{E002}';
+
+/// 模板目录下存放局部模板的子目录
+pub const PARTIALS_DIR: &str = "partials";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TemplateData {
     pub content: String,
     pub custom_prompt: Option<String>,
     pub metadata: HashMap<String, String>,
+    /// 模板头部声明的自定义变量，通过 `{{variables.<name>}}` 引用
+    pub variables: HashMap<String, Value>,
+}
+
+/// 模板的名称和用途说明
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, TemplateVariable>,
 }
 
 #[derive(Debug)]
 pub struct TemplateManager {
     handlebars: Handlebars<'static>,
     templates_dir: PathBuf,
+    /// 模板名称到模板原文的映射
+    sources: HashMap<String, String>,
+    /// 模板名称到模板头部的映射，仅包含声明了头部的模板
+    front_matter: HashMap<String, TemplateFrontMatter>,
+    /// 局部模板名称到原文的映射，局部模板来自模板目录下的 `partials/` 子目录
+    partial_sources: HashMap<String, String>,
+    /// `token_count` 助手使用的分词器
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl TemplateManager {
     pub fn new(templates_dir: &Path) -> Result<Self> {
+        let token_counter: Arc<dyn TokenCounter> = Arc::new(EstimateTokenCounter);
         let mut handlebars = Handlebars::new();
         handlebars.set_strict_mode(true);
-
-        // 注册自定义助手
-        handlebars.register_helper("truncate", Box::new(truncate_helper));
-        handlebars.register_helper("word_count", Box::new(word_count_helper));
-        handlebars.register_helper("line_count", Box::new(line_count_helper));
+        register_helpers(&mut handlebars, token_counter.clone());
 
         let mut manager = Self {
             handlebars,
             templates_dir: templates_dir.to_path_buf(),
+            sources: HashMap::new(),
+            front_matter: HashMap::new(),
+            partial_sources: HashMap::new(),
+            token_counter,
         };
 
         // 确保模板目录存在
@@ -44,55 +82,88 @@ impl TemplateManager {
         Ok(manager)
     }
 
+    /// 模板中的 `{{token_count}}` 使用指定的分词器，默认为估算
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        template_helpers::register_token_count_helper(&mut self.handlebars, token_counter.clone());
+        self.token_counter = token_counter;
+        self
+    }
+
     fn load_templates(&mut self) -> Result<()> {
         if !self.templates_dir.exists() {
             return Ok(());
         }
 
+        // 先注册局部模板，模板编译时即可引用
+        let partials_dir = self.templates_dir.join(PARTIALS_DIR);
+        if partials_dir.is_dir() {
+            self.load_partials(&partials_dir, "")?;
+        }
+
         for entry in fs::read_dir(&self.templates_dir)? {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "hbs" || ext == "html" || ext == "mustache" {
-                        let template_name =
-                            path.file_stem().and_then(|s| s.to_str()).ok_or_else(|| {
-                                SmartFetchError::TemplateError(format!(
-                                    "无效的模板文件名: {:?}",
-                                    path
-                                ))
-                            })?;
-
-                        let content = fs::read_to_string(&path).map_err(|e| {
-                            SmartFetchError::TemplateError(format!(
-                                "读取模板文件失败: {} - {}",
-                                template_name, e
-                            ))
-                        })?;
-
-                        self.handlebars
-                            .register_template_string(template_name, content)
-                            .map_err(|e| {
-                                SmartFetchError::TemplateError(format!(
-                                    "注册模板失败: {} - {}",
-                                    template_name, e
-                                ))
-                            })?;
-                    }
-                }
+            if path.is_file() && is_template_file(&path) {
+                let template_name =
+                    path.file_stem().and_then(|s| s.to_str()).ok_or_else(|| {
+                        SmartFetchError::TemplateError(format!(
+                            "无效的模板文件名: {:?}",
+                            path
+                        ))
+                    })?;
+
+                let content = fs::read_to_string(&path).map_err(|e| {
+                    SmartFetchError::TemplateError(format!(
+                        "读取模板文件失败: {} - {}",
+                        template_name, e
+                    ))
+                })?;
+
+                self.register_template_string(template_name, &content)?;
             }
         }
 
         Ok(())
     }
 
+    /// 递归加载局部模板，子目录中的局部模板以 `子目录/名称` 引用
+    fn load_partials(&mut self, dir: &Path, prefix: &str) -> Result<()> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.sort();
+
+        for path in paths {
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let name = format!("{}{}", prefix, stem);
+
+            if path.is_dir() {
+                self.load_partials(&path, &format!("{}/", name))?;
+            } else if is_template_file(&path) {
+                let content = fs::read_to_string(&path).map_err(|e| {
+                    SmartFetchError::TemplateError(format!("读取局部模板失败: {} - {}", name, e))
+                })?;
+                self.register_partial(&name, &content)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn render_template(
         &self,
         template_name: &str,
         content: &str,
         custom_prompt: Option<String>,
     ) -> Result<String> {
+        let template_data = Self::build_template_data(content, custom_prompt);
+        self.render_template_with_data(template_name, &template_data)
+    }
+
+    /// 根据内容构建模板数据，并填充基础统计信息
+    pub fn build_template_data(content: &str, custom_prompt: Option<String>) -> TemplateData {
         let mut metadata = HashMap::new();
         metadata.insert("content_length".to_string(), content.len().to_string());
         metadata.insert(
@@ -104,54 +175,223 @@ impl TemplateManager {
             content.lines().count().to_string(),
         );
 
-        let template_data = TemplateData {
+        TemplateData {
             content: content.to_string(),
             custom_prompt,
             metadata,
-        };
-
-        let rendered = self
-            .handlebars
-            .render(template_name, &template_data)
-            .map_err(|e| {
-                SmartFetchError::TemplateError(format!("渲染模板失败: {} - {}", template_name, e))
-            })?;
-
-        Ok(rendered)
+            variables: HashMap::new(),
+        }
     }
 
+    /// 渲染为纯文本，角色块只保留其中的内容
     pub fn render_template_with_data(
         &self,
         template_name: &str,
         data: &TemplateData,
     ) -> Result<String> {
-        let rendered = self.handlebars.render(template_name, data).map_err(|e| {
+        let rendered = self.render_raw(template_name, data)?;
+        Ok(strip_role_markers(&rendered))
+    }
+
+    /// 渲染为对话消息
+    ///
+    /// 模板头部的 `system` 作为第一条系统消息；正文中的角色块依次成为对应角色的消息，
+    /// 可用 `{{#user}}` 和 `{{#assistant}}` 交替给出 few-shot 示例；角色块之外的非空文本作为用户消息，
+    /// 相邻的同角色消息合并为一条。没有使用角色块的模板整体作为一条用户消息
+    pub fn render_messages(
+        &self,
+        template_name: &str,
+        data: &TemplateData,
+    ) -> Result<Vec<ChatMessage>> {
+        let mut messages = Vec::new();
+        if let Some(system) = self.render_system_prompt(template_name, data)? {
+            push_message(&mut messages, "system", &system);
+        }
+
+        let rendered = self.render_raw(template_name, data)?;
+        if rendered.contains(ROLE_START) {
+            split_role_blocks(template_name, &rendered, &mut messages)?;
+        } else {
+            messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: rendered,
+            });
+        }
+
+        if !messages.iter().any(|message| message.role == "user") {
+            return Err(SmartFetchError::TemplateError(format!(
+                "模板没有生成用户消息: {}",
+                template_name
+            )));
+        }
+        Ok(messages)
+    }
+
+    /// 渲染模板，结果中保留角色块标记
+    fn render_raw(&self, template_name: &str, data: &TemplateData) -> Result<String> {
+        self.handlebars.render(template_name, data).map_err(|e| {
             SmartFetchError::TemplateError(format!("渲染模板失败: {} - {}", template_name, e))
+        })
+    }
+
+    /// 已加载的模板名称（按名称排序），不含局部模板
+    pub fn get_available_templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.sources.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 已加载的局部模板名称（按名称排序）
+    pub fn get_available_partials(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.partial_sources.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 局部模板原文（包含模板头部）
+    pub fn partial_source(&self, partial_name: &str) -> Option<&str> {
+        self.partial_sources.get(partial_name).map(String::as_str)
+    }
+
+    /// 列出所有模板及其说明
+    pub fn list_templates(&self) -> Vec<TemplateInfo> {
+        self.get_available_templates()
+            .into_iter()
+            .filter_map(|name| self.template_info(&name))
+            .collect()
+    }
+
+    /// 模板的说明优先取自模板头部，否则取模板正文中第一行非空文本
+    pub fn template_info(&self, template_name: &str) -> Option<TemplateInfo> {
+        let source = self.template_source(template_name)?;
+        let front_matter = self.front_matter(template_name);
+        let description = match front_matter.and_then(|fm| fm.description.as_deref()) {
+            Some(description) => description.trim().to_string(),
+            None => front_matter::split_front_matter(template_name, source)
+                .map_or(source, |(_, body)| body)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with("{{"))
+                .unwrap_or_default()
+                .to_string(),
+        };
+
+        Some(TemplateInfo {
+            name: template_name.to_string(),
+            description,
+            variables: front_matter
+                .map(|fm| fm.variables.clone())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 模板头部，模板没有声明头部时为 `None`
+    pub fn front_matter(&self, template_name: &str) -> Option<&TemplateFrontMatter> {
+        self.front_matter.get(template_name)
+    }
+
+    /// 按模板头部的声明检查并补全自定义变量，缺少必填变量时返回 `TemplateError`
+    pub fn resolve_variables(
+        &self,
+        template_name: &str,
+        provided: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        front_matter::resolve_variables(template_name, self.front_matter(template_name), provided)
+    }
+
+    /// 渲染模板头部声明的系统提示词
+    pub fn render_system_prompt(
+        &self,
+        template_name: &str,
+        data: &TemplateData,
+    ) -> Result<Option<String>> {
+        let Some(system) = self
+            .front_matter(template_name)
+            .and_then(|fm| fm.system.as_deref())
+        else {
+            return Ok(None);
+        };
+        let rendered = self.handlebars.render_template(system, data).map_err(|e| {
+            SmartFetchError::TemplateError(format!(
+                "渲染系统提示词失败: {} - {}",
+                template_name, e
+            ))
         })?;
+        let rendered = strip_role_markers(&rendered);
+        Ok(Some(rendered.trim().to_string()).filter(|system| !system.is_empty()))
+    }
 
-        Ok(rendered)
+    /// 模板原文（包含模板头部）
+    pub fn template_source(&self, template_name: &str) -> Option<&str> {
+        self.sources.get(template_name).map(String::as_str)
     }
 
-    pub fn get_available_templates(&self) -> Vec<String> {
-        self.handlebars.get_templates().keys().cloned().collect()
+    /// 模板不存在时返回列出可用模板的错误
+    pub fn ensure_template(&self, template_name: &str) -> Result<()> {
+        if self.template_exists(template_name) {
+            Ok(())
+        } else {
+            Err(self.unknown_template(template_name))
+        }
+    }
+
+    pub(crate) fn unknown_template(&self, template_name: &str) -> SmartFetchError {
+        SmartFetchError::TemplateError(format!(
+            "模板不存在: {}（可选 {}）",
+            template_name,
+            self.get_available_templates().join("、")
+        ))
     }
 
     pub fn template_exists(&self, template_name: &str) -> bool {
-        self.handlebars.get_template(template_name).is_some()
+        self.sources.contains_key(template_name)
     }
 
+    /// 注册模板，模板以 `---`（YAML）或 `+++`（TOML）包围的头部开头时解析头部
     pub fn register_template_string(&mut self, name: &str, template: &str) -> Result<()> {
+        if self.partial_sources.contains_key(name) {
+            return Err(SmartFetchError::TemplateError(format!(
+                "模板与局部模板重名: {}",
+                name
+            )));
+        }
+        let (front_matter, body) = front_matter::split_front_matter(name, template)?;
         self.handlebars
-            .register_template_string(name, template)
+            .register_template_string(name, body)
             .map_err(|e| {
                 SmartFetchError::TemplateError(format!("注册模板失败: {} - {}", name, e))
             })?;
+        self.sources.insert(name.to_string(), template.to_string());
+        match front_matter {
+            Some(front_matter) => self.front_matter.insert(name.to_string(), front_matter),
+            None => self.front_matter.remove(name),
+        };
+        Ok(())
+    }
+
+    /// 注册局部模板，模板中以 `{{> 名称}}` 引用；局部模板的头部会被忽略
+    pub fn register_partial(&mut self, name: &str, partial: &str) -> Result<()> {
+        if self.sources.contains_key(name) {
+            return Err(SmartFetchError::TemplateError(format!(
+                "局部模板与模板重名: {}",
+                name
+            )));
+        }
+        let (_, body) = front_matter::split_front_matter(name, partial)?;
+        self.handlebars.register_partial(name, body).map_err(|e| {
+            SmartFetchError::TemplateError(format!("注册局部模板失败: {} - {}", name, e))
+        })?;
+        self.partial_sources
+            .insert(name.to_string(), partial.to_string());
         Ok(())
     }
 
     pub fn reload_templates(&mut self) -> Result<()> {
         // 清除现有模板
         self.handlebars.clear_templates();
+        self.sources.clear();
+        self.front_matter.clear();
+        self.partial_sources.clear();
 
         // 重新加载
         self.load_templates()?;
@@ -159,10 +399,20 @@ impl TemplateManager {
     }
 
     pub fn validate_template(&self, template_content: &str) -> Result<()> {
+        let (_, template_body) =
+            front_matter::split_front_matter("validation_template", template_content)?;
+
         // 创建临时handlebars实例进行验证
         let mut temp_handlebars = Handlebars::new();
+        register_helpers(&mut temp_handlebars, self.token_counter.clone());
+        for (name, partial) in &self.partial_sources {
+            let (_, body) = front_matter::split_front_matter(name, partial)?;
+            temp_handlebars
+                .register_partial(name, body)
+                .map_err(|e| SmartFetchError::TemplateError(format!("模板验证失败: {}", e)))?;
+        }
         temp_handlebars
-            .register_template_string("validation_template", template_content)
+            .register_template_string("validation_template", template_body)
             .map_err(|e| SmartFetchError::TemplateError(format!("模板验证失败: {}", e)))?;
 
         // 尝试渲染以验证语法
@@ -170,6 +420,7 @@ impl TemplateManager {
             content: "测试内容".to_string(),
             custom_prompt: Some("测试提示词".to_string()),
             metadata: HashMap::new(),
+            variables: HashMap::new(),
         };
 
         temp_handlebars
@@ -180,49 +431,98 @@ impl TemplateManager {
     }
 }
 
-// 自定义助手函数
-fn truncate_helper(
-    h: &handlebars::Helper<'_>,
-    _: &Handlebars<'_>,
-    _: &handlebars::Context,
-    _: &mut handlebars::RenderContext<'_, '_>,
-    out: &mut dyn handlebars::Output,
-) -> handlebars::HelperResult {
-    let text = h.param(0).unwrap().value().as_str().unwrap_or("");
-    let limit = h.param(1).unwrap().value().as_u64().unwrap_or(100);
+fn is_template_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "hbs" || ext == "html" || ext == "mustache")
+}
 
-    let truncated = if text.len() > limit as usize {
-        format!("{}...", &text[..limit as usize])
-    } else {
-        text.to_string()
+fn register_helpers(handlebars: &mut Handlebars<'static>, token_counter: Arc<dyn TokenCounter>) {
+    template_helpers::register_helpers(handlebars, token_counter);
+    for role in MESSAGE_ROLES {
+        handlebars.register_helper(role, Box::new(role_helper));
+    }
+}
+
+/// 按角色块标记拆分渲染结果，追加到 `messages`
+fn split_role_blocks(
+    template_name: &str,
+    rendered: &str,
+    messages: &mut Vec<ChatMessage>,
+) -> Result<()> {
+    let malformed = |reason: &str| {
+        SmartFetchError::TemplateError(format!("模板角色块无效: {} - {}", template_name, reason))
     };
 
-    out.write(&truncated)?;
+    let mut rest = rendered;
+    while let Some(start) = rest.find(ROLE_START) {
+        push_message(messages, "user", &rest[..start]);
+        let block = &rest[start + ROLE_START.len_utf8()..];
+        let (role, block) = block
+            .split_once(ROLE_NAME_END)
+            .ok_or_else(|| malformed("缺少角色名称"))?;
+        let (content, after) = block
+            .split_once(ROLE_END)
+            .ok_or_else(|| malformed("角色块未结束"))?;
+        if content.contains(ROLE_START) {
+            return Err(malformed("角色块不能嵌套"));
+        }
+        push_message(messages, role, content);
+        rest = after;
+    }
+    push_message(messages, "user", rest);
     Ok(())
 }
 
-fn word_count_helper(
-    h: &handlebars::Helper<'_>,
-    _: &Handlebars<'_>,
-    _: &handlebars::Context,
-    _: &mut handlebars::RenderContext<'_, '_>,
-    out: &mut dyn handlebars::Output,
-) -> handlebars::HelperResult {
-    let text = h.param(0).unwrap().value().as_str().unwrap_or("");
-    let count = text.split_whitespace().count();
-    out.write(&count.to_string())?;
-    Ok(())
+/// 追加一条消息，内容为空时忽略，与上一条消息角色相同时合并
+fn push_message(messages: &mut Vec<ChatMessage>, role: &str, content: &str) {
+    let content = content.trim();
+    if content.is_empty() {
+        return;
+    }
+    match messages.last_mut() {
+        Some(last) if last.role == role => {
+            last.content.push_str("\n\n");
+            last.content.push_str(content);
+        }
+        _ => messages.push(ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }),
+    }
+}
+
+/// 去除角色块标记和角色名称，只保留内容
+fn strip_role_markers(rendered: &str) -> String {
+    if !rendered.contains(ROLE_START) {
+        return rendered.to_string();
+    }
+    let mut text = String::with_capacity(rendered.len());
+    let mut in_role_name = false;
+    for c in rendered.chars() {
+        match c {
+            ROLE_START => in_role_name = true,
+            ROLE_NAME_END => in_role_name = false,
+            ROLE_END => {}
+            _ if !in_role_name => text.push(c),
+            _ => {}
+        }
+    }
+    text
 }
 
-fn line_count_helper(
-    h: &handlebars::Helper<'_>,
-    _: &Handlebars<'_>,
-    _: &handlebars::Context,
-    _: &mut handlebars::RenderContext<'_, '_>,
+/// `{{#system}}`、`{{#user}}`、`{{#assistant}}`：将块内容标记为对应角色的消息
+fn role_helper<'reg, 'rc>(
+    h: &handlebars::Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc handlebars::Context,
+    rc: &mut handlebars::RenderContext<'reg, 'rc>,
     out: &mut dyn handlebars::Output,
 ) -> handlebars::HelperResult {
-    let text = h.param(0).unwrap().value().as_str().unwrap_or("");
-    let count = text.lines().count();
-    out.write(&count.to_string())?;
+    let template = h
+        .template()
+        .ok_or(RenderErrorReason::BlockContentRequired)?;
+    out.write(&format!("{}{}{}", ROLE_START, h.name(), ROLE_NAME_END))?;
+    template.render(r, ctx, rc, out)?;
+    out.write(&ROLE_END.to_string())?;
     Ok(())
 }
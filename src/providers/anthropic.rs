@@ -0,0 +1,187 @@
+use super::{parse_error, sse_data, ChatRequest, ChatResponse, LlmProvider, StreamEvent};
+use crate::config::LLMConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::{ChatMessage, Usage};
+use serde::{Deserialize, Serialize};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Messages API 要求必须提供 max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<&'a ChatMessage>,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    #[serde(default)]
+    content: Vec<ContentBlock>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+impl From<AnthropicUsage> for Usage {
+    fn from(usage: AnthropicUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+/// 流式事件，按 `type` 字段区分
+#[derive(Debug, Deserialize)]
+struct AnthropicEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    message: Option<MessagesResponse>,
+    delta: Option<serde_json::Value>,
+    usage: Option<AnthropicUsage>,
+    error: Option<serde_json::Value>,
+}
+
+/// Anthropic Messages API：`x-api-key` 认证，系统提示词单独放在 `system` 字段
+#[derive(Debug, Clone)]
+pub struct AnthropicProvider {
+    api_endpoint: String,
+    api_key: Option<String>,
+}
+
+impl AnthropicProvider {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            api_endpoint: config.api_endpoint.clone(),
+            api_key: config.api_key.clone(),
+        }
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn build_request(
+        &self,
+        http_client: &reqwest::Client,
+        request: &ChatRequest,
+    ) -> reqwest::RequestBuilder {
+        let (system, messages): (Vec<_>, Vec<_>) = request
+            .messages
+            .iter()
+            .partition(|message| message.role == "system");
+        let system = system
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        // Messages API 不支持 response_format，结构化输出依赖提示词和本地校验
+        let body = MessagesRequest {
+            model: &request.model,
+            system: (!system.is_empty()).then_some(system),
+            messages,
+            max_tokens: request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            temperature: request.temperature,
+            stream: request.stream,
+        };
+
+        let builder = http_client
+            .post(&self.api_endpoint)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body);
+        match &self.api_key {
+            Some(api_key) => builder.header("x-api-key", api_key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &[u8]) -> Result<ChatResponse> {
+        let response: MessagesResponse =
+            serde_json::from_slice(body).map_err(|e| parse_error(self.name(), e))?;
+        let content: String = response
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect();
+
+        Ok(ChatResponse {
+            content,
+            usage: response.usage.map(Usage::from),
+        })
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Result<Option<StreamEvent>> {
+        // `event:` 行与 `data:` 中的 type 重复，只需解析数据行
+        let Some(data) = sse_data(line) else {
+            return Ok(None);
+        };
+        let event: AnthropicEvent = match serde_json::from_str(data) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("忽略无法解析的流式数据块: {}", e);
+                return Ok(None);
+            }
+        };
+
+        let parsed = match event.kind.as_str() {
+            "message_start" => StreamEvent {
+                usage: event
+                    .message
+                    .and_then(|message| message.usage)
+                    .map(Usage::from),
+                ..Default::default()
+            },
+            "content_block_delta" => StreamEvent {
+                delta: event
+                    .delta
+                    .as_ref()
+                    .and_then(|delta| delta.get("text"))
+                    .and_then(|text| text.as_str())
+                    .map(str::to_string),
+                ..Default::default()
+            },
+            "message_delta" => StreamEvent {
+                usage: event.usage.map(Usage::from),
+                ..Default::default()
+            },
+            "message_stop" => StreamEvent {
+                finished: true,
+                ..Default::default()
+            },
+            "error" => {
+                return Err(SmartFetchError::LlmApiError(format!(
+                    "流式响应返回错误: {}",
+                    event.error.unwrap_or_default()
+                )))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(parsed))
+    }
+}
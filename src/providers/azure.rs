@@ -0,0 +1,72 @@
+use super::openai::{parse_completion, parse_completion_chunk, ChatCompletionRequest};
+use super::{ChatRequest, ChatResponse, LlmProvider, StreamEvent};
+use crate::config::LLMConfig;
+use crate::error::Result;
+
+const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Azure OpenAI：按部署名称路由，使用 `api-key` 头认证，并要求 `api-version` 参数
+#[derive(Debug, Clone)]
+pub struct AzureOpenAiProvider {
+    url: String,
+    api_key: Option<String>,
+}
+
+impl AzureOpenAiProvider {
+    /// `api_endpoint` 为资源地址（如 `https://my-resource.openai.azure.com`），
+    /// 部署名称未配置时使用模型名称；也可以直接填写完整的 chat completions 地址
+    pub fn new(config: &LLMConfig) -> Self {
+        let azure = config.azure.clone().unwrap_or_default();
+        let api_version = azure.api_version.as_deref().unwrap_or(DEFAULT_API_VERSION);
+        let deployment = azure.deployment.as_deref().unwrap_or(&config.model);
+
+        let endpoint = config.api_endpoint.trim_end_matches('/');
+        let base = if endpoint.contains("/chat/completions") {
+            endpoint.to_string()
+        } else {
+            format!(
+                "{}/openai/deployments/{}/chat/completions",
+                endpoint, deployment
+            )
+        };
+        let separator = if base.contains('?') { '&' } else { '?' };
+
+        Self {
+            url: format!("{}{}api-version={}", base, separator, api_version),
+            api_key: config.api_key.clone(),
+        }
+    }
+
+    /// 实际请求的地址
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl LlmProvider for AzureOpenAiProvider {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn build_request(
+        &self,
+        http_client: &reqwest::Client,
+        request: &ChatRequest,
+    ) -> reqwest::RequestBuilder {
+        let builder = http_client
+            .post(&self.url)
+            .json(&ChatCompletionRequest::from(request));
+        match &self.api_key {
+            Some(api_key) => builder.header("api-key", api_key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &[u8]) -> Result<ChatResponse> {
+        parse_completion(self.name(), body)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Result<Option<StreamEvent>> {
+        parse_completion_chunk(line)
+    }
+}
@@ -0,0 +1,99 @@
+mod anthropic;
+mod azure;
+mod ollama;
+mod openai;
+
+pub use anthropic::AnthropicProvider;
+pub use azure::AzureOpenAiProvider;
+pub use ollama::OllamaProvider;
+pub use openai::{ChatCompletionRequest, ChatCompletionResponse, Choice, OpenAiProvider};
+
+use crate::config::LLMConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::{ChatMessage, ResponseFormat, Usage};
+use std::fmt;
+
+/// 支持的提供商名称
+pub const PROVIDER_NAMES: &[&str] = &["openai", "anthropic", "ollama", "azure"];
+
+/// 与提供商无关的对话请求
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+    pub stream: bool,
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// 与提供商无关的完整响应
+#[derive(Debug, Clone)]
+pub struct ChatResponse {
+    pub content: String,
+    pub usage: Option<Usage>,
+}
+
+/// 流式响应中解析出的一个事件
+#[derive(Debug, Clone, Default)]
+pub struct StreamEvent {
+    /// 新收到的文本片段
+    pub delta: Option<String>,
+    /// token 用量（部分提供商分多次给出）
+    pub usage: Option<Usage>,
+    /// 是否为流的最后一个事件
+    pub finished: bool,
+}
+
+/// LLM 服务的协议适配：负责构造请求、解析完整响应和流式数据
+///
+/// 重试、超时和用量统计由 [`LLMClient`](crate::LLMClient) 统一处理，提供商只关心协议本身
+pub trait LlmProvider: Send + Sync + fmt::Debug {
+    /// 提供商名称，用于日志和配置信息
+    fn name(&self) -> &str;
+
+    /// 构造 HTTP 请求：地址、认证头和请求体
+    fn build_request(
+        &self,
+        http_client: &reqwest::Client,
+        request: &ChatRequest,
+    ) -> reqwest::RequestBuilder;
+
+    /// 解析非流式响应体
+    fn parse_response(&self, body: &[u8]) -> Result<ChatResponse>;
+
+    /// 根据 Content-Type 判断响应是否为流式格式，否则按完整响应解析
+    fn is_stream_response(&self, content_type: &str) -> bool {
+        content_type.contains("text/event-stream")
+    }
+
+    /// 解析流式响应中的一行，不含数据的行返回 `None`
+    fn parse_stream_line(&self, line: &str) -> Result<Option<StreamEvent>>;
+}
+
+/// 按 `llm.provider` 创建提供商适配器
+pub fn create_provider(config: &LLMConfig) -> Result<Box<dyn LlmProvider>> {
+    let provider: Box<dyn LlmProvider> = match config.provider.as_deref().unwrap_or("openai") {
+        "openai" => Box::new(OpenAiProvider::new(config)),
+        "anthropic" => Box::new(AnthropicProvider::new(config)),
+        "ollama" => Box::new(OllamaProvider::new(config)),
+        "azure" => Box::new(AzureOpenAiProvider::new(config)),
+        other => {
+            return Err(SmartFetchError::ConfigError(format!(
+                "不支持的 LLM 提供商: {}（可选 {}）",
+                other,
+                PROVIDER_NAMES.join("、")
+            )))
+        }
+    };
+    Ok(provider)
+}
+
+/// 取出 SSE 行中 `data:` 之后的内容
+pub(crate) fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim)
+}
+
+pub(crate) fn parse_error(provider: &str, e: serde_json::Error) -> SmartFetchError {
+    SmartFetchError::SerializationError(format!("解析{}响应失败: {}", provider, e))
+}
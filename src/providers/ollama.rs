@@ -0,0 +1,155 @@
+use super::{parse_error, ChatRequest, ChatResponse, LlmProvider, StreamEvent};
+use crate::config::LLMConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::{ChatMessage, ResponseFormat, Usage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+/// 完整响应和流式响应的每一行使用同一结构
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+}
+
+impl OllamaResponse {
+    fn usage(&self) -> Option<Usage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        let prompt_tokens = self.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = self.eval_count.unwrap_or(0);
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+}
+
+/// Ollama 原生 `/api/chat` 接口，流式响应为逐行 JSON（NDJSON）
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    api_endpoint: String,
+    api_key: Option<String>,
+}
+
+impl OllamaProvider {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            api_endpoint: config.api_endpoint.clone(),
+            api_key: config.api_key.clone(),
+        }
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn build_request(
+        &self,
+        http_client: &reqwest::Client,
+        request: &ChatRequest,
+    ) -> reqwest::RequestBuilder {
+        let format = request.response_format.as_ref().map(|format| match format {
+            ResponseFormat::JsonObject => Value::from("json"),
+            ResponseFormat::JsonSchema { json_schema } => json_schema.schema.clone(),
+        });
+        let body = OllamaRequest {
+            model: &request.model,
+            messages: &request.messages,
+            stream: request.stream,
+            options: OllamaOptions {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+            },
+            format,
+        };
+
+        let builder = http_client.post(&self.api_endpoint).json(&body);
+        // 本地部署通常无需认证，经反向代理暴露时可使用 Bearer
+        match &self.api_key {
+            Some(api_key) => builder.bearer_auth(api_key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &[u8]) -> Result<ChatResponse> {
+        let response: OllamaResponse =
+            serde_json::from_slice(body).map_err(|e| parse_error(self.name(), e))?;
+        if let Some(error) = response.error {
+            return Err(SmartFetchError::LlmApiError(error));
+        }
+
+        Ok(ChatResponse {
+            usage: response.usage(),
+            content: response
+                .message
+                .map(|message| message.content)
+                .unwrap_or_default(),
+        })
+    }
+
+    fn is_stream_response(&self, content_type: &str) -> bool {
+        content_type.contains("ndjson")
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Result<Option<StreamEvent>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let chunk: OllamaResponse = match serde_json::from_str(line) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::warn!("忽略无法解析的流式数据块: {}", e);
+                return Ok(None);
+            }
+        };
+        if let Some(error) = chunk.error {
+            return Err(SmartFetchError::LlmApiError(format!(
+                "流式响应返回错误: {}",
+                error
+            )));
+        }
+
+        Ok(Some(StreamEvent {
+            usage: chunk.usage(),
+            finished: chunk.done,
+            delta: chunk
+                .message
+                .map(|message| message.content)
+                .filter(|content| !content.is_empty()),
+        }))
+    }
+}
@@ -0,0 +1,181 @@
+use super::{parse_error, sse_data, ChatRequest, ChatResponse, LlmProvider, StreamEvent};
+use crate::config::LLMConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::{ChatMessage, ResponseFormat, Usage};
+use serde::{Deserialize, Serialize};
+
+/// OpenAI chat completions 请求体
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+    pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// 流式请求选项：要求服务端在 `[DONE]` 之前的最后一个数据块中返回 token 用量
+#[derive(Debug, Serialize)]
+pub struct StreamOptions {
+    pub include_usage: bool,
+}
+
+impl From<&ChatRequest> for ChatCompletionRequest {
+    fn from(request: &ChatRequest) -> Self {
+        Self {
+            model: request.model.clone(),
+            messages: request.messages.clone(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            stream: Some(request.stream),
+            stream_options: request.stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+            response_format: request.response_format.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<Choice>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Choice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub finish_reason: Option<String>,
+}
+
+/// 流式响应中的一个数据块（`data: {...}`）
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<Usage>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+}
+
+/// OpenAI 及兼容 chat completions 接口的服务（DashScope、DeepSeek、vLLM 等）
+#[derive(Debug, Clone)]
+pub struct OpenAiProvider {
+    api_endpoint: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            api_endpoint: config.api_endpoint.clone(),
+            api_key: config.api_key.clone(),
+        }
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn build_request(
+        &self,
+        http_client: &reqwest::Client,
+        request: &ChatRequest,
+    ) -> reqwest::RequestBuilder {
+        let builder = http_client
+            .post(&self.api_endpoint)
+            .json(&ChatCompletionRequest::from(request));
+        match &self.api_key {
+            Some(api_key) => builder.bearer_auth(api_key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &[u8]) -> Result<ChatResponse> {
+        parse_completion(self.name(), body)
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Result<Option<StreamEvent>> {
+        parse_completion_chunk(line)
+    }
+}
+
+/// 解析 chat completions 格式的完整响应（OpenAI 与 Azure OpenAI 共用）
+pub(super) fn parse_completion(provider: &str, body: &[u8]) -> Result<ChatResponse> {
+    let response: ChatCompletionResponse =
+        serde_json::from_slice(body).map_err(|e| parse_error(provider, e))?;
+    let choice = response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| SmartFetchError::LlmApiError("API返回了空的选择列表".to_string()))?;
+
+    Ok(ChatResponse {
+        content: choice.message.content,
+        usage: response.usage,
+    })
+}
+
+/// 解析 chat completions 格式的 SSE 数据行（OpenAI 与 Azure OpenAI 共用）
+///
+/// `finish_reason` 之后还有一个只携带用量的数据块，读到 `[DONE]` 才算结束
+pub(super) fn parse_completion_chunk(line: &str) -> Result<Option<StreamEvent>> {
+    let Some(data) = sse_data(line) else {
+        return Ok(None);
+    };
+    if data == "[DONE]" {
+        return Ok(Some(StreamEvent {
+            finished: true,
+            ..Default::default()
+        }));
+    }
+
+    let chunk: StreamChunk = match serde_json::from_str(data) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            tracing::warn!("忽略无法解析的流式数据块: {}", e);
+            return Ok(None);
+        }
+    };
+    if let Some(error) = chunk.error {
+        return Err(SmartFetchError::LlmApiError(format!(
+            "流式响应返回错误: {}",
+            error
+        )));
+    }
+
+    let mut event = StreamEvent {
+        usage: chunk.usage,
+        ..Default::default()
+    };
+    for choice in chunk.choices {
+        if let Some(content) = choice.delta.content.filter(|content| !content.is_empty()) {
+            event
+                .delta
+                .get_or_insert_with(String::new)
+                .push_str(&content);
+        }
+    }
+    Ok(Some(event))
+}
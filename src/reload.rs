@@ -0,0 +1,311 @@
+use crate::config::AppConfig;
+use crate::error::Result;
+use crate::prompt_template::{TemplateManager, PARTIALS_DIR};
+use crate::SmartFetchService;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+use tracing_indicatif::indicatif_println;
+
+/// 可在运行中整体替换的服务实例
+///
+/// 每个请求开始时取得当时的服务实例；重新加载后新请求使用新实例，进行中的请求不受影响
+#[derive(Debug, Clone)]
+pub struct ServiceHandle {
+    current: Arc<RwLock<Arc<SmartFetchService>>>,
+    reloads: broadcast::Sender<ReloadSummary>,
+}
+
+impl ServiceHandle {
+    pub fn new(service: SmartFetchService) -> Self {
+        let (reloads, _) = broadcast::channel(16);
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(service))),
+            reloads,
+        }
+    }
+
+    /// 订阅重新加载事件，每次 [`HotReloader`] 替换服务实例后收到变更摘要
+    pub fn subscribe_reloads(&self) -> broadcast::Receiver<ReloadSummary> {
+        self.reloads.subscribe()
+    }
+
+    /// 当前的服务实例
+    pub fn current(&self) -> Arc<SmartFetchService> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// 替换服务实例，返回原有实例
+    pub fn replace(&self, service: SmartFetchService) -> Arc<SmartFetchService> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut *current, Arc::new(service))
+    }
+}
+
+/// 一次重新加载的变更摘要
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    pub templates_added: Vec<String>,
+    pub templates_removed: Vec<String>,
+    pub templates_changed: Vec<String>,
+    /// 发生变化的配置段（如 llm、processing）
+    pub config_changed: Vec<String>,
+    /// 配置文件中的服务器设置有变化，需重启后生效
+    pub server_changed: bool,
+}
+
+impl ReloadSummary {
+    pub fn is_empty(&self) -> bool {
+        self.templates_added.is_empty()
+            && self.templates_removed.is_empty()
+            && self.templates_changed.is_empty()
+            && self.config_changed.is_empty()
+            && !self.server_changed
+    }
+}
+
+impl fmt::Display for ReloadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "没有变化");
+        }
+
+        let mut parts = Vec::new();
+        for (label, names) in [
+            ("新增模板", &self.templates_added),
+            ("删除模板", &self.templates_removed),
+            ("修改模板", &self.templates_changed),
+            ("配置变更", &self.config_changed),
+        ] {
+            if !names.is_empty() {
+                parts.push(format!("{}: {}", label, names.join("、")));
+            }
+        }
+        if self.server_changed {
+            parts.push("服务器设置需重启后生效".to_string());
+        }
+        write!(f, "{}", parts.join("；"))
+    }
+}
+
+/// 配置文件和模板文件的修改时间与大小
+type FileSnapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// 重新加载后再次应用的配置调整（如命令行的 `--no-cache`）
+type ConfigOverride = Box<dyn Fn(&mut AppConfig) + Send + Sync>;
+
+/// 监视配置文件和模板目录，文件变更后重新加载服务
+///
+/// 以 `server.reload_interval_seconds` 为间隔比较文件的修改时间和大小
+pub struct HotReloader {
+    config_path: PathBuf,
+    handle: ServiceHandle,
+    interval: Duration,
+    config_override: Option<ConfigOverride>,
+}
+
+impl HotReloader {
+    pub fn new(config_path: &Path, handle: ServiceHandle) -> Self {
+        let interval = handle
+            .current()
+            .config()
+            .server
+            .reload_interval_seconds
+            .unwrap_or(2)
+            .max(1);
+        Self {
+            config_path: config_path.to_path_buf(),
+            handle,
+            interval: Duration::from_secs(interval),
+            config_override: None,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_config_override(
+        mut self,
+        config_override: impl Fn(&mut AppConfig) + Send + Sync + 'static,
+    ) -> Self {
+        self.config_override = Some(Box::new(config_override));
+        self
+    }
+
+    /// 立即重新加载
+    ///
+    /// 读取并校验配置、加载模板并创建新的服务实例，全部成功后才替换；任一步骤失败时返回错误，
+    /// 继续使用原有实例。API 密钥和服务器设置保持不变，见 [`AppConfig::retain_runtime_settings`]
+    pub fn reload(&self) -> Result<ReloadSummary> {
+        let current = self.handle.current();
+        let mut config = AppConfig::load(&self.config_path)?;
+        if let Some(config_override) = &self.config_override {
+            config_override(&mut config);
+        }
+        let server_changed = serde_json::to_value(&config.server)?
+            != serde_json::to_value(&current.config().server)?;
+        config.retain_runtime_settings(current.config());
+
+        let service = SmartFetchService::new(config)?.with_job_queue(current.job_queue().clone());
+        let mut summary = diff_templates(current.template_manager(), service.template_manager());
+        summary.config_changed = changed_sections(current.config(), service.config())?;
+        summary.server_changed = server_changed;
+
+        if !summary.is_empty() {
+            self.handle.replace(service);
+            // 没有订阅者时发送失败，忽略即可
+            let _ = self.handle.reloads.send(summary.clone());
+        }
+        Ok(summary)
+    }
+
+    /// 在后台定期检查文件变更并重新加载
+    ///
+    /// 读取文件和创建服务实例都是同步操作，在阻塞线程池中执行，不占用异步运行时的工作线程
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        let reloader = Arc::new(self);
+        tokio::spawn(async move {
+            let Some(mut snapshot) = reloader.run_blocking(|reloader| reloader.snapshot()).await
+            else {
+                return;
+            };
+            let mut ticker = tokio::time::interval(reloader.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                let previous = snapshot.clone();
+                let checked = reloader
+                    .run_blocking(move |reloader| {
+                        let latest = reloader.snapshot();
+                        let result = (latest != previous).then(|| reloader.reload());
+                        (latest, result)
+                    })
+                    .await;
+                let Some((latest, result)) = checked else {
+                    continue;
+                };
+                // 失败时也记录本次快照，等待文件再次变更后重试，避免重复报错
+                snapshot = latest;
+
+                match result {
+                    None => {}
+                    Some(Ok(summary)) if summary.is_empty() => {}
+                    Some(Ok(summary)) => {
+                        tracing::info!("重新加载配置和模板: {}", summary);
+                        indicatif_println!("🔄 已重新加载配置和模板: {}", summary);
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!("重新加载失败: {}", e);
+                        indicatif_println!("⚠️ 重新加载失败，继续使用原有配置和模板: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    async fn run_blocking<T: Send + 'static>(
+        self: &Arc<Self>,
+        task: impl FnOnce(&HotReloader) -> T + Send + 'static,
+    ) -> Option<T> {
+        let reloader = Arc::clone(self);
+        match tokio::task::spawn_blocking(move || task(&reloader)).await {
+            Ok(value) => Some(value),
+            Err(e) => {
+                tracing::warn!("检查文件变更的任务异常退出: {}", e);
+                None
+            }
+        }
+    }
+
+    fn snapshot(&self) -> FileSnapshot {
+        let mut files = FileSnapshot::new();
+        record_file(&mut files, &self.config_path);
+
+        let templates_dir = self.handle.current().config().templates_dir.clone();
+        record_dir(&mut files, &templates_dir, false);
+        record_dir(&mut files, &templates_dir.join(PARTIALS_DIR), true);
+        files
+    }
+}
+
+fn record_dir(files: &mut FileSnapshot, dir: &Path, recursive: bool) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if recursive && path.is_dir() {
+                record_dir(files, &path, true);
+            } else {
+                record_file(files, &path);
+            }
+        }
+    }
+}
+
+fn record_file(files: &mut FileSnapshot, path: &Path) {
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.is_file() {
+            files.insert(
+                path.to_path_buf(),
+                (metadata.modified().ok(), metadata.len()),
+            );
+        }
+    }
+}
+
+/// 比较两次加载的模板和局部模板，原文（含头部）不同即视为修改；局部模板记为 `partials/名称`
+fn diff_templates(old: &TemplateManager, new: &TemplateManager) -> ReloadSummary {
+    let sources = |manager: &TemplateManager| -> BTreeMap<String, String> {
+        let templates = manager.get_available_templates().into_iter().map(|name| {
+            let source = manager.template_source(&name).unwrap_or_default().to_string();
+            (name, source)
+        });
+        let partials = manager.get_available_partials().into_iter().map(|name| {
+            let source = manager.partial_source(&name).unwrap_or_default().to_string();
+            (format!("{}/{}", PARTIALS_DIR, name), source)
+        });
+        templates.chain(partials).collect()
+    };
+    let old_sources = sources(old);
+    let new_sources = sources(new);
+    let old_names: BTreeSet<&String> = old_sources.keys().collect();
+    let new_names: BTreeSet<&String> = new_sources.keys().collect();
+
+    ReloadSummary {
+        templates_added: new_names.difference(&old_names).map(|n| n.to_string()).collect(),
+        templates_removed: old_names.difference(&new_names).map(|n| n.to_string()).collect(),
+        templates_changed: old_names
+            .intersection(&new_names)
+            .filter(|name| old_sources.get(**name) != new_sources.get(**name))
+            .map(|n| n.to_string())
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// 按顶层配置段比较两份配置
+fn changed_sections(old: &AppConfig, new: &AppConfig) -> Result<Vec<String>> {
+    let old = serde_json::to_value(old)?;
+    let new = serde_json::to_value(new)?;
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return Ok(Vec::new());
+    };
+
+    let sections: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    Ok(sections
+        .into_iter()
+        .filter(|section| old.get(*section) != new.get(*section))
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,145 @@
+use crate::document::{has_supported_extension, list_documents};
+use crate::error::{Result, SmartFetchError};
+use crate::SmartFetchService;
+use std::path::{Path, PathBuf};
+
+/// 提示词模板资源的 URI 前缀，完整形式为 `template://<模板名称>`
+pub const TEMPLATE_URI_PREFIX: &str = "template://";
+/// 文档资源的 URI 前缀，完整形式为 `file://<绝对路径>`
+pub const FILE_URI_PREFIX: &str = "file://";
+/// 提取结果资源的 URI 前缀，完整形式为 `result://<结果 ID>`
+pub const RESULT_URI_PREFIX: &str = "result://";
+
+pub fn template_uri(name: &str) -> String {
+    format!("{}{}", TEMPLATE_URI_PREFIX, name)
+}
+
+pub fn result_uri(id: &str) -> String {
+    format!("{}{}", RESULT_URI_PREFIX, id)
+}
+
+/// 绝对路径转为 `file://` URI，路径中的空格、非 ASCII 字符等按百分号编码
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = FILE_URI_PREFIX.to_string();
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// 解析 `file://` URI 中的路径，不是 `file://` URI 或编码无效时返回 `None`
+pub fn file_uri_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix(FILE_URI_PREFIX)?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%' {
+            let hex = std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            bytes.push(encoded[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// 允许以 MCP 资源读取的文档目录（`server.resource_roots`）
+///
+/// 目录在创建时解析为规范路径，读取文件前同样解析文件的规范路径，
+/// 通过 `..` 或符号链接指向目录之外的文件会被拒绝
+#[derive(Debug, Clone, Default)]
+pub struct ResourceRoots {
+    roots: Vec<PathBuf>,
+}
+
+impl ResourceRoots {
+    /// 不存在的目录会被跳过
+    pub fn new(roots: &[PathBuf]) -> Self {
+        let roots = roots
+            .iter()
+            .filter_map(|root| match root.canonicalize() {
+                Ok(root) if root.is_dir() => Some(root),
+                Ok(_) => {
+                    tracing::warn!("资源目录不是目录，已跳过: {:?}", root);
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!("资源目录不可用，已跳过: {:?} - {}", root, e);
+                    None
+                }
+            })
+            .collect();
+        Self { roots }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// 各目录下扩展名在 `supported_formats` 中的文件（绝对路径，按路径排序）
+    pub fn list_files(&self, supported_formats: &[String]) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for root in &self.roots {
+            match list_documents(root, supported_formats, None) {
+                Ok(inputs) => files.extend(inputs.into_iter().map(|input| root.join(input))),
+                Err(e) => tracing::warn!("列出资源目录失败: {:?} - {}", root, e),
+            }
+        }
+        // 目录可能相互包含
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// 校验文件位于某个资源目录内且格式受支持，返回文件的规范路径
+    pub fn resolve(&self, path: &Path, supported_formats: &[String]) -> Result<PathBuf> {
+        let outside =
+            || SmartFetchError::ValidationError(format!("文件不在允许的资源目录内: {:?}", path));
+        let path = path.canonicalize().map_err(|_| outside())?;
+        if !path.is_file() || !self.roots.iter().any(|root| path.starts_with(root)) {
+            return Err(outside());
+        }
+
+        if !has_supported_extension(&path, supported_formats) {
+            return Err(SmartFetchError::DocumentError(format!(
+                "不支持的文档格式: {:?}",
+                path
+            )));
+        }
+        Ok(path)
+    }
+}
+
+impl SmartFetchService {
+    /// 配置中允许以 MCP 资源读取的文档目录
+    pub fn resource_roots(&self) -> ResourceRoots {
+        ResourceRoots::new(
+            self.config
+                .server
+                .resource_roots
+                .as_deref()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// 读取资源目录内的文档，返回加载并预处理后的文本，不调用模型
+    pub async fn read_document(&self, path: &Path) -> Result<String> {
+        let path = self
+            .resource_roots()
+            .resolve(path, &self.supported_formats())?;
+        Ok(self
+            .prepare_document(self.llm_client(None)?, &path)
+            .await?
+            .content)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::{ChatMessage, LLMClient};
+use serde_json::Value;
+use tracing_indicatif::indicatif_println;
+
+/// 单次反馈中最多列出的校验错误数，避免提示词过长
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// 编译调用方提供的 JSON Schema
+pub fn compile_schema(schema: &Value) -> Result<jsonschema::Validator> {
+    jsonschema::validator_for(schema)
+        .map_err(|e| SmartFetchError::ValidationError(format!("无效的 JSON Schema: {}", e)))
+}
+
+/// 解析模型输出的 JSON，兼容包裹在 Markdown 代码块中的情况
+pub fn parse_json_output(output: &str) -> std::result::Result<Value, String> {
+    let trimmed = output.trim();
+    let body = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .unwrap_or(trimmed);
+
+    serde_json::from_str(body.trim()).map_err(|e| format!("输出不是有效的 JSON: {}", e))
+}
+
+/// 返回实例违反 Schema 的所有错误，格式为 `路径: 原因`
+pub fn validation_errors(validator: &jsonschema::Validator, instance: &Value) -> Vec<String> {
+    validator
+        .iter_errors(instance)
+        .map(|error| {
+            let path = error.instance_path.as_str();
+            let path = if path.is_empty() { "/" } else { path };
+            format!("{}: {}", path, error)
+        })
+        .collect()
+}
+
+/// 请求模型输出 JSON 并按 Schema 校验，失败时携带错误信息重新请求
+///
+/// 最多请求 `max_retries + 1` 次，全部失败时返回最后一次的校验错误
+#[tracing::instrument(level = "info", skip(llm_client, messages, schema), name = "结构化提取")]
+pub async fn generate_validated_json(
+    llm_client: &LLMClient,
+    mut messages: Vec<ChatMessage>,
+    schema: &Value,
+    max_retries: u32,
+) -> Result<Value> {
+    let validator = compile_schema(schema)?;
+    let response_format = llm_client.response_format_for_schema(schema);
+
+    let max_attempts = max_retries + 1;
+
+    for attempt in 1..=max_attempts {
+        let output = llm_client
+            .generate_chat(messages.clone(), response_format.clone())
+            .await?;
+
+        let errors = match parse_json_output(&output) {
+            Ok(value) => {
+                let errors = validation_errors(&validator, &value);
+                if errors.is_empty() {
+                    indicatif_println!("✅ 结构化输出通过 Schema 校验 (第{}次尝试)", attempt);
+                    return Ok(value);
+                }
+                errors
+            }
+            Err(error) => vec![error],
+        };
+
+        if attempt == max_attempts {
+            return Err(SmartFetchError::ValidationError(format!(
+                "结构化输出在 {} 次尝试后仍未通过 Schema 校验: {}",
+                max_attempts,
+                errors.join("; ")
+            )));
+        }
+
+        indicatif_println!(
+            "⚠️ 结构化输出未通过校验 (第{}/{}次尝试)，携带{}个错误重新请求",
+            attempt,
+            max_attempts,
+            errors.len()
+        );
+        messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: output,
+        });
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: retry_prompt(&errors),
+        });
+    }
+
+    unreachable!("max_attempts 至少为 1")
+}
+
+fn retry_prompt(errors: &[String]) -> String {
+    let listed = errors
+        .iter()
+        .take(MAX_REPORTED_ERRORS)
+        .map(|error| format!("- {}", error))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let omitted = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+    let suffix = if omitted > 0 {
+        format!("\n- ……另有 {} 个错误", omitted)
+    } else {
+        String::new()
+    };
+
+    format!(
+        "上面的输出未通过 JSON Schema 校验：\n{}{}\n\n请修正这些问题，只输出符合 Schema 的 JSON，不要包含任何解释或 Markdown 代码块。",
+        listed, suffix
+    )
+}
@@ -0,0 +1,339 @@
+use crate::document::extract_page_range;
+use crate::tokenizer::TokenCounter;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, HelperResult, Output,
+    RenderContext, RenderError, RenderErrorReason, Renderable,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 注册模板中可用的自定义助手
+///
+/// 所有助手按字符而非字节处理文本，参数缺失或类型不符时返回渲染错误而不是 panic
+pub(crate) fn register_helpers(
+    handlebars: &mut Handlebars<'static>,
+    token_counter: Arc<dyn TokenCounter>,
+) {
+    handlebars.register_helper("truncate", Box::new(truncate_helper));
+    handlebars.register_helper("word_count", Box::new(word_count_helper));
+    handlebars.register_helper("line_count", Box::new(line_count_helper));
+    register_token_count_helper(handlebars, token_counter);
+    handlebars.register_helper("page_range", Box::new(page_range_helper));
+    handlebars.register_helper("head", Box::new(head_helper));
+    handlebars.register_helper("tail", Box::new(tail_helper));
+    handlebars.register_helper("sections", Box::new(sections_helper));
+    handlebars.register_helper("json", Box::new(json_helper));
+    handlebars.register_helper("date", Box::new(date_helper));
+    handlebars.register_helper("if_longer_than", Box::new(if_longer_than_helper));
+}
+
+/// `{{truncate content 2000}}`：保留前 N 个字符，超出部分以 `...` 代替
+fn truncate_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "truncate", 0)?;
+    let limit = usize_param(h, "truncate", 1)?;
+
+    match text.char_indices().nth(limit) {
+        Some((end, _)) => {
+            out.write(&text[..end])?;
+            out.write("...")?;
+        }
+        None => out.write(text)?,
+    }
+    Ok(())
+}
+
+fn word_count_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "word_count", 0)?;
+    out.write(&text.split_whitespace().count().to_string())?;
+    Ok(())
+}
+
+fn line_count_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "line_count", 0)?;
+    out.write(&text.lines().count().to_string())?;
+    Ok(())
+}
+
+/// 注册（或替换）`token_count` 助手使用的分词器
+pub(crate) fn register_token_count_helper(
+    handlebars: &mut Handlebars<'static>,
+    token_counter: Arc<dyn TokenCounter>,
+) {
+    handlebars.register_helper("token_count", Box::new(TokenCountHelper { token_counter }));
+}
+
+/// `{{token_count content}}`：按配置的分词器统计文本的 token 数，未加载词表时为估算值
+struct TokenCountHelper {
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl HelperDef for TokenCountHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let text = str_param(h, "token_count", 0)?;
+        out.write(&self.token_counter.count_tokens(text).to_string())?;
+        Ok(())
+    }
+}
+
+/// `{{page_range content 起始页 结束页}}`：截取分页文档（如 PDF）中指定页码范围的内容
+fn page_range_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "page_range", 0)?;
+    let from = usize_param(h, "page_range", 1)?;
+    let to = match h.param(2) {
+        Some(_) => usize_param(h, "page_range", 2)?,
+        None => from,
+    };
+
+    out.write(&extract_page_range(text, from, to))?;
+    Ok(())
+}
+
+/// `{{head content 20}}`：文本的前 N 行
+fn head_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "head", 0)?;
+    let count = usize_param(h, "head", 1)?;
+    out.write(&text.lines().take(count).collect::<Vec<_>>().join("\n"))?;
+    Ok(())
+}
+
+/// `{{tail content 20}}`：文本的最后 N 行
+fn tail_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "tail", 0)?;
+    let count = usize_param(h, "tail", 1)?;
+    let lines: Vec<&str> = text.lines().collect();
+    out.write(&lines[lines.len().saturating_sub(count)..].join("\n"))?;
+    Ok(())
+}
+
+// `{{#each (sections content level=2)}}{{this.title}}{{{this.content}}}{{/each}}`：
+// 按 Markdown 标题切分文本，`level` 为参与切分的最深标题级别
+handlebars_helper!(sections_helper: |text: str, { level: u64 = 6 }| split_sections(text, level as usize));
+
+/// `{{{json metadata}}}`：以缩进格式输出 JSON，JSON 字符串会先解析再格式化
+fn json_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = h
+        .param(0)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex("json", 0))?
+        .value();
+    let parsed = value
+        .as_str()
+        .and_then(|text| serde_json::from_str::<Value>(text).ok());
+    let pretty = serde_json::to_string_pretty(parsed.as_ref().unwrap_or(value))
+        .map_err(|e| RenderErrorReason::Other(format!("json 助手序列化失败: {}", e)))?;
+    out.write(&pretty)?;
+    Ok(())
+}
+
+/// `{{date}}`、`{{date format="%Y年%m月%d日"}}`、`{{date metadata.created_at format="%Y-%m-%d"}}`：
+/// 格式化当前日期或指定的日期（RFC 3339、`YYYY-MM-DD HH:MM:SS` 或 `YYYY-MM-DD`），默认格式为 `%Y-%m-%d`
+fn date_helper(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let format = match h.hash_get("format") {
+        Some(format) => format.value().as_str().ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "date",
+                "format".to_string(),
+                "string".to_string(),
+            )
+        })?,
+        None => "%Y-%m-%d",
+    };
+    let items: Vec<Item<'_>> = StrftimeItems::new(format).collect();
+    if items.contains(&Item::Error) {
+        return Err(RenderErrorReason::Other(format!("date 助手的格式无效: {}", format)).into());
+    }
+
+    let date = match h.param(0) {
+        Some(_) => {
+            let text = str_param(h, "date", 0)?;
+            parse_date(text).ok_or_else(|| {
+                RenderErrorReason::Other(format!("date 助手无法解析日期: {}", text))
+            })?
+        }
+        None => Local::now(),
+    };
+    out.write(&date.format_with_items(items.into_iter()).to_string())?;
+    Ok(())
+}
+
+/// `{{#if_longer_than content 2000}}...{{else}}...{{/if_longer_than}}`：文本超过 N 个字符时渲染块内容
+fn if_longer_than_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = str_param(h, "if_longer_than", 0)?;
+    let limit = usize_param(h, "if_longer_than", 1)?;
+
+    let longer = text.chars().nth(limit).is_some();
+    let template = if longer { h.template() } else { h.inverse() };
+    if let Some(template) = template {
+        template.render(r, ctx, rc, out)?;
+    }
+    Ok(())
+}
+
+fn str_param<'a>(
+    h: &'a Helper<'_>,
+    helper: &'static str,
+    index: usize,
+) -> Result<&'a str, RenderError> {
+    h.param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?
+        .value()
+        .as_str()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                helper,
+                index.to_string(),
+                "string".to_string(),
+            )
+            .into()
+        })
+}
+
+fn usize_param(h: &Helper<'_>, helper: &'static str, index: usize) -> Result<usize, RenderError> {
+    h.param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?
+        .value()
+        .as_u64()
+        .map(|value| value as usize)
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                helper,
+                index.to_string(),
+                "非负整数".to_string(),
+            )
+            .into()
+        })
+}
+
+fn parse_date(text: &str) -> Option<DateTime<Local>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// 按 Markdown 标题切分文本，代码块中的 `#` 不视为标题；第一个标题之前的内容作为标题为空、级别为 0 的一节
+fn split_sections(text: &str, max_level: usize) -> Value {
+    let mut sections = Vec::new();
+    let mut title = String::new();
+    let mut level = 0;
+    let mut content: Vec<&str> = Vec::new();
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        let heading = (!in_code_block)
+            .then(|| markdown_heading(line))
+            .flatten()
+            .filter(|(heading_level, _)| *heading_level <= max_level);
+
+        match heading {
+            Some((heading_level, heading_title)) => {
+                push_section(&mut sections, &title, level, &content);
+                title = heading_title.to_string();
+                level = heading_level;
+                content.clear();
+            }
+            None => content.push(line),
+        }
+    }
+    push_section(&mut sections, &title, level, &content);
+    Value::Array(sections)
+}
+
+fn push_section(sections: &mut Vec<Value>, title: &str, level: usize, content: &[&str]) {
+    let content = content.join("\n").trim().to_string();
+    if title.is_empty() && content.is_empty() {
+        return;
+    }
+    sections.push(json!({
+        "title": title,
+        "level": level,
+        "content": content,
+    }));
+}
+
+/// 解析 ATX 风格的标题行（`## 标题`），返回级别和标题文本
+fn markdown_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
@@ -0,0 +1,368 @@
+use crate::config::TokenizerConfig;
+use crate::error::{Result, SmartFetchError};
+use crate::llm_client::ChatMessage;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use fancy_regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// 构建时嵌入的词表，见 build.rs
+include!(concat!(env!("OUT_DIR"), "/bundled_vocab.rs"));
+
+/// 每条对话消息在角色和分隔符上额外占用的 token 数
+const TOKENS_PER_MESSAGE: usize = 4;
+/// 模型回复开头（`<|start|>assistant<|message|>`）占用的 token 数
+const TOKENS_PER_REPLY: usize = 3;
+
+/// 文本的 token 计数
+pub trait TokenCounter: Send + Sync + fmt::Debug {
+    /// 分词器名称，如 `cl100k_base`、`estimate`
+    fn name(&self) -> &str;
+
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// 一组对话消息的 token 数，包含每条消息的格式开销
+    fn count_messages(&self, messages: &[ChatMessage]) -> usize {
+        messages
+            .iter()
+            .map(|message| {
+                TOKENS_PER_MESSAGE
+                    + self.count_tokens(&message.role)
+                    + self.count_tokens(&message.content)
+            })
+            .sum::<usize>()
+            + TOKENS_PER_REPLY
+    }
+}
+
+/// BPE 编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// GPT-4、GPT-3.5 使用的编码
+    Cl100kBase,
+    /// GPT-4o、o1 及之后的模型使用的编码
+    O200kBase,
+}
+
+impl Encoding {
+    pub const NAMES: [&'static str; 2] = ["cl100k_base", "o200k_base"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cl100k_base" => Some(Encoding::Cl100kBase),
+            "o200k_base" => Some(Encoding::O200kBase),
+            _ => None,
+        }
+    }
+
+    /// 构建时嵌入二进制文件的词表，构建时 `tokenizers/` 目录下没有该词表时为 `None`
+    pub fn bundled_vocab(self) -> Option<&'static [u8]> {
+        match self {
+            Encoding::Cl100kBase => BUNDLED_CL100K_BASE,
+            Encoding::O200kBase => BUNDLED_O200K_BASE,
+        }
+    }
+
+    /// 按模型名称选择编码，无法识别的模型使用 cl100k_base
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        let o200k = [
+            "gpt-4o",
+            "gpt-4.1",
+            "gpt-4.5",
+            "gpt-5",
+            "chatgpt-4o",
+            "o1",
+            "o3",
+            "o4",
+        ];
+        if o200k.iter().any(|prefix| model.starts_with(prefix)) {
+            Encoding::O200kBase
+        } else {
+            Encoding::Cl100kBase
+        }
+    }
+
+    /// 分词前切分文本的正则表达式，与 tiktoken 保持一致
+    fn pattern(self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => {
+                r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+"
+            }
+            Encoding::O200kBase => concat!(
+                r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+                r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+                r"|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+"
+            ),
+        }
+    }
+}
+
+/// 使用 tiktoken 格式词表（每行为 base64 编码的字节串和排名）的 BPE 分词器
+pub struct BpeTokenizer {
+    encoding: Encoding,
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl fmt::Debug for BpeTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BpeTokenizer")
+            .field("encoding", &self.encoding)
+            .field("vocab_size", &self.ranks.len())
+            .finish()
+    }
+}
+
+impl BpeTokenizer {
+    /// 解析 tiktoken 格式的词表
+    pub fn from_tiktoken(encoding: Encoding, vocab: &str) -> Result<Self> {
+        let mut ranks = HashMap::new();
+        for (line_no, line) in vocab.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                SmartFetchError::ConfigError(format!(
+                    "{} 词表第 {} 行格式无效",
+                    encoding.name(),
+                    line_no + 1
+                ))
+            };
+            let (token, rank) = line.split_once(' ').ok_or_else(invalid)?;
+            let token = STANDARD.decode(token).map_err(|_| invalid())?;
+            let rank = rank.trim().parse().map_err(|_| invalid())?;
+            ranks.insert(token, rank);
+        }
+        if ranks.is_empty() {
+            return Err(SmartFetchError::ConfigError(format!(
+                "{} 词表为空",
+                encoding.name()
+            )));
+        }
+
+        let pattern = Regex::new(encoding.pattern()).map_err(|e| {
+            SmartFetchError::RegexError(format!("{} 分词正则无效: {}", encoding.name(), e))
+        })?;
+        Ok(Self {
+            encoding,
+            ranks,
+            pattern,
+        })
+    }
+
+    /// 从 `<目录>/<编码名称>.tiktoken` 加载词表
+    pub fn from_dir(encoding: Encoding, vocab_dir: &Path) -> Result<Self> {
+        let path = vocab_path(encoding, vocab_dir);
+        let vocab = fs::read_to_string(&path).map_err(|e| {
+            SmartFetchError::ConfigError(format!("读取分词词表失败: {:?} - {}", path, e))
+        })?;
+        Self::from_tiktoken(encoding, &vocab)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// 将文本编码为 token 排名序列
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = Vec::new();
+        // 回溯超限时跳过剩余文本，此时计数偏少，但不会中断请求
+        for piece in self.pattern.find_iter(text).map_while(|piece| piece.ok()) {
+            let piece = piece.as_str().as_bytes();
+            match self.ranks.get(piece) {
+                Some(rank) => tokens.push(*rank),
+                None => {
+                    let parts = self.byte_pair_merge(piece);
+                    tokens.extend(parts.windows(2).map(|pair| {
+                        self.ranks
+                            .get(&piece[pair[0].0..pair[1].0])
+                            .copied()
+                            .unwrap_or(u32::MAX)
+                    }));
+                }
+            }
+        }
+        tokens
+    }
+
+    /// 按排名反复合并相邻字节对，返回每个 token 的起始位置（最后一项为结尾）
+    fn byte_pair_merge(&self, piece: &[u8]) -> Vec<(usize, u32)> {
+        let rank_of = |parts: &[(usize, u32)], i: usize| -> u32 {
+            if i + 3 < parts.len() {
+                self.ranks
+                    .get(&piece[parts[i].0..parts[i + 3].0])
+                    .copied()
+                    .unwrap_or(u32::MAX)
+            } else {
+                u32::MAX
+            }
+        };
+
+        let mut parts: Vec<(usize, u32)> = (0..piece.len().saturating_sub(1))
+            .map(|i| {
+                let rank = self.ranks.get(&piece[i..i + 2]).copied();
+                (i, rank.unwrap_or(u32::MAX))
+            })
+            .collect();
+        parts.push((piece.len().saturating_sub(1), u32::MAX));
+        parts.push((piece.len(), u32::MAX));
+
+        while let Some((i, _)) = parts[..parts.len() - 1]
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, rank))| *rank != u32::MAX)
+            .min_by_key(|(i, (_, rank))| (*rank, *i))
+        {
+            if i > 0 {
+                parts[i - 1].1 = rank_of(&parts, i - 1);
+            }
+            parts[i].1 = rank_of(&parts, i);
+            parts.remove(i + 1);
+        }
+        parts
+    }
+}
+
+impl TokenCounter for BpeTokenizer {
+    fn name(&self) -> &str {
+        self.encoding.name()
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+}
+
+/// 没有词表时使用的估算：ASCII 文本约 4 个字符一个 token，中文等其他字符约 1.5 个 token
+///
+/// 中文按字符数的 1.5 倍估算，宁可偏多，避免超出上下文窗口
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimateTokenCounter;
+
+impl TokenCounter for EstimateTokenCounter {
+    fn name(&self) -> &str {
+        "estimate"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        let (ascii, other) = text.chars().fold((0usize, 0usize), |(ascii, other), c| {
+            if c.is_ascii() {
+                (ascii + 1, other)
+            } else {
+                (ascii, other + 1)
+            }
+        });
+        ascii.div_ceil(4) + (other * 3).div_ceil(2)
+    }
+}
+
+fn vocab_path(encoding: Encoding, vocab_dir: &Path) -> PathBuf {
+    vocab_dir.join(format!("{}.tiktoken", encoding.name()))
+}
+
+/// 按模型名称提供分词器，词表在首次使用时加载并缓存
+///
+/// 配置了 `vocab_dir` 时优先读取其中的词表，否则使用构建时嵌入的词表；
+/// 两者都不可用时退回 [`EstimateTokenCounter`]，并记录一次警告
+#[derive(Debug)]
+pub struct TokenizerRegistry {
+    config: TokenizerConfig,
+    loaded: Mutex<HashMap<Encoding, Arc<dyn TokenCounter>>>,
+}
+
+impl TokenizerRegistry {
+    pub fn new(config: TokenizerConfig) -> Self {
+        Self {
+            config,
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 模型对应的分词器
+    pub fn counter_for(&self, model: &str) -> Arc<dyn TokenCounter> {
+        let encoding = match self.config.encoding.as_deref().unwrap_or("auto") {
+            "auto" => Encoding::for_model(model),
+            name => match Encoding::from_name(name) {
+                Some(encoding) => encoding,
+                None => return Arc::new(EstimateTokenCounter),
+            },
+        };
+
+        let mut loaded = self.loaded.lock().unwrap_or_else(PoisonError::into_inner);
+        loaded
+            .entry(encoding)
+            .or_insert_with(|| match self.load(encoding) {
+                Ok(tokenizer) => Arc::new(tokenizer),
+                Err(e) => {
+                    tracing::warn!("{}，改用估算的 token 数", e);
+                    Arc::new(EstimateTokenCounter)
+                }
+            })
+            .clone()
+    }
+
+    fn load(&self, encoding: Encoding) -> Result<BpeTokenizer> {
+        if let Some(vocab_dir) = &self.config.vocab_dir {
+            match BpeTokenizer::from_dir(encoding, vocab_dir) {
+                Ok(tokenizer) => return Ok(tokenizer),
+                // 目录中没有该词表时继续使用嵌入的词表
+                Err(e) if encoding.bundled_vocab().is_some() => {
+                    tracing::warn!("{}，改用内置词表", e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let vocab = encoding.bundled_vocab().ok_or_else(|| {
+            SmartFetchError::ConfigError(format!(
+                "未配置 {} 词表（tokenizer.vocab_dir），构建时也未嵌入",
+                encoding.name()
+            ))
+        })?;
+        let vocab = std::str::from_utf8(vocab).map_err(|e| {
+            SmartFetchError::ConfigError(format!("内置词表不是有效的 UTF-8: {}", e))
+        })?;
+        BpeTokenizer::from_tiktoken(encoding, vocab)
+    }
+}
+
+/// 常见模型的上下文窗口（token 数），无法识别的模型返回 `None`
+pub fn default_context_window(model: &str) -> Option<usize> {
+    let model = model.to_lowercase();
+    // 按前缀匹配，较具体的前缀在前
+    let windows: [(&str, usize); 16] = [
+        ("gpt-4.1", 1_047_576),
+        ("gpt-4o", 128_000),
+        ("chatgpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4-32k", 32_768),
+        ("gpt-4", 8_192),
+        ("gpt-3.5-turbo", 16_385),
+        ("gpt-5", 400_000),
+        ("o1", 200_000),
+        ("o3", 200_000),
+        ("o4", 200_000),
+        ("claude", 200_000),
+        ("deepseek", 64_000),
+        ("qwen", 32_768),
+        ("llama3", 8_192),
+        ("mistral", 32_768),
+    ];
+    windows
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, window)| *window)
+}
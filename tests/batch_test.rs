@@ -0,0 +1,407 @@
+mod common;
+
+use common::{completion_body, stream_body, test_config};
+use mcp_smart_fetch::{
+    collect_batch_inputs, AppConfig, BatchOptions, FileStatus, GlobSet, JobStatus, JobsConfig,
+    SmartFetchService, BATCH_REPORT_FILE,
+};
+use mockito::Matcher;
+use std::path::{Path, PathBuf};
+
+fn create_test_config(endpoint: String, jobs_dir: &Path) -> AppConfig {
+    let mut config = test_config(endpoint);
+    config.jobs = Some(JobsConfig {
+        jobs_dir: Some(jobs_dir.to_path_buf()),
+        ..Default::default()
+    });
+    config.processing.supported_formats = vec!["txt".into(), "md".into(), "pdf".into()];
+    config
+}
+
+fn write_file(root: &Path, path: &str, content: &[u8]) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn patterns(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn test_glob_set_matching() {
+    let names = GlobSet::new(&patterns(&["*.md", "report-?.txt"])).unwrap();
+    assert!(names.is_match("README.md"));
+    assert!(names.is_match("docs/guide/intro.md"));
+    assert!(names.is_match("report-1.txt"));
+    assert!(!names.is_match("report-10.txt"));
+    assert!(!names.is_match("notes.txt"));
+
+    let paths = GlobSet::new(&patterns(&["docs/**/*.txt", "drafts/[!a]*"])).unwrap();
+    assert!(paths.is_match("docs/a.txt"));
+    assert!(paths.is_match("docs/2024/q1/a.txt"));
+    assert!(!paths.is_match("other/docs/a.txt"));
+    assert!(paths.is_match("drafts/b.md"));
+    assert!(!paths.is_match("drafts/a.md"));
+
+    assert!(GlobSet::new(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_glob_set_special_characters() {
+    // 字符集合：范围、取反、开头的 `]` 以及不匹配路径分隔符
+    let classes = GlobSet::new(&patterns(&["file[0-9].txt", "[]x].md", "a[!b].txt"])).unwrap();
+    assert!(classes.is_match("file7.txt"));
+    assert!(!classes.is_match("filex.txt"));
+    assert!(classes.is_match("].md"));
+    assert!(classes.is_match("x.md"));
+    assert!(classes.is_match("ac.txt"));
+    assert!(!classes.is_match("ab.txt"));
+    let path_class = GlobSet::new(&patterns(&["docs/a[!b]c.txt"])).unwrap();
+    assert!(!path_class.is_match("docs/a/c.txt"));
+
+    // 正则的元字符和集合运算符按普通字符处理
+    let literal = GlobSet::new(&patterns(&["[&~].txt", "a+b(1).txt"])).unwrap();
+    assert!(literal.is_match("&.txt"));
+    assert!(literal.is_match("a+b(1).txt"));
+    assert!(!literal.is_match("aab1.txt"));
+
+    // 没有闭合的 `[` 是普通字符
+    let unclosed = GlobSet::new(&patterns(&["draft[1.txt"])).unwrap();
+    assert!(unclosed.is_match("draft[1.txt"));
+    assert!(!unclosed.is_match("draft1.txt"));
+
+    // 备选项，可以包含其他通配符
+    let alternatives = GlobSet::new(&patterns(&["*.{md,txt}", "docs/{guide,api/*}.html"])).unwrap();
+    assert!(alternatives.is_match("a.md"));
+    assert!(alternatives.is_match("notes/b.txt"));
+    assert!(!alternatives.is_match("c.pdf"));
+    assert!(alternatives.is_match("docs/guide.html"));
+    assert!(alternatives.is_match("docs/api/index.html"));
+    assert!(!alternatives.is_match("docs/api/v1/index.html"));
+    assert!(GlobSet::new(&patterns(&["*.{md,txt"])).is_err());
+
+    // 反斜杠转义通配符
+    let escaped = GlobSet::new(&patterns(&[r"\*.txt", r"what\?.md", r"\{a,b\}.txt"])).unwrap();
+    assert!(escaped.is_match("*.txt"));
+    assert!(!escaped.is_match("a.txt"));
+    assert!(escaped.is_match("what?.md"));
+    assert!(!escaped.is_match("whatx.md"));
+    assert!(escaped.is_match("{a,b}.txt"));
+
+    // 开头的 `**/` 也匹配根目录下的文件，开头的 `/` 只匹配根目录
+    let root = GlobSet::new(&patterns(&["**/*.md", "/top.txt"])).unwrap();
+    assert!(root.is_match("README.md"));
+    assert!(root.is_match("docs/guide/intro.md"));
+    assert!(root.is_match("top.txt"));
+    assert!(!root.is_match("docs/top.txt"));
+}
+
+#[test]
+fn test_collect_batch_inputs_filters_files() {
+    let dir = tempfile::tempdir().unwrap();
+    for path in [
+        "a.txt",
+        "b.md",
+        "image.png",
+        "docs/c.txt",
+        "docs/draft-d.txt",
+        "out/previous.md",
+    ] {
+        write_file(dir.path(), path, b"content");
+    }
+    let formats = patterns(&["txt", "md"]);
+    let output_dir = dir.path().join("out");
+
+    let inputs = collect_batch_inputs(
+        dir.path(),
+        &BatchOptions::default(),
+        &formats,
+        Some(&output_dir),
+    )
+    .unwrap();
+    let expected: Vec<PathBuf> = ["a.txt", "b.md", "docs/c.txt", "docs/draft-d.txt"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(inputs, expected);
+
+    let options = BatchOptions {
+        include: patterns(&["*.txt"]),
+        exclude: patterns(&["draft-*"]),
+        ..Default::default()
+    };
+    let inputs = collect_batch_inputs(dir.path(), &options, &formats, None).unwrap();
+    assert_eq!(
+        inputs,
+        [PathBuf::from("a.txt"), PathBuf::from("docs/c.txt")]
+    );
+
+    assert!(collect_batch_inputs(&dir.path().join("missing"), &options, &formats, None).is_err());
+}
+
+#[test]
+fn test_default_formats_follow_loader_registry() {
+    let dir = tempfile::tempdir().unwrap();
+    for path in ["a.docx", "b.html", "c.txt", "image.png"] {
+        write_file(dir.path(), path, b"content");
+    }
+
+    // 未配置 supported_formats 时使用加载器注册表支持的所有格式
+    let service = SmartFetchService::new(test_config("http://localhost".to_string())).unwrap();
+    let formats = service.supported_formats();
+    assert_eq!(formats, service.loader_registry().supported_formats());
+
+    let inputs =
+        collect_batch_inputs(dir.path(), &BatchOptions::default(), &formats, None).unwrap();
+    let expected: Vec<PathBuf> = ["a.docx", "b.html", "c.txt"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(inputs, expected);
+}
+
+#[tokio::test]
+async fn test_extract_batch_mirrors_outputs_and_continues_after_failures() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let jobs_dir = tempfile::tempdir().unwrap();
+    write_file(input_dir.path(), "a.txt", "第一份文档".as_bytes());
+    write_file(input_dir.path(), "a.md", "同名的另一份文档".as_bytes());
+    write_file(
+        input_dir.path(),
+        "nested/deep/b.txt",
+        "第二份文档".as_bytes(),
+    );
+    write_file(input_dir.path(), "broken.pdf", b"not a pdf");
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(3)
+        .create_async()
+        .await;
+
+    let config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        jobs_dir.path(),
+    );
+    let service = SmartFetchService::new(config).unwrap();
+    let options = BatchOptions {
+        concurrency: 2,
+        ..Default::default()
+    };
+    let report = service
+        .extract_batch(input_dir.path(), output_dir.path(), &options)
+        .await
+        .unwrap();
+
+    assert_eq!((report.total, report.succeeded, report.failed), (4, 3, 1));
+    let usage = report.usage.as_ref().unwrap();
+    assert_eq!(usage.total_tokens, 45);
+
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures[0].input, PathBuf::from("broken.pdf"));
+    assert!(failures[0].error.is_some());
+
+    // 替换扩展名后重名的文件保留原扩展名
+    for output in ["a.txt.md", "a.md.md", "nested/deep/b.md"] {
+        let content = std::fs::read_to_string(output_dir.path().join(output)).unwrap();
+        assert_eq!(content, "提取结果", "{}", output);
+    }
+    assert!(!output_dir.path().join("broken.md").exists());
+
+    let saved: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(output_dir.path().join(BATCH_REPORT_FILE)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(saved["failed"], 1);
+    assert_eq!(saved["items"].as_array().unwrap().len(), 4);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_extract_batch_reports_streaming_usage() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let jobs_dir = tempfile::tempdir().unwrap();
+    write_file(input_dir.path(), "a.txt", "第一份文档".as_bytes());
+    write_file(input_dir.path(), "b.txt", "第二份文档".as_bytes());
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(serde_json::json!({ "stream": true })))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(stream_body("流式结果", true))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        jobs_dir.path(),
+    );
+    config.llm.stream = Some(true);
+    let service = SmartFetchService::new(config).unwrap();
+    let report = service
+        .extract_batch(
+            input_dir.path(),
+            output_dir.path(),
+            &BatchOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!((report.total, report.succeeded), (2, 2));
+    let usage = report
+        .usage
+        .as_ref()
+        .expect("流式调用的用量应计入批处理报告");
+    assert_eq!(usage.total_tokens, 30);
+    assert!(report.items.iter().all(|item| item
+        .usage
+        .as_ref()
+        .is_some_and(|usage| usage.total_tokens == 15)));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_resume_retries_failed_and_changed_files_only() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let jobs_dir = tempfile::tempdir().unwrap();
+    write_file(input_dir.path(), "a.txt", b"FILE-A");
+    write_file(input_dir.path(), "b.txt", b"FILE-B");
+    write_file(input_dir.path(), "c.txt", b"FILE-C");
+
+    let mut server = mockito::Server::new_async().await;
+    let endpoint = format!("{}/v1/chat/completions", server.url());
+    let service = SmartFetchService::new(create_test_config(endpoint, jobs_dir.path())).unwrap();
+
+    // 第一次运行：b.txt 失败
+    let succeeded = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex("FILE-[AC]".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(2)
+        .create_async()
+        .await;
+    let failed = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex("FILE-B".to_string()))
+        .with_status(400)
+        .with_body(r#"{"error": {"message": "bad request"}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let options = BatchOptions::default();
+    let first = service
+        .extract_batch(input_dir.path(), output_dir.path(), &options)
+        .await
+        .unwrap();
+    assert_eq!((first.succeeded, first.failed), (2, 1));
+    succeeded.assert_async().await;
+    failed.assert_async().await;
+
+    let job = service.job_store().load(&first.job_id[..8]).unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.count(FileStatus::Failed), 1);
+    assert!(job.files.iter().all(|file| file.hash.is_some()));
+
+    // 续跑：只重新提取失败的 b.txt 和内容有变化的 c.txt
+    server.reset();
+    write_file(input_dir.path(), "c.txt", b"FILE-C v2");
+    let retried = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex("FILE-[BC]".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let resumed = service
+        .extract_batch(
+            input_dir.path(),
+            output_dir.path(),
+            &BatchOptions {
+                resume: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(resumed.job_id, first.job_id);
+    assert_eq!(
+        (resumed.succeeded, resumed.failed, resumed.skipped),
+        (3, 0, 1)
+    );
+    assert_eq!(resumed.usage.as_ref().unwrap().total_tokens, 30);
+    retried.assert_async().await;
+
+    let jobs = service.job_store().list().unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn test_resume_requires_matching_job() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let jobs_dir = tempfile::tempdir().unwrap();
+    write_file(input_dir.path(), "a.txt", b"FILE-A");
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+    let endpoint = format!("{}/v1/chat/completions", server.url());
+    let service = SmartFetchService::new(create_test_config(endpoint, jobs_dir.path())).unwrap();
+
+    let resume = BatchOptions {
+        resume: true,
+        ..Default::default()
+    };
+    let error = service
+        .extract_batch(input_dir.path(), output_dir.path(), &resume)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("没有可续跑的任务"), "{}", error);
+
+    service
+        .extract_batch(
+            input_dir.path(),
+            output_dir.path(),
+            &BatchOptions::default(),
+        )
+        .await
+        .unwrap();
+    // 提示词与原任务不同时不能续跑
+    let error = service
+        .extract_batch(
+            input_dir.path(),
+            output_dir.path(),
+            &BatchOptions {
+                custom_prompt: Some("只提取标题".to_string()),
+                ..resume
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("无法续跑"), "{}", error);
+    mock.assert_async().await;
+}
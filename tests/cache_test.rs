@@ -0,0 +1,261 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{
+    AppConfig, CacheConfig, ChatMessage, ExtractionProgress, ExtractionStage, LLMConfig, LlmOutput,
+    ProgressCallback, ResponseCache, SmartFetchService,
+};
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn create_test_config(endpoint: String, cache_dir: &Path) -> AppConfig {
+    let mut config = test_config(endpoint);
+    config.llm.model = "test-model".to_string();
+    config.cache = Some(CacheConfig {
+        enabled: Some(true),
+        cache_dir: Some(cache_dir.to_path_buf()),
+        ..Default::default()
+    });
+    config
+}
+
+fn user_message(content: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage {
+        role: "user".to_string(),
+        content: content.to_string(),
+    }]
+}
+
+fn output(content: &str) -> LlmOutput {
+    LlmOutput {
+        content: content.to_string(),
+        model: "test-model".to_string(),
+        usage: None,
+        cached: false,
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_extraction_uses_cache() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let endpoint = format!("{}/v1/chat/completions", server.url());
+    let first = SmartFetchService::new(create_test_config(endpoint.clone(), cache_dir.path()))
+        .unwrap()
+        .extract_from_text_with_progress("相同的文本", None, None, None, None, None)
+        .await
+        .unwrap();
+    assert!(!first.cached);
+
+    // 新的服务实例读取同一缓存目录
+    let second = SmartFetchService::new(create_test_config(endpoint, cache_dir.path()))
+        .unwrap()
+        .extract_from_text_with_progress("相同的文本", None, None, None, None, None)
+        .await
+        .unwrap();
+    assert!(second.cached);
+    assert_eq!(second.content, "提取结果");
+    assert_eq!(second.model, "test-model");
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_cache_hit_reported_as_progress() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let chunk = json!({
+        "choices": [{ "index": 0, "delta": { "content": "流式结果" }, "finish_reason": "stop" }]
+    });
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(format!("data: {}\n\ndata: [DONE]\n\n", chunk))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        cache_dir.path(),
+    );
+    config.llm.stream = Some(true);
+    let service = SmartFetchService::new(config).unwrap();
+    service
+        .extract_from_text_with_progress("文本", None, None, None, None, None)
+        .await
+        .unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorder = events.clone();
+    let progress: ProgressCallback = Arc::new(move |event: &ExtractionProgress| {
+        recorder.lock().unwrap().push(event.clone());
+    });
+    let result = service
+        .extract_from_text_with_progress("文本", None, None, None, None, Some(progress))
+        .await
+        .unwrap();
+
+    assert!(result.cached);
+    mock.assert_async().await;
+
+    let events = events.lock().unwrap();
+    let hit = events
+        .iter()
+        .find(|event| event.stage == ExtractionStage::Generating)
+        .unwrap();
+    assert!(hit.message.contains("命中响应缓存"), "{}", hit.message);
+    // 流式模式下缓存内容作为一次文本片段输出
+    assert_eq!(hit.delta.as_deref(), Some("流式结果"));
+}
+
+#[tokio::test]
+async fn test_disabled_cache_calls_model_every_time() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("提取结果", true))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        cache_dir.path(),
+    );
+    config.cache.as_mut().unwrap().enabled = Some(false);
+    let service = SmartFetchService::new(config).unwrap();
+    for _ in 0..2 {
+        let result = service
+            .extract_from_text_with_progress("文本", None, None, None, None, None)
+            .await
+            .unwrap();
+        assert!(!result.cached);
+    }
+
+    mock.assert_async().await;
+    assert_eq!(std::fs::read_dir(cache_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_cache_key_covers_prompt_and_sampling_parameters() {
+    let config = LLMConfig::default();
+    let key = ResponseCache::key(&config, &user_message("提示词"), None);
+    assert_eq!(key.len(), 64);
+    assert_eq!(
+        key,
+        ResponseCache::key(&config, &user_message("提示词"), None)
+    );
+    assert_ne!(
+        key,
+        ResponseCache::key(&config, &user_message("另一个提示词"), None)
+    );
+
+    let other_model = LLMConfig {
+        model: "other-model".to_string(),
+        ..LLMConfig::default()
+    };
+    assert_ne!(
+        key,
+        ResponseCache::key(&other_model, &user_message("提示词"), None)
+    );
+
+    let other_temperature = LLMConfig {
+        temperature: Some(0.1),
+        ..LLMConfig::default()
+    };
+    assert_ne!(
+        key,
+        ResponseCache::key(&other_temperature, &user_message("提示词"), None)
+    );
+}
+
+#[test]
+fn test_expired_entries_and_maintenance() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::new(&CacheConfig {
+        enabled: Some(true),
+        cache_dir: Some(cache_dir.path().to_path_buf()),
+        ttl_seconds: Some(3600),
+        max_size_mb: Some(0.0),
+    });
+
+    cache.put("fresh", &output("新结果")).unwrap();
+    std::fs::write(
+        cache_dir.path().join("stale.json"),
+        json!({
+            "model": "test-model",
+            "content": "旧结果",
+            "usage": null,
+            "created_at": "2000-01-01T00:00:00Z"
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let stats = cache.stats().unwrap();
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.expired, 1);
+    assert!(stats.size_bytes > 0);
+
+    assert_eq!(cache.get("fresh").unwrap().content, "新结果");
+    assert!(cache.get("stale").is_none());
+    assert!(!cache_dir.path().join("stale.json").exists());
+
+    cache.put("another", &output("另一个结果")).unwrap();
+    assert_eq!(cache.prune().unwrap(), 0);
+    assert_eq!(cache.clear().unwrap(), 2);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+}
+
+#[test]
+fn test_size_limit_evicts_least_recently_used() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let content = "x".repeat(200);
+    let entry_size = {
+        let probe = ResponseCache::new(&CacheConfig {
+            cache_dir: Some(cache_dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        probe.put("probe", &output(&content)).unwrap();
+        let size = std::fs::metadata(cache_dir.path().join("probe.json"))
+            .unwrap()
+            .len();
+        probe.clear().unwrap();
+        size
+    };
+
+    // 上限只够容纳两个条目
+    let cache = ResponseCache::new(&CacheConfig {
+        enabled: Some(true),
+        cache_dir: Some(cache_dir.path().to_path_buf()),
+        ttl_seconds: Some(0),
+        max_size_mb: Some((entry_size * 2 + entry_size / 2) as f64 / 1024.0 / 1024.0),
+    });
+    cache.put("first", &output(&content)).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    cache.put("second", &output(&content)).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    // 读取刷新 first 的使用时间，second 成为最久未使用的条目
+    assert!(cache.get("first").is_some());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    cache.put("third", &output(&content)).unwrap();
+
+    assert!(cache.get("first").is_some());
+    assert!(cache.get("second").is_none());
+    assert!(cache.get("third").is_some());
+}
@@ -0,0 +1,54 @@
+//! 集成测试共用的模拟 OpenAI 响应和测试配置
+#![allow(dead_code)]
+
+use mcp_smart_fetch::AppConfig;
+use serde_json::json;
+
+/// 模拟 OpenAI 非流式补全响应，`usage` 为 true 时附带 10 + 5 = 15 个 token 的用量
+pub fn completion_body(content: &str, usage: bool) -> String {
+    let mut body = json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "test-model",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }]
+    });
+    if usage {
+        body["usage"] = json!({ "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 });
+    }
+    body.to_string()
+}
+
+/// 模拟 OpenAI 流式补全响应，`usage` 为 true 时在 `[DONE]` 前附带与 [`completion_body`] 相同的用量数据块
+pub fn stream_body(content: &str, usage: bool) -> String {
+    let chunk = json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion.chunk",
+        "choices": [{ "index": 0, "delta": { "content": content }, "finish_reason": "stop" }]
+    });
+    let mut body = format!("data: {}\n\n", chunk);
+    if usage {
+        let usage_chunk = json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion.chunk",
+            "choices": [],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        });
+        body.push_str(&format!("data: {}\n\n", usage_chunk));
+    }
+    body.push_str("data: [DONE]\n\n");
+    body
+}
+
+/// 指向模拟端点、使用测试密钥且关闭流式输出的配置
+pub fn test_config(endpoint: String) -> AppConfig {
+    let mut config = AppConfig::default();
+    config.llm.api_endpoint = endpoint;
+    config.llm.api_key = Some("test-api-key".to_string());
+    config.llm.stream = Some(false);
+    config
+}
@@ -0,0 +1,234 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{
+    classify_failure, AppConfig, FailureKind, LLMClient, LLMProfile, RetryConfig,
+    SmartFetchService, TokenizerConfig,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// 主模型使用 `/primary`，备用模型使用 `/backup`
+fn create_test_config(server_url: &str) -> AppConfig {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        "backup".to_string(),
+        LLMProfile {
+            api_endpoint: Some(format!("{}/backup/chat/completions", server_url)),
+            model: Some("backup-model".to_string()),
+            ..Default::default()
+        },
+    );
+
+    let mut config = test_config(format!("{}/primary/chat/completions", server_url));
+    config.llm.model = "primary-model".to_string();
+    config.llm.retry = Some(RetryConfig {
+        max_retries: Some(0),
+        initial_backoff_ms: Some(1),
+        max_backoff_ms: Some(5),
+    });
+    config.llm.profiles = Some(profiles);
+    config.llm.fallback = Some(vec!["backup".to_string()]);
+    config
+}
+
+#[tokio::test]
+async fn test_unavailable_primary_falls_back() {
+    let mut server = mockito::Server::new_async().await;
+    let primary = server
+        .mock("POST", "/primary/chat/completions")
+        .with_status(503)
+        .with_body("service unavailable")
+        .expect(1)
+        .create_async()
+        .await;
+    let backup = server
+        .mock("POST", "/backup/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("备用模型结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let service = SmartFetchService::new(create_test_config(&server.url())).unwrap();
+    let output = service
+        .extract_from_text_with_progress("文本", None, None, None, None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(output.content, "备用模型结果");
+    assert_eq!(output.model, "backup-model");
+    assert_eq!(output.usage.unwrap().total_tokens, 15);
+    primary.assert_async().await;
+    backup.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_context_length_error_falls_back() {
+    let mut server = mockito::Server::new_async().await;
+    let primary = server
+        .mock("POST", "/primary/chat/completions")
+        .with_status(400)
+        .with_body(
+            r#"{"error":{"code":"context_length_exceeded","message":"This model's maximum context length is 8192 tokens."}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let backup = server
+        .mock("POST", "/backup/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("长上下文结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = LLMClient::new(create_test_config(&server.url()).llm).unwrap();
+    let result = client.generate_response("很长的文本").await.unwrap();
+
+    assert_eq!(result, "长上下文结果");
+    primary.assert_async().await;
+    backup.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_context_window_check_considers_fallback_models() {
+    let mut server = mockito::Server::new_async().await;
+    let primary = server
+        .mock("POST", "/primary/chat/completions")
+        .with_status(400)
+        .with_body(r#"{"error":{"code":"context_length_exceeded"}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let backup = server
+        .mock("POST", "/backup/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("长上下文结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    // 主模型放不下的请求不在发送前报错，而是交给上下文窗口更大的备用模型
+    let mut config = create_test_config(&server.url());
+    config.tokenizer = Some(TokenizerConfig {
+        encoding: Some("estimate".to_string()),
+        vocab_dir: None,
+        on_overflow: Some("error".to_string()),
+    });
+    config.llm.max_tokens = Some(100);
+    config.llm.context_window = Some(300);
+    if let Some(backup) = config
+        .llm
+        .profiles
+        .as_mut()
+        .and_then(|profiles| profiles.get_mut("backup"))
+    {
+        backup.context_window = Some(100_000);
+    }
+    let service = SmartFetchService::new(config).unwrap();
+    let output = service
+        .extract_from_text_with_progress(&"长文本内容。".repeat(40), None, None, None, None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(output.content, "长上下文结果");
+    assert_eq!(output.model, "backup-model");
+    primary.assert_async().await;
+    backup.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_fatal_errors_do_not_fall_back() {
+    let mut server = mockito::Server::new_async().await;
+    let primary = server
+        .mock("POST", "/primary/chat/completions")
+        .with_status(401)
+        .with_body(r#"{"error": "invalid api key"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let backup = server
+        .mock("POST", "/backup/chat/completions")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let client = LLMClient::new(create_test_config(&server.url()).llm).unwrap();
+    let error = client.generate_response("测试").await.unwrap_err();
+
+    assert!(error.to_string().contains("401"), "{}", error);
+    primary.assert_async().await;
+    backup.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_overloaded_stream_falls_back() {
+    let mut server = mockito::Server::new_async().await;
+    let _primary = server
+        .mock("POST", "/primary/chat/completions")
+        .with_status(429)
+        .with_body("rate limited")
+        .create_async()
+        .await;
+    let chunk = json!({
+        "choices": [{ "index": 0, "delta": { "content": "流式备用" }, "finish_reason": "stop" }]
+    });
+    let _backup = server
+        .mock("POST", "/backup/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(format!("data: {}\n\ndata: [DONE]\n\n", chunk))
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(&server.url());
+    config.llm.stream = Some(true);
+    let service = SmartFetchService::new(config).unwrap();
+    let output = service
+        .extract_from_text_with_progress("文本", None, None, None, None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(output.content, "流式备用");
+    assert_eq!(output.model, "backup-model");
+}
+
+#[test]
+fn test_failure_classification_and_config() {
+    let cases = [
+        (503, "", FailureKind::Unavailable),
+        (500, "internal error", FailureKind::Unavailable),
+        (429, "", FailureKind::Overloaded),
+        (529, "overloaded_error", FailureKind::Overloaded),
+        (
+            400,
+            "prompt is too long: 210000 tokens > 200000 maximum",
+            FailureKind::ContextTooLong,
+        ),
+        (400, "invalid temperature", FailureKind::Fatal),
+        (401, "", FailureKind::Fatal),
+    ];
+    for (status, body, expected) in cases {
+        assert_eq!(
+            classify_failure(status.try_into().unwrap(), body),
+            expected,
+            "{} {}",
+            status,
+            body
+        );
+    }
+    assert!(!FailureKind::Fatal.triggers_fallback());
+
+    let mut config = create_test_config("http://localhost").llm;
+    config.fallback = Some(vec!["missing".to_string()]);
+    let error = AppConfig::validate_llm_config(&config).unwrap_err();
+    assert!(
+        error.to_string().contains("未知的模型配置: missing"),
+        "{}",
+        error
+    );
+}
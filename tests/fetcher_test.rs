@@ -0,0 +1,130 @@
+use mcp_smart_fetch::{AppConfig, FetchConfig, SmartFetchService, UrlFetcher};
+use mockito::Matcher;
+use serde_json::json;
+
+const TEST_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>测试页面</title>
+  <style>body { color: red; }</style>
+</head>
+<body>
+  <script>console.log("tracking");</script>
+  <h1>页面标题</h1>
+  <p>这是网页的正文内容。</p>
+  <p>第二段&amp;更多内容</p>
+</body>
+</html>"#;
+
+#[tokio::test]
+async fn test_fetch_html_page() {
+    let mut server = mockito::Server::new_async().await;
+    let _page = server
+        .mock("GET", "/page")
+        .with_status(200)
+        .with_header("content-type", "text/html; charset=utf-8")
+        .with_body(TEST_PAGE)
+        .create_async()
+        .await;
+
+    let fetcher = UrlFetcher::new(FetchConfig::default()).unwrap();
+    let page = fetcher.fetch(&format!("{}/page", server.url())).await.unwrap();
+
+    assert_eq!(page.content_type, "text/html");
+    assert_eq!(page.title, Some("测试页面".to_string()));
+    assert!(page.content.contains("这是网页的正文内容。"));
+    assert!(page.content.contains("第二段&更多内容"));
+    assert!(!page.content.contains("console.log"));
+    assert!(!page.content.contains("color: red"));
+    assert!(!page.content.contains("<p>"));
+}
+
+#[tokio::test]
+async fn test_fetch_respects_size_limit() {
+    let mut server = mockito::Server::new_async().await;
+    let _page = server
+        .mock("GET", "/large")
+        .with_status(200)
+        .with_header("content-type", "text/plain")
+        .with_body("a".repeat(4096))
+        .create_async()
+        .await;
+
+    let config = FetchConfig {
+        max_size_mb: Some(0.001),
+        ..Default::default()
+    };
+    let fetcher = UrlFetcher::new(config).unwrap();
+    let result = fetcher.fetch(&format!("{}/large", server.url())).await;
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("大小超过限制"));
+}
+
+#[tokio::test]
+async fn test_fetch_rejects_unsupported_scheme_and_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let _missing = server
+        .mock("GET", "/missing")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let fetcher = UrlFetcher::new(FetchConfig::default()).unwrap();
+
+    assert!(fetcher.fetch("file:///etc/passwd").await.is_err());
+    assert!(fetcher.fetch("not a url").await.is_err());
+    assert!(fetcher
+        .fetch(&format!("{}/missing", server.url()))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_extract_from_url_pipeline() {
+    let mut server = mockito::Server::new_async().await;
+    let _page = server
+        .mock("GET", "/article")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(TEST_PAGE)
+        .create_async()
+        .await;
+    let llm = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex("这是网页的正文内容".to_string()),
+            Matcher::Regex("测试页面".to_string()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "网页提取结果" },
+                    "finish_reason": "stop"
+                }]
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let mut config = AppConfig::default();
+    config.llm.api_endpoint = format!("{}/v1/chat/completions", server.url());
+    config.llm.api_key = Some("test-api-key".to_string());
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service
+        .extract_from_url(&format!("{}/article", server.url()), None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "网页提取结果");
+    llm.assert_async().await;
+}
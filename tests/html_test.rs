@@ -0,0 +1,108 @@
+use mcp_smart_fetch::{AppConfig, DocumentProcessor, HtmlConfig, HtmlConverter};
+use std::path::PathBuf;
+
+const ARTICLE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>  测试 文章 </title>
+  <style>.nav { display: none; }</style>
+  <script>window.analytics = true;</script>
+</head>
+<body>
+  <nav><a href="/">首页</a> <a href="/about">关于</a></nav>
+  <h1>主标题</h1>
+  <p>这是<strong>重要</strong>的段落，参见 <a href="https://example.com/spec">规范</a>。</p>
+  <h2>列表</h2>
+  <ul>
+    <li>第一项</li>
+    <li>第二项
+      <ol><li>子项</li></ol>
+    </li>
+  </ul>
+  <table>
+    <tr><th>名称</th><th>数值</th></tr>
+    <tr><td>a|b</td><td>1</td></tr>
+  </table>
+  <pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+  <footer>版权所有</footer>
+</body>
+</html>"#;
+
+#[test]
+fn test_html_to_markdown_structure() {
+    let markdown = HtmlConverter::default().convert(ARTICLE);
+
+    assert!(markdown.contains("# 主标题"));
+    assert!(markdown.contains("## 列表"));
+    assert!(markdown.contains("这是**重要**的段落，参见 规范。"));
+    assert!(markdown.contains("- 第一项"));
+    assert!(markdown.contains("- 第二项"));
+    assert!(markdown.contains("  1. 子项"));
+    assert!(markdown.contains("| 名称 | 数值 |"));
+    assert!(markdown.contains("| --- | --- |"));
+    assert!(markdown.contains("| a\\|b | 1 |"));
+    assert!(markdown.contains("```rust\nfn main() {\n    println!(\"hi\");\n}\n```"));
+}
+
+#[test]
+fn test_html_to_markdown_removes_boilerplate() {
+    let markdown = HtmlConverter::default().convert(ARTICLE);
+
+    assert!(!markdown.contains("window.analytics"));
+    assert!(!markdown.contains("display: none"));
+    assert!(!markdown.contains("首页"));
+    assert!(!markdown.contains("版权所有"));
+    assert!(!markdown.contains('<'));
+}
+
+#[test]
+fn test_html_to_markdown_keep_links() {
+    let converter = HtmlConverter::new(HtmlConfig {
+        keep_links: Some(true),
+        remove_boilerplate: Some(false),
+    });
+    let markdown = converter.convert(ARTICLE);
+
+    assert!(markdown.contains("[规范](https://example.com/spec)"));
+    // 关闭模板内容移除后保留导航和页脚
+    assert!(markdown.contains("[首页](/)"));
+    assert!(markdown.contains("版权所有"));
+}
+
+#[test]
+fn test_html_prefers_main_content() {
+    let html = "<body><div>侧边栏</div><main><p>主要内容</p></main></body>";
+    let markdown = HtmlConverter::default().convert(html);
+
+    assert_eq!(markdown, "主要内容");
+}
+
+#[test]
+fn test_html_title_extraction() {
+    assert_eq!(
+        HtmlConverter::extract_title(ARTICLE),
+        Some("测试 文章".to_string())
+    );
+    assert_eq!(HtmlConverter::extract_title("<p>没有标题</p>"), None);
+}
+
+#[tokio::test]
+async fn test_load_html_document_as_markdown() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let html_path = temp_dir.path().join("article.html");
+    tokio::fs::write(&html_path, ARTICLE).await.unwrap();
+
+    let processor = DocumentProcessor::new(AppConfig::default().processing).unwrap();
+    let document = processor.load_document(&html_path).await.unwrap();
+
+    assert_eq!(document.content_type, "text/html");
+    assert_eq!(document.metadata.title, Some("测试 文章".to_string()));
+    assert!(document.content.contains("# 主标题"));
+    assert!(!document.content.contains("<p>"));
+    assert_eq!(
+        DocumentProcessor::detect_content_type(&PathBuf::from("index.htm")).unwrap(),
+        "text/html"
+    );
+}
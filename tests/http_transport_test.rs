@@ -0,0 +1,124 @@
+use mcp_smart_fetch::{AppConfig, McpSmartFetchServer, SmartFetchService};
+use serde_json::json;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+async fn start_http_server(max_connections: Option<u32>) -> (SocketAddr, CancellationToken) {
+    let mut config = AppConfig::default();
+    config.llm.api_key = Some("test-api-key".to_string());
+    let service = SmartFetchService::new(config).unwrap();
+    let server = McpSmartFetchServer::new(service);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let ct = CancellationToken::new();
+    let server_ct = ct.clone();
+    tokio::spawn(async move {
+        server
+            .serve_http(listener, max_connections, server_ct)
+            .await
+            .unwrap();
+    });
+
+    (addr, ct)
+}
+
+fn initialize_request() -> serde_json::Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1.0" }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_streamable_http_initialize() {
+    let (addr, ct) = start_http_server(None).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/mcp", addr))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&initialize_request())
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    assert!(response.headers().contains_key("mcp-session-id"));
+
+    let body = tokio::time::timeout(Duration::from_secs(5), response.text())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(body.contains("mcp-smart-fetch"));
+
+    ct.cancel();
+}
+
+#[tokio::test]
+async fn test_sse_endpoint_announces_message_path() {
+    let (addr, ct) = start_http_server(None).await;
+
+    let mut response = reqwest::Client::new()
+        .get(format!("http://{}/sse", addr))
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = String::from_utf8_lossy(&chunk);
+    assert!(event.contains("endpoint"));
+    assert!(event.contains("/message?sessionId="));
+
+    ct.cancel();
+}
+
+#[tokio::test]
+async fn test_http_max_connections() {
+    let (addr, ct) = start_http_server(Some(1)).await;
+
+    // 占用唯一的连接名额
+    let holder = tokio::net::TcpStream::connect(addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .build()
+        .unwrap();
+    let blocked = client
+        .post(format!("http://{}/mcp", addr))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&initialize_request())
+        .send()
+        .await;
+    assert!(blocked.is_err());
+
+    // 释放连接后新的请求可以被处理
+    drop(holder);
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+    let response = client
+        .post(format!("http://{}/mcp", addr))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&initialize_request())
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    ct.cancel();
+}
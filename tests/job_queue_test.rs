@@ -0,0 +1,187 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{
+    ExtractionProgress, ExtractionSource, ExtractionStage, JobQueue, JobsConfig, LlmOutput,
+    QueuedJobInfo, QueuedJobOutcome, QueuedJobStatus, SmartFetchService,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn output(content: &str) -> LlmOutput {
+    LlmOutput {
+        content: content.to_string(),
+        model: "test-model".to_string(),
+        usage: None,
+        cached: false,
+    }
+}
+
+fn queue(max_concurrent: usize, max_retained: usize) -> Arc<JobQueue> {
+    Arc::new(JobQueue::new(&JobsConfig {
+        max_concurrent: Some(max_concurrent),
+        max_retained: Some(max_retained),
+        ..Default::default()
+    }))
+}
+
+/// 等待任务进入指定状态
+async fn wait_for(queue: &JobQueue, id: &str, status: QueuedJobStatus) -> QueuedJobInfo {
+    for _ in 0..200 {
+        let job = queue.status(id).unwrap();
+        if job.status == status {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("任务 {} 未进入 {} 状态", id, status);
+}
+
+#[tokio::test]
+async fn test_submit_extraction_runs_in_background() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("后台提取结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = test_config(format!("{}/v1/chat/completions", server.url()));
+    let service = Arc::new(SmartFetchService::new(config).unwrap());
+
+    let id = service
+        .submit_extraction(
+            ExtractionSource::Text("需要提取的文本".to_string()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    let job = wait_for(service.job_queue(), &id, QueuedJobStatus::Succeeded).await;
+    assert_eq!(job.progress, 100.0);
+    assert!(job.source.contains("文本"), "{}", job.source);
+
+    match service.job_queue().result(&id).unwrap().1 {
+        Some(QueuedJobOutcome::Succeeded(output)) => assert_eq!(output.content, "后台提取结果"),
+        other => panic!("unexpected outcome: {:?}", other),
+    }
+    mock.assert_async().await;
+
+    // 模板不存在时不创建任务
+    let error = service
+        .submit_extraction(
+            ExtractionSource::Text("文本".to_string()),
+            None,
+            Some("missing-template".to_string()),
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("missing-template"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_queue_reports_progress_and_cancels_jobs() {
+    let queue = queue(1, 10);
+    let running = queue.submit("第一个任务".to_string(), |progress| async move {
+        progress(&ExtractionProgress {
+            stage: ExtractionStage::Map,
+            completed: 1,
+            total: 4,
+            message: "已完成 1/4 个分块".to_string(),
+            delta: None,
+            generated_chars: 0,
+        });
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(output("不会完成"))
+    });
+    let queued = queue.submit("第二个任务".to_string(), |_| async {
+        Ok(output("第二个结果"))
+    });
+
+    let job = wait_for(&queue, &running, QueuedJobStatus::Running).await;
+    assert_eq!(job.source, "第一个任务");
+    for _ in 0..200 {
+        if queue.status(&running).unwrap().progress > 0.0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let job = queue.status(&running).unwrap();
+    assert_eq!(job.progress, 25.0);
+    assert_eq!(job.message.as_deref(), Some("已完成 1/4 个分块"));
+    // 同时只运行一个任务
+    assert_eq!(
+        queue.status(&queued).unwrap().status,
+        QueuedJobStatus::Queued
+    );
+
+    let job = queue.cancel(&running).unwrap();
+    assert_eq!(job.status, QueuedJobStatus::Cancelled);
+    assert!(matches!(
+        queue.result(&running).unwrap().1,
+        Some(QueuedJobOutcome::Cancelled)
+    ));
+
+    // 取消后排队的任务获得执行名额
+    wait_for(&queue, &queued, QueuedJobStatus::Succeeded).await;
+    // 已结束的任务不能取消
+    assert_eq!(
+        queue.cancel(&queued).unwrap().status,
+        QueuedJobStatus::Succeeded
+    );
+    assert!(queue.cancel("unknown-job").is_err());
+}
+
+#[tokio::test]
+async fn test_finished_jobs_are_pruned_beyond_retention_limit() {
+    let queue = queue(2, 1);
+    let first = queue.submit("第一个任务".to_string(), |_| async { Ok(output("1")) });
+    wait_for(&queue, &first, QueuedJobStatus::Succeeded).await;
+    let second = queue.submit("第二个任务".to_string(), |_| async {
+        Err(mcp_smart_fetch::SmartFetchError::LlmApiError(
+            "调用失败".to_string(),
+        ))
+    });
+    let job = wait_for(&queue, &second, QueuedJobStatus::Failed).await;
+    assert!(job.error.unwrap().contains("调用失败"));
+
+    // 只保留最近结束的一个任务
+    let error = queue.status(&first).unwrap_err();
+    assert!(error.to_string().contains("不存在或已过期"), "{}", error);
+    assert!(queue.status(&second).is_ok());
+}
+
+#[tokio::test]
+async fn test_recorded_results_are_kept_apart_from_jobs() {
+    let queue = Arc::new(JobQueue::new(&JobsConfig {
+        max_retained: Some(1),
+        max_recorded_results: Some(2),
+        ..Default::default()
+    }));
+    let job = queue.submit("后台任务".to_string(), |_| async { Ok(output("任务结果")) });
+    wait_for(&queue, &job, QueuedJobStatus::Succeeded).await;
+
+    let recorded: Vec<String> = (0..3)
+        .map(|i| queue.record(format!("同步提取 {}", i), output(&i.to_string())))
+        .collect();
+
+    // 同步结果不计入后台任务的保留数量，也不作为任务出现
+    assert!(queue.result(&job).unwrap().1.is_some());
+    assert_eq!(queue.list().len(), 1);
+    assert!(queue.status(&recorded[2]).is_err());
+
+    // 同步结果按自己的上限删除最早的记录
+    assert!(queue.recorded(&recorded[0]).is_err());
+    assert_eq!(queue.recorded(&recorded[2]).unwrap().output.content, "2");
+    let sources: Vec<String> = queue
+        .recorded_results()
+        .into_iter()
+        .map(|result| result.source)
+        .collect();
+    assert_eq!(sources, ["同步提取 1", "同步提取 2"]);
+}
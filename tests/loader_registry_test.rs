@@ -0,0 +1,186 @@
+use mcp_smart_fetch::{
+    AppConfig, Document, DocumentLoader, DocumentProcessor, LoadedContent, LoaderRegistry, Result,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 模拟业务方自定义的内部格式：每行 `key=value`
+struct KeyValueLoader;
+
+impl DocumentLoader for KeyValueLoader {
+    fn name(&self) -> &str {
+        "键值配置"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["kv"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/x-key-value"]
+    }
+
+    fn matches_content(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"#KV")
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+        let content = String::from_utf8_lossy(bytes)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| format!("- **{}**: {}", key.trim(), value.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let loaded = LoadedContent {
+            content,
+            ..Default::default()
+        };
+        Ok(Document::from_loaded(
+            path,
+            self.content_type(),
+            bytes.len(),
+            loaded,
+        ))
+    }
+}
+
+fn builtin_registry() -> LoaderRegistry {
+    LoaderRegistry::with_builtin(&AppConfig::default().processing)
+}
+
+#[test]
+fn test_builtin_registry_lookup() {
+    let registry = builtin_registry();
+
+    assert_eq!(registry.find_by_extension("PDF").unwrap().name(), "PDF");
+    assert_eq!(
+        registry.find_by_extension(".md").unwrap().content_type(),
+        "text/markdown"
+    );
+    assert_eq!(
+        registry
+            .find_by_mime("text/html; charset=utf-8")
+            .unwrap()
+            .name(),
+        "HTML"
+    );
+    assert!(registry.find_by_extension("exe").is_none());
+
+    let formats = registry.supported_formats();
+    for format in ["txt", "md", "html", "pdf", "docx", "odt", "epub"] {
+        assert!(formats.contains(&format.to_string()), "缺少格式 {}", format);
+    }
+}
+
+#[test]
+fn test_detect_by_magic_bytes() {
+    let registry = builtin_registry();
+
+    let pdf = registry
+        .detect(Path::new("download"), b"%PDF-1.7\n...")
+        .unwrap();
+    assert_eq!(pdf.name(), "PDF");
+
+    let html = registry
+        .detect(Path::new("page"), b"  <!DOCTYPE html><html></html>")
+        .unwrap();
+    assert_eq!(html.name(), "HTML");
+
+    // 扩展名优先于文件内容
+    let text = registry
+        .detect(Path::new("notes.txt"), b"%PDF-1.7")
+        .unwrap();
+    assert_eq!(text.content_type(), "text/plain");
+
+    // 无法识别时退回到纯文本
+    let fallback = registry.detect(Path::new("README"), b"hello").unwrap();
+    assert_eq!(fallback.content_type(), "text/plain");
+}
+
+#[test]
+fn test_custom_loader_registration() {
+    let mut registry = builtin_registry();
+    registry.register(KeyValueLoader);
+
+    assert!(registry.supported_formats().contains(&"kv".to_string()));
+    assert_eq!(
+        registry
+            .find_by_mime("application/x-key-value")
+            .unwrap()
+            .name(),
+        "键值配置"
+    );
+    assert_eq!(
+        registry
+            .detect(Path::new("settings"), b"#KV\nport=8080")
+            .unwrap()
+            .name(),
+        "键值配置"
+    );
+    assert!(format!("{:?}", registry).contains("键值配置"));
+}
+
+#[tokio::test]
+async fn test_processor_uses_custom_registry() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("server.kv");
+    tokio::fs::write(&path, "#KV\nhost = localhost\nport = 8080")
+        .await
+        .unwrap();
+
+    let mut registry = builtin_registry();
+    registry.register(KeyValueLoader);
+    let processor = DocumentProcessor::new(AppConfig::default().processing)
+        .unwrap()
+        .with_loader_registry(Arc::new(registry));
+
+    let document = processor.load_document(&path).await.unwrap();
+    assert_eq!(document.content_type, "application/x-key-value");
+    assert_eq!(document.content, "- **host**: localhost\n- **port**: 8080");
+    assert_eq!(document.metadata.line_count, 2);
+
+    // 默认注册表不认识该格式，按纯文本读取
+    let default_processor = DocumentProcessor::new(AppConfig::default().processing).unwrap();
+    let document = default_processor.load_document(&path).await.unwrap();
+    assert_eq!(document.content_type, "text/plain");
+}
+
+#[test]
+fn test_later_registration_overrides_builtin() {
+    struct UppercaseTextLoader;
+
+    impl DocumentLoader for UppercaseTextLoader {
+        fn name(&self) -> &str {
+            "大写文本"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+
+        fn mime_types(&self) -> &[&str] {
+            &["text/plain"]
+        }
+
+        fn load(&self, path: &Path, bytes: &[u8]) -> Result<Document> {
+            let loaded = LoadedContent {
+                content: String::from_utf8_lossy(bytes).to_uppercase(),
+                ..Default::default()
+            };
+            Ok(Document::from_loaded(
+                path,
+                "text/plain",
+                bytes.len(),
+                loaded,
+            ))
+        }
+    }
+
+    let mut registry = builtin_registry();
+    registry.register(UppercaseTextLoader);
+
+    let loader = registry.find_by_extension("txt").unwrap();
+    assert_eq!(loader.name(), "大写文本");
+    let document = loader.load(Path::new("a.txt"), b"hello").unwrap();
+    assert_eq!(document.content, "HELLO");
+}
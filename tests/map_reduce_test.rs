@@ -0,0 +1,120 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{AppConfig, ExtractionProgress, ExtractionStage, SmartFetchService};
+use std::sync::{Arc, Mutex};
+
+fn create_test_config(endpoint: String, chunk_size: usize) -> AppConfig {
+    let mut config = test_config(endpoint);
+    config.processing.chunk_size = Some(chunk_size);
+    config
+}
+
+#[tokio::test]
+async fn test_map_reduce_calls_llm_per_chunk_and_reduce() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("部分结果", true))
+        .expect(4)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 200);
+    let service = SmartFetchService::new(config).unwrap();
+
+    // 三段各约 150 字节的文本，分块后得到 3 个块
+    let text = ["第一段内容。".repeat(8), "第二段内容。".repeat(8), "第三段内容。".repeat(8)]
+        .join("\n");
+
+    let events: Arc<Mutex<Vec<ExtractionProgress>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorder = events.clone();
+    let result = service
+        .extract_from_text_with_progress(
+            &text,
+            None,
+            None,
+            None,
+            None,
+            Some(Arc::new(move |event: &ExtractionProgress| {
+                recorder.lock().unwrap().push(event.clone());
+            })),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.content, "部分结果");
+    mock.assert_async().await;
+
+    let events = events.lock().unwrap();
+    let map_events = events
+        .iter()
+        .filter(|event| event.stage == ExtractionStage::Map)
+        .count();
+    assert_eq!(map_events, 3);
+    let last = events.last().unwrap();
+    assert_eq!(last.stage, ExtractionStage::Completed);
+    assert_eq!(last.percent(), 100.0);
+}
+
+#[tokio::test]
+async fn test_short_content_uses_single_request() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("完整结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 4000);
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service.extract_from_text("一段简短的文本", None).await.unwrap();
+
+    assert_eq!(result, "完整结果");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_map_reduce_disabled_sends_whole_content() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("完整结果", true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(format!("{}/v1/chat/completions", server.url()), 100);
+    if let Some(map_reduce) = config.processing.map_reduce.as_mut() {
+        map_reduce.enable_map_reduce = Some(false);
+    }
+    let service = SmartFetchService::new(config).unwrap();
+
+    let text = "这是一段比分块大小更长的文本。".repeat(20);
+    let result = service.extract_from_text(&text, None).await.unwrap();
+
+    assert_eq!(result, "完整结果");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_map_reduce_missing_reduce_template() {
+    let mut config = create_test_config("http://127.0.0.1:9/v1/chat/completions".to_string(), 100);
+    if let Some(map_reduce) = config.processing.map_reduce.as_mut() {
+        map_reduce.reduce_template = Some("not_exists".to_string());
+    }
+    let service = SmartFetchService::new(config).unwrap();
+
+    let text = "这是一段比分块大小更长的文本。".repeat(20);
+    let err = service.extract_from_text(&text, None).await.unwrap_err();
+
+    assert!(err.to_string().contains("not_exists"));
+}
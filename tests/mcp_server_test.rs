@@ -1,4 +1,4 @@
-use mcp_smart_fetch::{AppConfig, SmartFetchService, McpSmartFetchServer};
+use mcp_smart_fetch::{AppConfig, SmartFetchService, McpSmartFetchServer, RetryConfig};
 use tempfile::NamedTempFile;
 use tokio::time::Duration;
 use serde_json::json;
@@ -10,6 +10,10 @@ fn create_test_config() -> AppConfig {
     config.llm.api_key = Some("test-api-key".to_string());
     // Reduce timeouts for faster testing
     config.llm.timeout_seconds = Some(5);
+    config.llm.retry = Some(RetryConfig {
+        max_retries: Some(0),
+        ..Default::default()
+    });
     config.processing.max_document_size_mb = Some(1.0);
     config
 }
@@ -120,10 +124,10 @@ async fn test_mcp_server_supported_formats() {
 
     // Test that the service can list supported formats
     // This would be exposed via the list_supported_formats MCP tool
-    let config = service.config();
+    let supported_formats = service.supported_formats();
 
-    assert!(config.processing.supported_formats.contains(&"txt".to_string()));
-    assert!(config.processing.supported_formats.contains(&"md".to_string()));
+    assert!(supported_formats.contains(&"txt".to_string()));
+    assert!(supported_formats.contains(&"md".to_string()));
 }
 
 #[tokio::test]
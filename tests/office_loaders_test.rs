@@ -0,0 +1,252 @@
+use mcp_smart_fetch::loaders::docx::load_docx;
+use mcp_smart_fetch::loaders::epub::load_epub;
+use mcp_smart_fetch::loaders::odt::load_odt;
+use mcp_smart_fetch::{AppConfig, DocumentProcessor, HtmlConverter};
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+fn build_zip(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+const DOCX_DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>项目概述</w:t></w:r></w:p>
+    <w:p><w:r><w:t xml:space="preserve">这是 </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>第一段</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Subheading"/></w:pPr><w:r><w:t>里程碑</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>需求分析</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>访谈</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>开发</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="2"/></w:numPr></w:pPr><w:r><w:t>风险</w:t></w:r></w:p>
+    <w:tbl>
+      <w:tr><w:tc><w:p><w:r><w:t>阶段</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>周期</w:t></w:r></w:p></w:tc></w:tr>
+      <w:tr><w:tc><w:p><w:r><w:t>设计</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>2 周</w:t></w:r></w:p></w:tc></w:tr>
+    </w:tbl>
+  </w:body>
+</w:document>"#;
+
+const DOCX_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="Subheading"><w:name w:val="heading 2"/></w:style>
+</w:styles>"#;
+
+const DOCX_NUMBERING: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:abstractNum w:abstractNumId="10">
+    <w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl>
+    <w:lvl w:ilvl="1"><w:numFmt w:val="bullet"/></w:lvl>
+  </w:abstractNum>
+  <w:abstractNum w:abstractNumId="11">
+    <w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/></w:lvl>
+  </w:abstractNum>
+  <w:num w:numId="1"><w:abstractNumId w:val="10"/></w:num>
+  <w:num w:numId="2"><w:abstractNumId w:val="11"/></w:num>
+</w:numbering>"#;
+
+const DOCX_CORE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties"
+  xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/">
+  <dc:title>项目计划</dc:title>
+  <dc:creator>张三</dc:creator>
+  <dcterms:created>2024-03-01T08:00:00Z</dcterms:created>
+</cp:coreProperties>"#;
+
+fn build_docx() -> Vec<u8> {
+    build_zip(&[
+        ("word/document.xml", DOCX_DOCUMENT),
+        ("word/styles.xml", DOCX_STYLES),
+        ("word/numbering.xml", DOCX_NUMBERING),
+        ("docProps/core.xml", DOCX_CORE),
+    ])
+}
+
+#[test]
+fn test_load_docx_to_markdown() {
+    let loaded = load_docx(&build_docx()).unwrap();
+
+    assert_eq!(
+        loaded.content,
+        "# 项目概述\n\n这是 第一段\n\n## 里程碑\n\n1. 需求分析\n  - 访谈\n2. 开发\n\n- 风险\n\n| 阶段 | 周期 |\n| --- | --- |\n| 设计 | 2 周 |"
+    );
+    assert_eq!(loaded.metadata.title, Some("项目计划".to_string()));
+    assert_eq!(loaded.metadata.author, Some("张三".to_string()));
+    assert_eq!(
+        loaded.metadata.created_at,
+        Some("2024-03-01T08:00:00Z".to_string())
+    );
+}
+
+#[test]
+fn test_load_docx_rejects_invalid_archive() {
+    assert!(load_docx(b"not a zip archive").is_err());
+
+    let missing_body = build_zip(&[("docProps/core.xml", DOCX_CORE)]);
+    let error = load_docx(&missing_body).unwrap_err();
+    assert!(error.to_string().contains("word/document.xml"));
+}
+
+const ODT_CONTENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+  xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"
+  xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0"
+  xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0">
+  <office:automatic-styles>
+    <text:list-style style:name="L1">
+      <text:list-level-style-number text:level="1"/>
+      <text:list-level-style-bullet text:level="2"/>
+    </text:list-style>
+  </office:automatic-styles>
+  <office:body>
+    <office:text>
+      <text:h text:outline-level="1">用户手册</text:h>
+      <text:p>欢迎使用<text:s text:c="2"/>本产品<text:note><text:note-body><text:p>脚注内容</text:p></text:note-body></text:note>。</text:p>
+      <text:h text:outline-level="2">安装步骤</text:h>
+      <text:list text:style-name="L1">
+        <text:list-item>
+          <text:p>下载安装包</text:p>
+          <text:list>
+            <text:list-item><text:p>选择版本</text:p></text:list-item>
+          </text:list>
+        </text:list-item>
+        <text:list-item><text:p>运行安装程序</text:p></text:list-item>
+      </text:list>
+      <table:table table:name="参数">
+        <table:table-row>
+          <table:table-cell><text:p>参数</text:p></table:table-cell>
+          <table:table-cell><text:p>说明</text:p></table:table-cell>
+        </table:table-row>
+        <table:table-row>
+          <table:table-cell><text:p>port</text:p></table:table-cell>
+          <table:table-cell><text:p>监听端口</text:p></table:table-cell>
+        </table:table-row>
+      </table:table>
+    </office:text>
+  </office:body>
+</office:document-content>"#;
+
+const ODT_META: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+  xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0"
+  xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <office:meta>
+    <dc:title>用户手册</dc:title>
+    <meta:initial-creator>李四</meta:initial-creator>
+    <meta:creation-date>2023-11-20T10:30:00</meta:creation-date>
+    <dc:date>2024-01-05T09:00:00</dc:date>
+  </office:meta>
+</office:document-meta>"#;
+
+#[test]
+fn test_load_odt_to_markdown() {
+    let odt = build_zip(&[("content.xml", ODT_CONTENT), ("meta.xml", ODT_META)]);
+    let loaded = load_odt(&odt).unwrap();
+
+    assert_eq!(
+        loaded.content,
+        "# 用户手册\n\n欢迎使用  本产品。\n\n## 安装步骤\n\n1. 下载安装包\n  - 选择版本\n2. 运行安装程序\n\n| 参数 | 说明 |\n| --- | --- |\n| port | 监听端口 |"
+    );
+    assert_eq!(loaded.metadata.title, Some("用户手册".to_string()));
+    assert_eq!(loaded.metadata.author, Some("李四".to_string()));
+    assert_eq!(
+        loaded.metadata.created_at,
+        Some("2023-11-20T10:30:00".to_string())
+    );
+    assert_eq!(
+        loaded.metadata.modified_at,
+        Some("2024-01-05T09:00:00".to_string())
+    );
+}
+
+const EPUB_CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+const EPUB_PACKAGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>运维指南</dc:title>
+    <dc:creator>王五</dc:creator>
+    <dc:date>2022-06-15</dc:date>
+  </metadata>
+  <manifest>
+    <item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch2" href="text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch1" href="text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine>
+    <itemref idref="cover" linear="no"/>
+    <itemref idref="ch1"/>
+    <itemref idref="ch2"/>
+  </spine>
+</package>"#;
+
+fn build_epub() -> Vec<u8> {
+    build_zip(&[
+        ("mimetype", "application/epub+zip"),
+        ("META-INF/container.xml", EPUB_CONTAINER),
+        ("OEBPS/content.opf", EPUB_PACKAGE),
+        ("OEBPS/cover.xhtml", "<html><body><p>封面</p></body></html>"),
+        (
+            "OEBPS/text/chapter1.xhtml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><html xmlns="http://www.w3.org/1999/xhtml"><body><h1>第一章 部署</h1><ul><li>准备服务器</li></ul></body></html>"#,
+        ),
+        (
+            "OEBPS/text/chapter 2.xhtml",
+            r#"<html xmlns="http://www.w3.org/1999/xhtml"><body><h1>第二章 监控</h1><p>配置告警。</p></body></html>"#,
+        ),
+    ])
+}
+
+#[test]
+fn test_load_epub_in_spine_order() {
+    let loaded = load_epub(&build_epub(), &HtmlConverter::default()).unwrap();
+
+    assert_eq!(
+        loaded.content,
+        "# 第一章 部署\n\n- 准备服务器\n\n# 第二章 监控\n\n配置告警。"
+    );
+    assert_eq!(loaded.metadata.title, Some("运维指南".to_string()));
+    assert_eq!(loaded.metadata.author, Some("王五".to_string()));
+    assert_eq!(loaded.metadata.created_at, Some("2022-06-15".to_string()));
+}
+
+#[tokio::test]
+async fn test_load_office_documents_by_extension() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let processor = DocumentProcessor::new(AppConfig::default().processing).unwrap();
+
+    let docx_path = temp_dir.path().join("plan.docx");
+    tokio::fs::write(&docx_path, build_docx()).await.unwrap();
+    let document = processor.load_document(&docx_path).await.unwrap();
+    assert_eq!(
+        document.content_type,
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    );
+    assert_eq!(document.metadata.title, Some("项目计划".to_string()));
+    assert!(document.content.contains("| 设计 | 2 周 |"));
+
+    let epub_path = temp_dir.path().join("guide.epub");
+    tokio::fs::write(&epub_path, build_epub()).await.unwrap();
+    let document = processor.load_document(&epub_path).await.unwrap();
+    assert_eq!(document.content_type, "application/epub+zip");
+    assert_eq!(document.metadata.author, Some("王五".to_string()));
+
+    assert_eq!(
+        DocumentProcessor::detect_content_type(&PathBuf::from("notes.odt")).unwrap(),
+        "application/vnd.oasis.opendocument.text"
+    );
+}
@@ -0,0 +1,174 @@
+use mcp_smart_fetch::loaders::pdf::load_pdf;
+use mcp_smart_fetch::{
+    extract_page_range, page_marker, AppConfig, DocumentProcessor, TemplateManager,
+};
+use std::path::PathBuf;
+
+/// 构造一个使用 Helvetica 字体、每页一行文本的最小 PDF
+fn build_pdf(pages: &[&str], title: &str, author: &str) -> Vec<u8> {
+    let page_count = pages.len();
+    let font_id = 3;
+    let first_page_id = 4;
+    let info_id = first_page_id + page_count * 2;
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..page_count)
+                .map(|i| format!("{} 0 R", first_page_id + i * 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+            page_count
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (i, text) in pages.iter().enumerate() {
+        let content_id = first_page_id + i * 2 + 1;
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 {} 0 R >> >> /Contents {} 0 R >>",
+            font_id, content_id
+        ));
+        let stream = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            stream.len(),
+            stream
+        ));
+    }
+    objects.push(format!(
+        "<< /Title ({}) /Author ({}) /CreationDate (D:20240102030405Z) >>",
+        title, author
+    ));
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            info_id,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+#[test]
+fn test_load_pdf_extracts_text_per_page() {
+    let pdf = build_pdf(
+        &["Introduction page", "Details page", "Summary page"],
+        "Test Report",
+        "Alice",
+    );
+    let loaded = load_pdf(&pdf).unwrap();
+
+    assert_eq!(loaded.metadata.page_count, Some(3));
+    assert_eq!(loaded.metadata.pages.len(), 3);
+    assert_eq!(loaded.metadata.title, Some("Test Report".to_string()));
+    assert_eq!(loaded.metadata.author, Some("Alice".to_string()));
+    assert_eq!(
+        loaded.metadata.created_at,
+        Some("2024-01-02 03:04:05".to_string())
+    );
+
+    let second = &loaded.metadata.pages[1];
+    assert_eq!(second.number, 2);
+    let second_text = &loaded.content[second.start..second.end];
+    assert!(second_text.starts_with(&page_marker(2)));
+    assert!(second_text.contains("Details page"));
+    assert!(!second_text.contains("Summary page"));
+}
+
+#[test]
+fn test_load_pdf_rejects_invalid_data() {
+    let result = load_pdf(b"this is not a pdf");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_extract_page_range() {
+    let content = format!(
+        "{}\n\n第一页\n\n{}\n\n第二页\n\n{}\n\n第三页",
+        page_marker(1),
+        page_marker(2),
+        page_marker(3)
+    );
+
+    let range = extract_page_range(&content, 2, 3);
+    assert!(!range.contains("第一页"));
+    assert!(range.contains("第二页"));
+    assert!(range.contains("第三页"));
+    assert_eq!(extract_page_range(&content, 5, 6), "");
+}
+
+#[test]
+fn test_page_range_template_helper() {
+    let templates_dir = tempfile::tempdir().unwrap();
+    let mut manager = TemplateManager::new(templates_dir.path()).unwrap();
+    manager
+        .register_template_string("pages", "{{{page_range content 2 2}}}")
+        .unwrap();
+
+    let content = format!("{}\n\nA\n\n{}\n\nB", page_marker(1), page_marker(2));
+    let rendered = manager.render_template("pages", &content, None).unwrap();
+
+    assert_eq!(rendered, format!("{}\n\nB", page_marker(2)));
+
+    manager
+        .register_template_string("broken", "{{page_range content}}")
+        .unwrap();
+    assert!(manager.render_template("broken", &content, None).is_err());
+}
+
+#[tokio::test]
+async fn test_load_pdf_document() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let pdf_path = temp_dir.path().join("report.pdf");
+    tokio::fs::write(&pdf_path, build_pdf(&["First", "Second"], "Report", "Bob"))
+        .await
+        .unwrap();
+
+    let processor = DocumentProcessor::new(AppConfig::default().processing).unwrap();
+    let document = processor.load_document(&pdf_path).await.unwrap();
+
+    assert_eq!(document.content_type, "application/pdf");
+    assert_eq!(document.metadata.page_count, Some(2));
+    assert_eq!(document.metadata.title, Some("Report".to_string()));
+    assert_eq!(
+        document.metadata.template_metadata().get("page_count"),
+        Some(&"2".to_string())
+    );
+    assert!(document.content.contains("Second"));
+}
+
+#[tokio::test]
+async fn test_binary_file_reports_clear_error() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let binary_path = temp_dir.path().join("data.bin");
+    tokio::fs::write(&binary_path, [0xFFu8, 0xFE, 0x00, 0x81])
+        .await
+        .unwrap();
+
+    let processor = DocumentProcessor::new(AppConfig::default().processing).unwrap();
+    let error = processor.load_document(&binary_path).await.unwrap_err();
+
+    assert!(error.to_string().contains("UTF-8"));
+    assert_eq!(
+        DocumentProcessor::detect_content_type(&PathBuf::from("a.PDF")).unwrap(),
+        "application/pdf"
+    );
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{AppConfig, LLMConfig, LLMProfile, SmartFetchService};
+use mockito::Matcher;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn create_test_config(endpoint: String) -> AppConfig {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        "fast".to_string(),
+        LLMProfile {
+            model: Some("fast-model".to_string()),
+            temperature: Some(0.0),
+            ..Default::default()
+        },
+    );
+    profiles.insert(
+        "accurate".to_string(),
+        LLMProfile {
+            model: Some("accurate-model".to_string()),
+            max_tokens: Some(8000),
+            ..Default::default()
+        },
+    );
+
+    let mut config = test_config(endpoint);
+    config.llm.model = "base-model".to_string();
+    config.llm.profiles = Some(profiles);
+    config
+}
+
+#[test]
+fn test_profiles_inherit_base_config() {
+    let config: LLMConfig = toml::from_str(
+        r#"
+        api_endpoint = "https://api.example.com/v1/chat/completions"
+        api_key = "base-key"
+        model = "base-model"
+        temperature = 0.7
+        default_profile = "cheap"
+
+        [profiles.cheap]
+        model = "cheap-model"
+
+        [profiles.local]
+        provider = "ollama"
+        api_endpoint = "http://localhost:11434/api/chat"
+        model = "qwen2.5"
+        temperature = 0.0
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.profile_names(), vec!["cheap", "local"]);
+
+    let cheap = config.profile("cheap").unwrap();
+    assert_eq!(cheap.model, "cheap-model");
+    assert_eq!(cheap.api_key.as_deref(), Some("base-key"));
+    assert_eq!(cheap.temperature, Some(0.7));
+    assert!(cheap.default_profile.is_none());
+
+    let local = config.profile("local").unwrap();
+    assert_eq!(local.provider.as_deref(), Some("ollama"));
+    assert_eq!(local.api_endpoint, "http://localhost:11434/api/chat");
+    assert_eq!(local.temperature, Some(0.0));
+
+    assert!(AppConfig::validate_llm_config(&config).is_ok());
+}
+
+#[test]
+fn test_invalid_profiles_rejected() {
+    let mut config = create_test_config("http://localhost".to_string()).llm;
+    config.default_profile = Some("missing".to_string());
+    let error = AppConfig::validate_llm_config(&config).unwrap_err();
+    assert!(
+        error.to_string().contains("未知的模型配置: missing"),
+        "{}",
+        error
+    );
+
+    let mut config = create_test_config("http://localhost".to_string()).llm;
+    config.profiles.as_mut().unwrap().insert(
+        "hot".to_string(),
+        LLMProfile {
+            temperature: Some(5.0),
+            ..Default::default()
+        },
+    );
+    let error = AppConfig::validate_llm_config(&config).unwrap_err();
+    assert!(error.to_string().contains("模型配置 hot 无效"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_request_selects_profile_model() {
+    let mut server = mockito::Server::new_async().await;
+    let fast = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(
+            json!({ "model": "fast-model", "temperature": 0.0 }),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("快速结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+    let base = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({ "model": "base-model" })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("默认结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()));
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service
+        .extract_from_text_with_progress("文本", None, None, None, Some("fast"), None)
+        .await
+        .unwrap();
+    assert_eq!(result.content, "快速结果");
+    assert_eq!(result.model, "fast-model");
+
+    let result = service.extract_from_text("文本", None).await.unwrap();
+    assert_eq!(result, "默认结果");
+
+    fast.assert_async().await;
+    base.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_default_profile_and_unknown_profile() {
+    let mut server = mockito::Server::new_async().await;
+    let accurate = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(
+            json!({ "model": "accurate-model", "max_tokens": 8000 }),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("精确结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(format!("{}/v1/chat/completions", server.url()));
+    config.llm.default_profile = Some("accurate".to_string());
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service.extract_from_text("文本", None).await.unwrap();
+    assert_eq!(result, "精确结果");
+    accurate.assert_async().await;
+
+    let error = service
+        .extract_from_text_with_progress("文本", None, None, None, Some("cheap"), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("可选 accurate、fast"),
+        "{}",
+        error
+    );
+}
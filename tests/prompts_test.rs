@@ -0,0 +1,167 @@
+use mcp_smart_fetch::{AppConfig, McpSmartFetchServer, SmartFetchService};
+use rmcp::model::{GetPromptRequestParam, PromptMessageContent, PromptMessageRole};
+use rmcp::service::RunningService;
+use rmcp::{RoleClient, ServiceExt};
+use serde_json::json;
+use std::path::Path;
+
+const QA_TEMPLATE: &str = r#"---
+description: 根据文档回答问题
+system: 你是严谨的文档问答助手
+variables:
+  question:
+    type: string
+    description: 要回答的问题
+  max_points:
+    type: integer
+    default: 3
+---
+{{#user}}文档：{{{content}}}
+问题：{{variables.question}}
+最多列出 {{variables.max_points}} 点{{#if custom_prompt}}，{{{custom_prompt}}}{{/if}}{{/user}}
+{{#assistant}}好的。{{/assistant}}
+{{#user}}请开始回答。{{/user}}
+"#;
+
+fn create_service(dir: &Path) -> SmartFetchService {
+    let templates_dir = dir.join("templates");
+    std::fs::create_dir_all(&templates_dir).unwrap();
+    std::fs::write(templates_dir.join("default.hbs"), "请提取：{{{content}}}").unwrap();
+    std::fs::write(templates_dir.join("qa.hbs"), QA_TEMPLATE).unwrap();
+
+    let mut config = AppConfig::default();
+    config.llm.api_key = Some("test-api-key".to_string());
+    config.templates_dir = templates_dir;
+    SmartFetchService::new(config).unwrap()
+}
+
+async fn connect(service: SmartFetchService) -> RunningService<RoleClient, ()> {
+    let (server_transport, client_transport) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        let server = McpSmartFetchServer::new(service);
+        let running = server.serve(server_transport).await.unwrap();
+        let _ = running.waiting().await;
+    });
+    ().serve(client_transport).await.unwrap()
+}
+
+fn prompt_request(name: &str, arguments: serde_json::Value) -> GetPromptRequestParam {
+    GetPromptRequestParam {
+        name: name.to_string(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn message_text(content: &PromptMessageContent) -> &str {
+    match content {
+        PromptMessageContent::Text { text } => text,
+        other => panic!("unexpected content: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_templates_are_listed_as_prompts() {
+    let dir = tempfile::tempdir().unwrap();
+    let client = connect(create_service(dir.path())).await;
+
+    let capabilities = client.peer_info().unwrap().capabilities.clone();
+    assert!(capabilities.prompts.is_some());
+
+    let prompts = client.list_all_prompts().await.unwrap();
+    let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["default", "qa"]);
+
+    let qa = &prompts[1];
+    assert_eq!(qa.description.as_deref(), Some("根据文档回答问题"));
+    let arguments: Vec<(&str, Option<bool>)> = qa
+        .arguments
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|argument| (argument.name.as_str(), argument.required))
+        .collect();
+    assert_eq!(
+        arguments,
+        [
+            ("content", Some(true)),
+            ("prompt", Some(false)),
+            ("max_points", Some(false)),
+            ("question", Some(true)),
+        ]
+    );
+    client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_prompt_renders_messages_without_llm() {
+    let dir = tempfile::tempdir().unwrap();
+    // 未配置可用的模型端点，渲染提示词不会调用模型
+    let client = connect(create_service(dir.path())).await;
+
+    let result = client
+        .get_prompt(prompt_request(
+            "qa",
+            json!({
+                "content": "Rust 是一门系统编程语言",
+                "question": "Rust 是什么？",
+                "max_points": "2",
+                "prompt": "用中文回答",
+            }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.description.as_deref(), Some("根据文档回答问题"));
+
+    let messages: Vec<(PromptMessageRole, &str)> = result
+        .messages
+        .iter()
+        .map(|message| (message.role.clone(), message_text(&message.content)))
+        .collect();
+    assert_eq!(messages.len(), 4, "{:?}", messages);
+    // 系统提示词作为第一条用户消息
+    assert_eq!(
+        messages[0],
+        (PromptMessageRole::User, "你是严谨的文档问答助手")
+    );
+    assert_eq!(messages[1].0, PromptMessageRole::User);
+    assert!(messages[1].1.contains("Rust 是一门系统编程语言"));
+    assert!(messages[1].1.contains("问题：Rust 是什么？"));
+    assert!(messages[1].1.contains("最多列出 2 点，用中文回答"));
+    assert_eq!(messages[2], (PromptMessageRole::Assistant, "好的。"));
+    assert_eq!(messages[3], (PromptMessageRole::User, "请开始回答。"));
+
+    let result = client
+        .get_prompt(prompt_request("default", json!({ "content": "文档内容" })))
+        .await
+        .unwrap();
+    assert_eq!(result.messages.len(), 1);
+    assert_eq!(
+        message_text(&result.messages[0].content),
+        "请提取：文档内容"
+    );
+    client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_prompt_rejects_invalid_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+    let client = connect(create_service(dir.path())).await;
+
+    for (name, arguments, expected) in [
+        ("missing", json!({ "content": "文档" }), "模板不存在"),
+        ("qa", json!({ "question": "问题" }), "content"),
+        ("qa", json!({ "content": "文档" }), "question"),
+        (
+            "qa",
+            json!({ "content": "文档", "question": "问题", "max_points": "很多" }),
+            "max_points",
+        ),
+    ] {
+        let error = client
+            .get_prompt(prompt_request(name, arguments))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(expected), "{}", error);
+    }
+    client.cancel().await.unwrap();
+}
@@ -0,0 +1,279 @@
+use mcp_smart_fetch::{
+    create_provider, AzureConfig, ChatMessage, LLMClient, LLMConfig, RetryConfig,
+};
+use mockito::Matcher;
+use serde_json::json;
+use std::sync::Mutex;
+
+fn create_test_client(provider: &str, endpoint: String, max_retries: u32) -> LLMClient {
+    let config = LLMConfig {
+        provider: Some(provider.to_string()),
+        api_endpoint: endpoint,
+        api_key: Some("test-api-key".to_string()),
+        model: "test-model".to_string(),
+        retry: Some(RetryConfig {
+            max_retries: Some(max_retries),
+            initial_backoff_ms: Some(1),
+            max_backoff_ms: Some(5),
+        }),
+        ..Default::default()
+    };
+    LLMClient::new(config).unwrap()
+}
+
+#[tokio::test]
+async fn test_anthropic_messages_request_and_response() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_header("x-api-key", "test-api-key")
+        .match_header("anthropic-version", "2023-06-01")
+        .match_header("authorization", Matcher::Missing)
+        .match_body(Matcher::PartialJson(json!({
+            "model": "test-model",
+            "system": "你是信息提取助手",
+            "messages": [{ "role": "user", "content": "提取" }],
+            "stream": false
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": "msg_test",
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "text", "text": "Anthropic 结果" }],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 12, "output_tokens": 5 }
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client("anthropic", format!("{}/v1/messages", server.url()), 0);
+    let result = client
+        .generate_response_with_context("你是信息提取助手", "提取")
+        .await
+        .unwrap();
+
+    assert_eq!(result, "Anthropic 结果");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_anthropic_stream_events() {
+    let events = [
+        json!({ "type": "message_start", "message": { "content": [], "usage": { "input_tokens": 10, "output_tokens": 1 } } }),
+        json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "流式" } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "结果" } }),
+        json!({ "type": "message_delta", "delta": { "stop_reason": "end_turn" }, "usage": { "output_tokens": 4 } }),
+        json!({ "type": "message_stop" }),
+    ];
+    let body: String = events
+        .iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {}\n\n",
+                event["type"].as_str().unwrap(),
+                event
+            )
+        })
+        .collect();
+
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(Matcher::PartialJson(json!({ "stream": true })))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = create_test_client("anthropic", format!("{}/v1/messages", server.url()), 0);
+    let deltas = Mutex::new(Vec::new());
+    let result = client
+        .generate_response_stream("测试", &|delta: &str| {
+            deltas.lock().unwrap().push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result, "流式结果");
+    assert_eq!(deltas.into_inner().unwrap(), vec!["流式", "结果"]);
+}
+
+#[tokio::test]
+async fn test_ollama_native_chat() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_body(Matcher::PartialJson(json!({
+            "model": "test-model",
+            "stream": false,
+            "options": { "num_predict": 4000 },
+            "format": "json"
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "model": "test-model",
+                "message": { "role": "assistant", "content": "{\"ok\":true}" },
+                "done": true,
+                "prompt_eval_count": 8,
+                "eval_count": 3
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client("ollama", format!("{}/api/chat", server.url()), 0);
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: "输出 JSON".to_string(),
+    }];
+    let result = client
+        .generate_chat(messages, Some(mcp_smart_fetch::ResponseFormat::JsonObject))
+        .await
+        .unwrap();
+
+    assert_eq!(result, "{\"ok\":true}");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_ollama_ndjson_stream() {
+    let lines = [
+        json!({ "message": { "role": "assistant", "content": "本地" }, "done": false }),
+        json!({ "message": { "role": "assistant", "content": "模型" }, "done": false }),
+        json!({ "message": { "role": "assistant", "content": "" }, "done": true, "prompt_eval_count": 5, "eval_count": 2 }),
+    ];
+    let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server
+        .mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/x-ndjson")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = create_test_client("ollama", format!("{}/api/chat", server.url()), 0);
+    let result = client
+        .generate_response_stream("测试", &|_: &str| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result, "本地模型");
+}
+
+#[tokio::test]
+async fn test_azure_deployment_url_and_api_key() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/openai/deployments/my-gpt/chat/completions")
+        .match_query(Matcher::UrlEncoded(
+            "api-version".to_string(),
+            "2024-06-01".to_string(),
+        ))
+        .match_header("api-key", "test-api-key")
+        .match_header("authorization", Matcher::Missing)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "Azure 结果" },
+                    "finish_reason": "stop"
+                }]
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = LLMConfig {
+        provider: Some("azure".to_string()),
+        api_endpoint: format!("{}/", server.url()),
+        api_key: Some("test-api-key".to_string()),
+        stream: Some(false),
+        azure: Some(AzureConfig {
+            deployment: Some("my-gpt".to_string()),
+            api_version: Some("2024-06-01".to_string()),
+        }),
+        retry: Some(RetryConfig {
+            max_retries: Some(0),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = LLMClient::new(config).unwrap();
+    let result = client.generate_response("测试").await.unwrap();
+
+    assert_eq!(result, "Azure 结果");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_providers_share_retry_behavior() {
+    // Anthropic 过载时返回 529，与其他提供商一样按服务端错误重试
+    let mut server = mockito::Server::new_async().await;
+    let overloaded = server
+        .mock("POST", "/v1/messages")
+        .with_status(529)
+        .with_body(r#"{"type":"error","error":{"type":"overloaded_error"}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let success = server
+        .mock("POST", "/v1/messages")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "content": [{ "type": "text", "text": "重试后成功" }],
+                "usage": { "input_tokens": 1, "output_tokens": 1 }
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client("anthropic", format!("{}/v1/messages", server.url()), 2);
+    let result = client.generate_response("测试").await.unwrap();
+
+    assert_eq!(result, "重试后成功");
+    overloaded.assert_async().await;
+    success.assert_async().await;
+}
+
+#[test]
+fn test_unknown_provider_rejected() {
+    let config = LLMConfig {
+        provider: Some("gemini".to_string()),
+        ..Default::default()
+    };
+
+    let error = create_provider(&config).unwrap_err();
+    assert!(
+        error.to_string().contains("不支持的 LLM 提供商"),
+        "{}",
+        error
+    );
+    assert!(mcp_smart_fetch::AppConfig::validate_llm_config(&config).is_err());
+    assert!(LLMClient::new(config).is_err());
+}
@@ -0,0 +1,193 @@
+use mcp_smart_fetch::{AppConfig, HotReloader, LLMProfile, ServiceHandle, SmartFetchService};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 在临时目录中写入配置文件和模板，返回配置文件路径
+fn write_config(dir: &Path, edit: impl FnOnce(&mut AppConfig)) -> PathBuf {
+    let mut config = AppConfig::default();
+    config.llm.api_key = Some("original-key".to_string());
+    config.llm.model = "original-model".to_string();
+    config.templates_dir = dir.join("templates");
+    edit(&mut config);
+
+    let path = dir.join("config.toml");
+    std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    path
+}
+
+fn write_template(dir: &Path, name: &str, content: &str) {
+    let templates_dir = dir.join("templates");
+    std::fs::create_dir_all(&templates_dir).unwrap();
+    std::fs::write(templates_dir.join(format!("{}.hbs", name)), content).unwrap();
+}
+
+fn start(config_path: &Path) -> ServiceHandle {
+    let config = AppConfig::load(&config_path.to_path_buf()).unwrap();
+    ServiceHandle::new(SmartFetchService::new(config).unwrap())
+}
+
+#[test]
+fn test_reload_swaps_templates_and_config() {
+    let dir = tempfile::tempdir().unwrap();
+    write_template(dir.path(), "default", "默认 {{{content}}}");
+    write_template(dir.path(), "removed", "将被删除 {{{content}}}");
+    let config_path = write_config(dir.path(), |_| {});
+    let handle = start(&config_path);
+    let original = handle.current();
+    let reloader = HotReloader::new(&config_path, handle.clone());
+
+    assert!(reloader.reload().unwrap().is_empty());
+
+    write_template(dir.path(), "default", "修改后 {{{content}}}");
+    write_template(dir.path(), "added", "新增 {{{content}}}");
+    std::fs::remove_file(dir.path().join("templates/removed.hbs")).unwrap();
+    write_config(dir.path(), |config| {
+        config.llm.model = "reloaded-model".to_string();
+        config.llm.api_key = Some("changed-key".to_string());
+        config.server.port = 9999;
+    });
+
+    let summary = reloader.reload().unwrap();
+    assert_eq!(summary.templates_added, ["added"]);
+    assert_eq!(summary.templates_removed, ["removed"]);
+    assert_eq!(summary.templates_changed, ["default"]);
+    assert_eq!(summary.config_changed, ["llm"]);
+    assert!(summary.server_changed);
+    assert!(
+        summary.to_string().contains("新增模板: added"),
+        "{}",
+        summary
+    );
+
+    let current = handle.current();
+    assert_eq!(current.config().llm.model, "reloaded-model");
+    // 密钥和服务器设置保持运行中的值
+    assert_eq!(
+        current.config().llm.api_key.as_deref(),
+        Some("original-key")
+    );
+    assert_eq!(current.config().server.port, 8080);
+    assert!(current.template_manager().template_exists("added"));
+    assert!(!current.template_manager().template_exists("removed"));
+
+    // 已取得的旧实例不受影响
+    assert_eq!(original.config().llm.model, "original-model");
+    assert!(original.template_manager().template_exists("removed"));
+}
+
+fn profile_with_key(api_key: &str) -> LLMProfile {
+    LLMProfile {
+        api_key: Some(api_key.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_reload_keeps_keys_of_existing_profiles_only() {
+    let dir = tempfile::tempdir().unwrap();
+    write_template(dir.path(), "default", "默认 {{{content}}}");
+    let config_path = write_config(dir.path(), |config| {
+        config.llm.profiles = Some(BTreeMap::from([(
+            "fast".to_string(),
+            profile_with_key("fast-key"),
+        )]));
+    });
+    let handle = start(&config_path);
+    let reloader = HotReloader::new(&config_path, handle.clone());
+
+    write_config(dir.path(), |config| {
+        config.llm.profiles = Some(BTreeMap::from([
+            ("fast".to_string(), profile_with_key("changed-key")),
+            ("added".to_string(), profile_with_key("added-key")),
+        ]));
+    });
+    reloader.reload().unwrap();
+
+    let current = handle.current();
+    let profiles = current.config().llm.profiles.as_ref().unwrap();
+    // 已有配置保留运行中的密钥，新增配置使用配置文件中的密钥
+    assert_eq!(profiles["fast"].api_key.as_deref(), Some("fast-key"));
+    assert_eq!(profiles["added"].api_key.as_deref(), Some("added-key"));
+}
+
+#[test]
+fn test_invalid_reload_keeps_previous_version() {
+    let dir = tempfile::tempdir().unwrap();
+    write_template(dir.path(), "default", "默认 {{{content}}}");
+    let config_path = write_config(dir.path(), |_| {});
+    let handle = start(&config_path);
+    let reloader = HotReloader::new(&config_path, handle.clone());
+
+    write_template(dir.path(), "broken", "{{#if content}}未闭合");
+    write_template(dir.path(), "default", "修改后 {{{content}}}");
+    assert!(reloader.reload().is_err());
+
+    std::fs::remove_file(dir.path().join("templates/broken.hbs")).unwrap();
+    std::fs::write(&config_path, "[llm\nmodel = ").unwrap();
+    let error = reloader.reload().unwrap_err();
+    assert!(error.to_string().contains("解析配置文件失败"), "{}", error);
+
+    let current = handle.current();
+    assert!(!current.template_manager().template_exists("broken"));
+    assert_eq!(
+        current.template_manager().template_source("default"),
+        Some("默认 {{{content}}}")
+    );
+}
+
+#[test]
+fn test_reload_detects_changed_partials() {
+    let dir = tempfile::tempdir().unwrap();
+    let partials_dir = dir.path().join("templates/partials");
+    std::fs::create_dir_all(&partials_dir).unwrap();
+    std::fs::write(partials_dir.join("footer.hbs"), "旧页脚").unwrap();
+    write_template(dir.path(), "default", "{{{content}}}{{> footer}}");
+    let config_path = write_config(dir.path(), |_| {});
+    let handle = start(&config_path);
+    let reloader = HotReloader::new(&config_path, handle.clone());
+
+    std::fs::write(partials_dir.join("footer.hbs"), "新页脚").unwrap();
+    let summary = reloader.reload().unwrap();
+    assert_eq!(summary.templates_changed, ["partials/footer"]);
+    assert!(summary.templates_added.is_empty());
+
+    let rendered = handle
+        .current()
+        .template_manager()
+        .render_template("default", "正文", None)
+        .unwrap();
+    assert_eq!(rendered, "正文新页脚");
+}
+
+#[tokio::test]
+async fn test_watcher_reloads_changed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    write_template(dir.path(), "default", "默认 {{{content}}}");
+    let config_path = write_config(dir.path(), |_| {});
+    let handle = start(&config_path);
+
+    let watcher = HotReloader::new(&config_path, handle.clone())
+        .with_interval(Duration::from_millis(20))
+        .with_config_override(|config| config.default_template = Some("added".to_string()))
+        .spawn();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    write_template(dir.path(), "added", "新增 {{{content}}}");
+
+    let mut reloaded = false;
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        if handle.current().template_manager().template_exists("added") {
+            reloaded = true;
+            break;
+        }
+    }
+    watcher.abort();
+
+    assert!(reloaded);
+    assert_eq!(
+        handle.current().config().default_template.as_deref(),
+        Some("added")
+    );
+}
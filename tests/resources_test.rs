@@ -0,0 +1,243 @@
+use mcp_smart_fetch::{
+    file_uri, file_uri_path, AppConfig, HotReloader, LlmOutput, McpSmartFetchServer, ResourceRoots,
+    ServiceHandle, SmartFetchService,
+};
+use rmcp::model::{
+    ReadResourceRequestParam, ResourceContents, ResourceUpdatedNotificationParam,
+    SubscribeRequestParam,
+};
+use rmcp::service::{NotificationContext, RunningService};
+use rmcp::{ClientHandler, RoleClient, ServiceExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 记录服务器发来的资源和提示词通知
+#[derive(Clone)]
+struct NotificationRecorder {
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl ClientHandler for NotificationRecorder {
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        let _ = self.sender.send(format!("updated {}", params.uri));
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        let _ = self.sender.send("list_changed".to_string());
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        let _ = self.sender.send("prompt_list_changed".to_string());
+    }
+}
+
+fn write_file(root: &Path, path: &str, content: &str) -> PathBuf {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+/// 在临时目录中写入配置文件、模板和文档目录，返回配置文件路径
+fn write_config(dir: &Path) -> PathBuf {
+    write_file(dir, "templates/default.hbs", "默认 {{{content}}}");
+    write_file(dir, "templates/summary.hbs", "总结 {{{content}}}");
+    write_file(dir, "docs/guide.md", "# 使用指南\n\n第一步");
+    write_file(dir, "docs/nested/notes.txt", "备注内容");
+    write_file(dir, "docs/image.png", "not a document");
+    write_file(dir, "secret.txt", "目录外的文件");
+
+    let mut config = AppConfig::default();
+    config.llm.api_key = Some("test-api-key".to_string());
+    config.templates_dir = dir.join("templates");
+    config.server.resource_roots = Some(vec![dir.join("docs")]);
+    config.processing.supported_formats = vec!["txt".into(), "md".into()];
+
+    let path = dir.join("config.toml");
+    std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    path
+}
+
+async fn connect(
+    handle: ServiceHandle,
+) -> (
+    RunningService<RoleClient, NotificationRecorder>,
+    mpsc::UnboundedReceiver<String>,
+) {
+    let (server_transport, client_transport) = tokio::io::duplex(64 * 1024);
+    let server = McpSmartFetchServer::with_service_handle(handle);
+    tokio::spawn(async move {
+        let running = server.serve(server_transport).await.unwrap();
+        let _ = running.waiting().await;
+    });
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let client = NotificationRecorder { sender }
+        .serve(client_transport)
+        .await
+        .unwrap();
+    (client, receiver)
+}
+
+async fn read_text(client: &RunningService<RoleClient, NotificationRecorder>, uri: &str) -> String {
+    let result = client
+        .read_resource(ReadResourceRequestParam {
+            uri: uri.to_string(),
+        })
+        .await
+        .unwrap();
+    match &result.contents[0] {
+        ResourceContents::TextResourceContents { text, .. } => text.clone(),
+        other => panic!("unexpected contents: {:?}", other),
+    }
+}
+
+#[test]
+fn test_file_uri_round_trip_and_root_checks() {
+    let path = Path::new("/data/文档 1/a#b.md");
+    let uri = file_uri(path);
+    assert_eq!(uri.matches(' ').count(), 0, "{}", uri);
+    assert!(uri.starts_with("file:///data/"), "{}", uri);
+    assert_eq!(file_uri_path(&uri).unwrap(), path);
+    assert!(file_uri_path("template://default").is_none());
+    assert!(file_uri_path("file:///bad%zz").is_none());
+
+    let dir = tempfile::tempdir().unwrap();
+    write_config(dir.path());
+    let formats = vec!["txt".to_string(), "md".to_string()];
+    let roots = ResourceRoots::new(&[dir.path().join("docs"), dir.path().join("missing")]);
+    assert_eq!(roots.roots().len(), 1);
+
+    let docs = dir.path().join("docs").canonicalize().unwrap();
+    assert_eq!(
+        roots.list_files(&formats),
+        [docs.join("guide.md"), docs.join("nested/notes.txt")]
+    );
+    assert!(roots.resolve(&docs.join("guide.md"), &formats).is_ok());
+    // 通过 .. 跳出目录、不支持的格式、不存在的文件都会被拒绝
+    assert!(roots
+        .resolve(&docs.join("nested/../../secret.txt"), &formats)
+        .is_err());
+    assert!(roots.resolve(&docs.join("image.png"), &formats).is_err());
+    assert!(roots.resolve(&docs.join("missing.md"), &formats).is_err());
+    assert!(ResourceRoots::new(&[])
+        .resolve(&docs.join("guide.md"), &formats)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_list_and_read_resources() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = write_config(dir.path());
+    let config = AppConfig::load(&config_path).unwrap();
+    let service = SmartFetchService::new(config).unwrap();
+    let result_id = service.job_queue().record(
+        "文本（4 个字符）".to_string(),
+        LlmOutput {
+            content: "之前的提取结果".to_string(),
+            model: "test-model".to_string(),
+            usage: None,
+            cached: false,
+        },
+    );
+    let (client, _) = connect(ServiceHandle::new(service)).await;
+
+    let resources = client.list_all_resources().await.unwrap();
+    let uris: Vec<&str> = resources.iter().map(|r| r.raw.uri.as_str()).collect();
+    let docs = dir.path().join("docs").canonicalize().unwrap();
+    let guide_uri = file_uri(&docs.join("guide.md"));
+    let result_uri = format!("result://{}", result_id);
+    assert!(uris.contains(&"template://default"), "{:?}", uris);
+    assert!(uris.contains(&"template://summary"), "{:?}", uris);
+    assert!(uris.contains(&guide_uri.as_str()), "{:?}", uris);
+    assert!(uris.contains(&result_uri.as_str()), "{:?}", uris);
+    assert!(!uris.iter().any(|uri| uri.ends_with("image.png")));
+    assert_eq!(uris.len(), 5);
+
+    assert_eq!(
+        read_text(&client, "template://summary").await,
+        "总结 {{{content}}}"
+    );
+    assert!(read_text(&client, &guide_uri).await.contains("使用指南"));
+    assert_eq!(read_text(&client, &result_uri).await, "之前的提取结果");
+
+    for uri in [
+        "template://missing".to_string(),
+        "result://unknown".to_string(),
+        file_uri(&dir.path().join("secret.txt")),
+        "https://example.com".to_string(),
+    ] {
+        let error = client
+            .read_resource(ReadResourceRequestParam { uri: uri.clone() })
+            .await;
+        assert!(error.is_err(), "{}", uri);
+    }
+
+    let templates = client.list_resource_templates(None).await.unwrap();
+    assert_eq!(templates.resource_templates.len(), 3);
+    client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribed_templates_notify_on_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = write_config(dir.path());
+    let config = AppConfig::load(&config_path).unwrap();
+    let handle = ServiceHandle::new(SmartFetchService::new(config).unwrap());
+    let reloader = HotReloader::new(&config_path, handle.clone());
+    let (client, mut notifications) = connect(handle).await;
+
+    client
+        .subscribe(SubscribeRequestParam {
+            uri: "template://summary".to_string(),
+        })
+        .await
+        .unwrap();
+    // 只能订阅存在的模板
+    assert!(client
+        .subscribe(SubscribeRequestParam {
+            uri: "template://missing".to_string(),
+        })
+        .await
+        .is_err());
+
+    // 未订阅的模板变更不发送更新通知
+    write_file(dir.path(), "templates/default.hbs", "修改后 {{{content}}}");
+    reloader.reload().unwrap();
+    write_file(
+        dir.path(),
+        "templates/summary.hbs",
+        "新的总结 {{{content}}}",
+    );
+    write_file(dir.path(), "templates/added.hbs", "新增 {{{content}}}");
+    reloader.reload().unwrap();
+
+    let mut received = Vec::new();
+    while received.len() < 4 {
+        let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+            .await
+            .expect("没有收到资源通知")
+            .unwrap();
+        received.push(notification);
+    }
+    // 每次模板变更都会更新提示词列表
+    assert_eq!(
+        received,
+        [
+            "prompt_list_changed",
+            "list_changed",
+            "prompt_list_changed",
+            "updated template://summary"
+        ]
+    );
+    assert_eq!(
+        read_text(&client, "template://summary").await,
+        "新的总结 {{{content}}}"
+    );
+    client.cancel().await.unwrap();
+}
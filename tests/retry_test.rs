@@ -0,0 +1,210 @@
+mod common;
+
+use common::completion_body;
+use mcp_smart_fetch::{
+    is_retryable_status, parse_retry_after, retry_backoff, retry_delay, LLMClient, LLMConfig,
+    RetryConfig,
+};
+use std::time::{Duration, Instant};
+
+fn create_test_client(endpoint: String, max_retries: u32) -> LLMClient {
+    create_client_with_max_backoff(endpoint, max_retries, 5)
+}
+
+fn create_client_with_max_backoff(
+    endpoint: String,
+    max_retries: u32,
+    max_backoff_ms: u64,
+) -> LLMClient {
+    let config = LLMConfig {
+        api_endpoint: endpoint,
+        api_key: Some("test-api-key".to_string()),
+        retry: Some(RetryConfig {
+            max_retries: Some(max_retries),
+            initial_backoff_ms: Some(1),
+            max_backoff_ms: Some(max_backoff_ms),
+        }),
+        ..Default::default()
+    };
+    LLMClient::new(config).unwrap()
+}
+
+#[tokio::test]
+async fn test_retries_transient_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let unavailable = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(503)
+        .with_body("service unavailable")
+        .expect(2)
+        .create_async()
+        .await;
+    let success = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("重试后成功", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(format!("{}/v1/chat/completions", server.url()), 3);
+    let result = client.generate_response("测试").await.unwrap();
+
+    assert_eq!(result, "重试后成功");
+    unavailable.assert_async().await;
+    success.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_fatal_errors_are_not_retried() {
+    let mut server = mockito::Server::new_async().await;
+    let unauthorized = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(401)
+        .with_body(r#"{"error": "invalid api key"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(format!("{}/v1/chat/completions", server.url()), 3);
+    let error = client.generate_response("测试").await.unwrap_err();
+
+    assert!(error.to_string().contains("401"), "{}", error);
+    unauthorized.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_retries() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(500)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = create_test_client(format!("{}/v1/chat/completions", server.url()), 2);
+    let error = client.generate_response("测试").await.unwrap_err();
+
+    assert!(error.to_string().contains("500"), "{}", error);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_honors_retry_after_header() {
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(429)
+        .with_header("retry-after", "1")
+        .expect(1)
+        .create_async()
+        .await;
+    let success = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("限流解除", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client =
+        create_client_with_max_backoff(format!("{}/v1/chat/completions", server.url()), 1, 2000);
+    let started = Instant::now();
+    let result = client.generate_response("测试").await.unwrap();
+
+    // 退避时间只有几毫秒，等待时间来自 Retry-After
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(result, "限流解除");
+    limited.assert_async().await;
+    success.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_large_retry_after_is_capped() {
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(429)
+        .with_header("retry-after", "3600")
+        .expect(1)
+        .create_async()
+        .await;
+    let success = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("限流解除", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(format!("{}/v1/chat/completions", server.url()), 1);
+    let started = Instant::now();
+    let result = client.generate_response("测试").await.unwrap();
+
+    // Retry-After 超过 max_backoff_ms 时按上限等待
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(result, "限流解除");
+    limited.assert_async().await;
+    success.assert_async().await;
+}
+
+#[test]
+fn test_retry_helpers() {
+    assert_eq!(parse_retry_after("2"), Some(Duration::from_secs(2)));
+    assert_eq!(parse_retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon"), None);
+    assert_eq!(parse_retry_after("-1"), None);
+
+    for status in [408, 429, 500, 502, 503] {
+        assert!(
+            is_retryable_status(status.try_into().unwrap()),
+            "{}",
+            status
+        );
+    }
+    for status in [400, 401, 403, 404, 422] {
+        assert!(
+            !is_retryable_status(status.try_into().unwrap()),
+            "{}",
+            status
+        );
+    }
+
+    let config = RetryConfig {
+        max_retries: Some(5),
+        initial_backoff_ms: Some(100),
+        max_backoff_ms: Some(1000),
+    };
+    assert_eq!(
+        retry_delay(&config, Some(Duration::from_secs(3600)), 1),
+        Duration::from_millis(1000)
+    );
+    assert_eq!(
+        retry_delay(&config, Some(Duration::from_millis(300)), 1),
+        Duration::from_millis(300)
+    );
+    for (attempt, base) in [
+        (1, 100),
+        (2, 200),
+        (3, 400),
+        (4, 800),
+        (5, 1000),
+        (40, 1000),
+    ] {
+        let delay = retry_backoff(&config, attempt).as_millis() as u64;
+        assert!(
+            (base / 2..=base).contains(&delay),
+            "第{}次重试等待 {}ms",
+            attempt,
+            delay
+        );
+    }
+}
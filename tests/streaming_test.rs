@@ -0,0 +1,268 @@
+mod common;
+
+use common::test_config;
+use mcp_smart_fetch::{
+    AppConfig, ChatMessage, ExtractionProgress, ExtractionStage, LLMClient, RetryConfig,
+    SmartFetchError, SmartFetchService,
+};
+use mockito::Matcher;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// 构造 SSE 响应体；`finished` 为 false 时模拟在结束前断开的流
+fn sse_body(parts: &[&str], finished: bool) -> String {
+    let mut body = String::new();
+    for part in parts {
+        let chunk = json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion.chunk",
+            "choices": [{ "index": 0, "delta": { "content": part }, "finish_reason": null }]
+        });
+        body.push_str(&format!("data: {}\n\n", chunk));
+    }
+    if finished {
+        let last = json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion.chunk",
+            "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }]
+        });
+        body.push_str(&format!("data: {}\n\ndata: [DONE]\n\n", last));
+    }
+    body
+}
+
+fn create_test_config(endpoint: String, max_retries: u32) -> AppConfig {
+    let mut config = test_config(endpoint);
+    config.llm.stream = Some(true);
+    config.llm.retry = Some(RetryConfig {
+        max_retries: Some(max_retries),
+        initial_backoff_ms: Some(1),
+        max_backoff_ms: Some(5),
+    });
+    config
+}
+
+#[tokio::test]
+async fn test_stream_delivers_deltas_in_order() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({ "stream": true })))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&["第一段", "，第二段", "。"], true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 0);
+    let client = LLMClient::new(config.llm).unwrap();
+
+    let deltas = Mutex::new(Vec::new());
+    let result = client
+        .generate_response_stream("测试", &|delta: &str| {
+            deltas.lock().unwrap().push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result, "第一段，第二段。");
+    assert_eq!(
+        deltas.into_inner().unwrap(),
+        vec!["第一段", "，第二段", "。"]
+    );
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_stream_returns_usage_from_final_chunk() {
+    // include_usage 开启后，用量在 finish_reason 之后的单独数据块中给出
+    let mut body = sse_body(&["带用量的结果"], true);
+    let usage_chunk = json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion.chunk",
+        "choices": [],
+        "usage": { "prompt_tokens": 12, "completion_tokens": 6, "total_tokens": 18 }
+    });
+    body = body.replace(
+        "data: [DONE]",
+        &format!("data: {}\n\ndata: [DONE]", usage_chunk),
+    );
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "stream": true,
+            "stream_options": { "include_usage": true }
+        })))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 0);
+    let client = LLMClient::new(config.llm).unwrap();
+
+    let output = client
+        .chat_stream(
+            vec![ChatMessage {
+                role: "user".to_string(),
+                content: "测试".to_string(),
+            }],
+            &|_: &str| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(output.content, "带用量的结果");
+    let usage = output.usage.expect("流式响应应返回用量");
+    assert_eq!(usage.prompt_tokens, 12);
+    assert_eq!(usage.completion_tokens, 6);
+    assert_eq!(usage.total_tokens, 18);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_service_reports_generating_progress() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&["提取", "结果"], true))
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 0);
+    let service = SmartFetchService::new(config).unwrap();
+
+    let events: Arc<Mutex<Vec<ExtractionProgress>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorder = events.clone();
+    let result = service
+        .extract_from_text_with_progress(
+            "一段需要提取的文本。",
+            None,
+            None,
+            None,
+            None,
+            Some(Arc::new(move |event: &ExtractionProgress| {
+                recorder.lock().unwrap().push(event.clone());
+            })),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.content, "提取结果");
+    let events = events.lock().unwrap();
+    let generating: Vec<_> = events
+        .iter()
+        .filter(|event| event.stage == ExtractionStage::Generating)
+        .collect();
+    assert_eq!(generating.len(), 2);
+    assert_eq!(generating[0].delta.as_deref(), Some("提取"));
+    assert_eq!(generating[1].generated_chars, 4);
+    assert!(generating[0].progress_value() < generating[1].progress_value());
+    assert!(generating[1].progress_value() < 1.0);
+    assert_eq!(events.last().unwrap().stage, ExtractionStage::Completed);
+}
+
+#[tokio::test]
+async fn test_interrupted_stream_is_resumed() {
+    let mut server = mockito::Server::new_async().await;
+    let cut = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&["前半部分"], false))
+        .expect(1)
+        .create_async()
+        .await;
+    // 续传请求应携带已接收的内容并要求从断点继续
+    let resumed = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex("截断".to_string()),
+            Matcher::Regex(r#""role":"assistant","content":"前半部分""#.to_string()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&["，后半部分"], true))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 2);
+    let client = LLMClient::new(config.llm).unwrap();
+
+    let result = client
+        .generate_response_stream("测试", &|_: &str| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result, "前半部分，后半部分");
+    cut.assert_async().await;
+    resumed.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_partial_output_recoverable_after_retries() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&["已生成的内容"], false))
+        .create_async()
+        .await;
+
+    let config = create_test_config(format!("{}/v1/chat/completions", server.url()), 0);
+    let service = SmartFetchService::new(config).unwrap();
+
+    let error = service
+        .extract_from_text("一段需要提取的文本。", None)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, SmartFetchError::StreamInterrupted { .. }));
+    assert_eq!(error.partial_output(), Some("已生成的内容"));
+    assert!(error.to_string().contains("已接收6字符"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_stream_disabled_uses_single_response() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({ "stream": false })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "完整结果" },
+                    "finish_reason": "stop"
+                }]
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = create_test_config(format!("{}/v1/chat/completions", server.url()), 0);
+    config.llm.stream = Some(false);
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service.extract_from_text("文本", None).await.unwrap();
+
+    assert_eq!(result, "完整结果");
+    mock.assert_async().await;
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::structured::parse_json_output;
+use mcp_smart_fetch::{ExtractionSource, SmartFetchService, StructuredConfig};
+use mockito::Matcher;
+use serde_json::{json, Value};
+
+fn person_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "age": { "type": "integer" }
+        },
+        "required": ["name", "age"]
+    })
+}
+
+fn text_source() -> ExtractionSource {
+    ExtractionSource::Text("张三今年 30 岁，是一名工程师。".to_string())
+}
+
+#[test]
+fn test_parse_json_output_accepts_code_fence() {
+    let fenced = "```json\n{\"name\": \"张三\"}\n```";
+    assert_eq!(parse_json_output(fenced).unwrap(), json!({"name": "张三"}));
+    assert_eq!(
+        parse_json_output("  {\"age\": 30}  ").unwrap(),
+        json!({"age": 30})
+    );
+    assert!(parse_json_output("姓名：张三").is_err());
+}
+
+#[tokio::test]
+async fn test_structured_request_uses_json_schema_mode() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "schema": person_schema() }
+            }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body(r#"{"name": "张三", "age": 30}"#, false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = test_config(format!("{}/v1/chat/completions", server.url()));
+    let service = SmartFetchService::new(config).unwrap();
+
+    let value = service
+        .extract_structured(&text_source(), &person_schema(), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(value, json!({"name": "张三", "age": 30}));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_structured_retries_with_validation_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let invalid = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body(r#"{"name": "张三", "age": "三十"}"#, false))
+        .expect(1)
+        .create_async()
+        .await;
+    // 第二次请求应携带上一次的输出和校验错误
+    let retry = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex("未通过 JSON Schema 校验".to_string()),
+            Matcher::Regex("/age".to_string()),
+            Matcher::Regex(r#""role":"assistant""#.to_string()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body(r#"{"name": "张三", "age": 30}"#, false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = test_config(format!("{}/v1/chat/completions", server.url()));
+    config.llm.response_format = Some("json_object".to_string());
+    let service = SmartFetchService::new(config).unwrap();
+
+    let value = service
+        .extract_structured(&text_source(), &person_schema(), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(value["age"], 30);
+    invalid.assert_async().await;
+    retry.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_structured_fails_after_max_retries() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("抱歉，我无法提取这些信息。", false))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let mut config = test_config(format!("{}/v1/chat/completions", server.url()));
+    config.processing.structured = Some(StructuredConfig {
+        max_retries: Some(1),
+        ..Default::default()
+    });
+    let service = SmartFetchService::new(config).unwrap();
+
+    let error = service
+        .extract_structured(&text_source(), &person_schema(), None, None)
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("2 次尝试"), "{}", error);
+    assert!(error.contains("不是有效的 JSON"), "{}", error);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_invalid_schema_is_rejected_before_request() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let config = test_config(format!("{}/v1/chat/completions", server.url()));
+    let service = SmartFetchService::new(config).unwrap();
+
+    let result = service
+        .extract_structured(&text_source(), &json!({"type": "not-a-type"}), None, None)
+        .await;
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("无效的 JSON Schema"));
+    mock.assert_async().await;
+}
@@ -0,0 +1,175 @@
+use mcp_smart_fetch::TemplateManager;
+use serde_json::json;
+use std::path::Path;
+
+fn manager_with(templates: &[(&str, &str)]) -> (tempfile::TempDir, TemplateManager) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut manager = TemplateManager::new(dir.path()).unwrap();
+    for (name, template) in templates {
+        manager.register_template_string(name, template).unwrap();
+    }
+    (dir, manager)
+}
+
+fn render(manager: &TemplateManager, name: &str, content: &str) -> String {
+    let data = TemplateManager::build_template_data(content, None);
+    manager.render_template_with_data(name, &data).unwrap()
+}
+
+#[test]
+fn test_text_helpers_are_utf8_safe() {
+    let (_dir, manager) = manager_with(&[
+        ("truncate", "{{truncate content 3}}"),
+        ("head", "{{head content 2}}"),
+        ("tail", "{{tail content 2}}"),
+        ("tokens", "{{token_count content}}"),
+        (
+            "longer",
+            "{{#if_longer_than content 4}}长{{else}}短{{/if_longer_than}}",
+        ),
+    ]);
+
+    assert_eq!(render(&manager, "truncate", "你好世界啊"), "你好世...");
+    assert_eq!(render(&manager, "truncate", "你好"), "你好");
+    assert_eq!(
+        render(&manager, "head", "第一行\n第二行\n第三行"),
+        "第一行\n第二行"
+    );
+    assert_eq!(
+        render(&manager, "tail", "第一行\n第二行\n第三行"),
+        "第二行\n第三行"
+    );
+    assert_eq!(render(&manager, "tail", "唯一一行"), "唯一一行");
+    assert_eq!(render(&manager, "tokens", &"a".repeat(40)), "10");
+    // 按字符而非字节计算长度
+    assert_eq!(render(&manager, "longer", "一二三四"), "短");
+    assert_eq!(render(&manager, "longer", "一二三四五"), "长");
+}
+
+#[test]
+fn test_helpers_error_instead_of_panicking() {
+    let (_dir, manager) = manager_with(&[
+        ("missing", "{{truncate content}}"),
+        ("wrong_type", "{{head content \"两行\"}}"),
+        ("negative", "{{tail content -1}}"),
+        ("bad_format", "{{date format=\"%Q\"}}"),
+        ("bad_date", "{{date \"下周一\"}}"),
+        ("no_param", "{{json}}"),
+    ]);
+    let data = TemplateManager::build_template_data("测试内容", None);
+
+    for name in [
+        "missing",
+        "wrong_type",
+        "negative",
+        "bad_format",
+        "bad_date",
+        "no_param",
+    ] {
+        let error = manager.render_template_with_data(name, &data).unwrap_err();
+        assert!(
+            error.to_string().contains("渲染模板失败"),
+            "{}: {}",
+            name,
+            error
+        );
+    }
+}
+
+#[test]
+fn test_sections_json_and_date_helpers() {
+    let (_dir, manager) = manager_with(&[
+        (
+            "sections",
+            "{{#each (sections content level=2)}}[{{this.level}}|{{this.title}}|{{{this.content}}}]{{/each}}",
+        ),
+        ("json", "{{{json variables.meta}}}"),
+        ("json_string", "{{{json content}}}"),
+        ("date", "{{date \"2024-03-05T08:30:00+00:00\" format=\"%Y/%m/%d\"}}"),
+        ("today", "{{date}}"),
+    ]);
+
+    let markdown = "前言\n# 第一章\n内容一\n### 小节\n```\n# 代码中的注释\n```\n## 第二章\n内容二";
+    assert_eq!(
+        render(&manager, "sections", markdown),
+        "[0||前言][1|第一章|内容一\n### 小节\n```\n# 代码中的注释\n```][2|第二章|内容二]"
+    );
+
+    let mut data = TemplateManager::build_template_data("", None);
+    data.variables
+        .insert("meta".to_string(), json!({ "标题": "周报", "页数": 3 }));
+    assert_eq!(
+        manager.render_template_with_data("json", &data).unwrap(),
+        "{\n  \"标题\": \"周报\",\n  \"页数\": 3\n}"
+    );
+    assert_eq!(
+        render(&manager, "json_string", "{\"a\":[1,2]}"),
+        "{\n  \"a\": [\n    1,\n    2\n  ]\n}"
+    );
+
+    assert_eq!(render(&manager, "date", ""), "2024/03/05");
+    let today = render(&manager, "today", "");
+    assert_eq!(today.len(), 10);
+    assert_eq!(today.matches('-').count(), 2);
+}
+
+#[test]
+fn test_partials_are_loaded_from_partials_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let partials_dir = dir.path().join("partials/common");
+    std::fs::create_dir_all(&partials_dir).unwrap();
+    std::fs::write(
+        dir.path().join("partials/header.hbs"),
+        "---\ndescription: 头部\n---\n标题：{{metadata.title}}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        partials_dir.join("footer.hbs"),
+        "（共 {{line_count content}} 行）",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("report.hbs"),
+        "{{> header}}\n{{{content}}}\n{{> common/footer}}",
+    )
+    .unwrap();
+
+    let mut manager = TemplateManager::new(dir.path()).unwrap();
+    assert_eq!(manager.get_available_templates(), ["report"]);
+    assert_eq!(
+        manager.get_available_partials(),
+        ["common/footer", "header"]
+    );
+    assert!(!manager.template_exists("header"));
+
+    let mut data = TemplateManager::build_template_data("第一行\n第二行", None);
+    data.metadata
+        .insert("title".to_string(), "周报".to_string());
+    assert_eq!(
+        manager.render_template_with_data("report", &data).unwrap(),
+        "标题：周报\n第一行\n第二行\n（共 2 行）"
+    );
+    assert!(manager
+        .validate_template("{{> header}}{{{content}}}")
+        .is_ok());
+
+    let error = manager
+        .register_template_string("header", "{{{content}}}")
+        .unwrap_err();
+    assert!(error.to_string().contains("重名"), "{}", error);
+}
+
+#[test]
+fn test_bundled_templates_use_shared_partials() {
+    let manager = TemplateManager::new(Path::new("templates")).unwrap();
+    assert!(manager
+        .get_available_partials()
+        .contains(&"document_stats".to_string()));
+    assert!(!manager
+        .get_available_templates()
+        .contains(&"document_stats".to_string()));
+
+    let data = TemplateManager::build_template_data("第一行\n第二行", None);
+    let rendered = manager.render_template_with_data("qa", &data).unwrap();
+    assert!(rendered.contains("- 行数：2 行"), "{}", rendered);
+}
@@ -0,0 +1,370 @@
+mod common;
+
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{LLMProfile, SmartFetchService, TemplateManager, VariableType};
+use mockito::Matcher;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[tokio::test]
+async fn test_request_selects_template() {
+    let mut server = mockito::Server::new_async().await;
+    let qa = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex("专业的问答助手".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("问答结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+    let default = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex("专业的文档内容提取助手".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("默认结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let service =
+        SmartFetchService::new(test_config(format!("{}/v1/chat/completions", server.url())))
+            .unwrap();
+
+    let result = service
+        .extract_from_text_with_progress(
+            "文档内容",
+            Some("文档讲了什么？".to_string()),
+            Some("qa"),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.content, "问答结果");
+
+    let result = service.extract_from_text("文档内容", None).await.unwrap();
+    assert_eq!(result, "默认结果");
+
+    qa.assert_async().await;
+    default.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_unknown_template_rejected_before_llm_call() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let service =
+        SmartFetchService::new(test_config(format!("{}/v1/chat/completions", server.url())))
+            .unwrap();
+    let error = service
+        .extract_from_text_with_progress("文档内容", None, Some("missing"), None, None, None)
+        .await
+        .unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.contains("模板不存在: missing"), "{}", message);
+    assert!(message.contains("qa"), "{}", message);
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_list_templates_with_descriptions() {
+    let manager = TemplateManager::new(Path::new("templates")).unwrap();
+
+    let templates = manager.list_templates();
+    let names: Vec<&str> = templates.iter().map(|info| info.name.as_str()).collect();
+    assert_eq!(names, manager.get_available_templates());
+    for name in ["default", "qa", "summary"] {
+        assert!(names.contains(&name), "{:?}", names);
+    }
+
+    let summary = manager.template_info("summary").unwrap();
+    assert!(
+        summary.description.contains("总结"),
+        "{}",
+        summary.description
+    );
+    assert!(manager
+        .template_source("summary")
+        .unwrap()
+        .contains("{{{truncate content 2000}}}"));
+    assert!(manager.template_info("missing").is_none());
+}
+
+const BRIEFING_TEMPLATE: &str = r#"---
+description: 面向指定读者的简报
+system: 你是{{variables.audience}}的简报助手
+model: template-model
+temperature: 0.1
+variables:
+  audience:
+    type: string
+    description: 简报的读者
+  points:
+    type: integer
+    default: 3
+---
+为{{variables.audience}}列出{{variables.points}}条要点：
+{{{content}}}
+"#;
+
+fn create_template_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("briefing.hbs"), BRIEFING_TEMPLATE).unwrap();
+    std::fs::write(
+        dir.path().join("toml.hbs"),
+        "+++\ndescription = \"TOML 头部\"\nmax_tokens = 256\n\n[variables.strict]\ntype = \"boolean\"\nrequired = false\n+++\n{{#if variables.strict}}严格模式{{/if}}{{{content}}}",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_front_matter_parsing() {
+    let dir = create_template_dir();
+    let manager = TemplateManager::new(dir.path()).unwrap();
+
+    let briefing = manager.template_info("briefing").unwrap();
+    assert_eq!(briefing.description, "面向指定读者的简报");
+    assert!(briefing.variables["audience"].is_required());
+    assert!(!briefing.variables["points"].is_required());
+    let front_matter = manager.front_matter("briefing").unwrap();
+    assert_eq!(front_matter.model.as_deref(), Some("template-model"));
+    assert_eq!(front_matter.temperature, Some(0.1));
+
+    let toml = manager.front_matter("toml").unwrap();
+    assert_eq!(toml.description.as_deref(), Some("TOML 头部"));
+    assert_eq!(toml.max_tokens, Some(256));
+    assert_eq!(toml.variables["strict"].kind, VariableType::Boolean);
+
+    // 命令行传入的字符串按声明的类型转换，未声明的可选变量以 null 填充
+    let variables = manager
+        .resolve_variables(
+            "briefing",
+            HashMap::from([
+                ("audience".to_string(), json!("管理层")),
+                ("points".to_string(), json!("5")),
+            ]),
+        )
+        .unwrap();
+    assert_eq!(variables["points"], json!(5));
+    let variables = manager.resolve_variables("toml", HashMap::new()).unwrap();
+    assert_eq!(variables["strict"], serde_json::Value::Null);
+
+    let error = manager
+        .resolve_variables(
+            "briefing",
+            HashMap::from([
+                ("audience".to_string(), json!("管理层")),
+                ("points".to_string(), json!("很多")),
+            ]),
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("integer"), "{}", error);
+
+    // 内置模板的头部
+    let manager = TemplateManager::new(Path::new("templates")).unwrap();
+    let summary = manager.template_info("summary").unwrap();
+    assert_eq!(summary.description, "为文档生成简洁的总结");
+    assert!(summary.variables.contains_key("length"));
+    assert!(manager.validate_template(BRIEFING_TEMPLATE).is_ok());
+}
+
+#[tokio::test]
+async fn test_missing_required_variable_rejected_before_llm_call() {
+    let dir = create_template_dir();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut config = test_config(format!("{}/v1/chat/completions", server.url()));
+    config.templates_dir = dir.path().to_path_buf();
+    let service = SmartFetchService::new(config).unwrap();
+    let error = service
+        .extract_from_text_with_progress("文档内容", None, Some("briefing"), None, None, None)
+        .await
+        .unwrap_err();
+
+    let message = error.to_string();
+    assert!(
+        message.contains("缺少必填变量: audience（简报的读者）"),
+        "{}",
+        message
+    );
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_front_matter_system_prompt_and_model_override() {
+    let dir = create_template_dir();
+    let mut server = mockito::Server::new_async().await;
+    let templated = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""model":"template-model""#.to_string()),
+            Matcher::Regex(r#""role":"system","content":"你是管理层的简报助手""#.to_string()),
+            Matcher::Regex("为管理层列出3条要点".to_string()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("模板模型结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+    let profiled = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex(r#""model":"profile-model""#.to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("模型配置结果", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = test_config(format!("{}/v1/chat/completions", server.url()));
+    config.templates_dir = dir.path().to_path_buf();
+    config.llm.profiles = Some(BTreeMap::from([(
+        "fast".to_string(),
+        LLMProfile {
+            model: Some("profile-model".to_string()),
+            ..Default::default()
+        },
+    )]));
+    let service = SmartFetchService::new(config).unwrap();
+    let variables = HashMap::from([("audience".to_string(), json!("管理层"))]);
+
+    let result = service
+        .extract_from_text_with_progress(
+            "文档内容",
+            None,
+            Some("briefing"),
+            Some(variables.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.content, "模板模型结果");
+
+    // 显式指定的模型配置优先于模板头部
+    let result = service
+        .extract_from_text_with_progress(
+            "文档内容",
+            None,
+            Some("briefing"),
+            Some(variables),
+            Some("fast"),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.content, "模型配置结果");
+
+    templated.assert_async().await;
+    profiled.assert_async().await;
+}
+
+const FEW_SHOT_TEMPLATE: &str = r#"---
+system: 只输出关键词
+---
+{{#system}}关键词之间用逗号分隔{{/system}}
+{{#user}}Rust 是一门系统编程语言{{/user}}
+{{#assistant}}Rust,系统编程{{/assistant}}
+{{#user}}{{{content}}}{{/user}}
+"#;
+
+#[test]
+fn test_role_blocks_render_as_messages() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("few_shot.hbs"), FEW_SHOT_TEMPLATE).unwrap();
+    std::fs::write(
+        dir.path().join("nested.hbs"),
+        "{{#user}}外层{{#assistant}}内层{{/assistant}}{{/user}}",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("system_only.hbs"),
+        "{{#system}}系统{{/system}}",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("plain.hbs"), "  纯文本 {{{content}}}\n").unwrap();
+    let manager = TemplateManager::new(dir.path()).unwrap();
+    let data = TemplateManager::build_template_data("Go 适合编写网络服务", None);
+
+    let messages = manager.render_messages("few_shot", &data).unwrap();
+    let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+    // 模板头部和正文中的系统提示词合并为一条
+    assert_eq!(messages[0].content, "只输出关键词\n\n关键词之间用逗号分隔");
+    assert_eq!(messages[2].content, "Rust,系统编程");
+    assert_eq!(messages[3].content, "Go 适合编写网络服务");
+
+    let text = manager
+        .render_template_with_data("few_shot", &data)
+        .unwrap();
+    assert!(
+        text.contains("关键词之间用逗号分隔\nRust 是一门系统编程语言"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("assistant"), "{}", text);
+
+    // 没有角色块的模板原样作为一条用户消息
+    let messages = manager.render_messages("plain", &data).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].content, "  纯文本 Go 适合编写网络服务\n");
+
+    let error = manager.render_messages("nested", &data).unwrap_err();
+    assert!(error.to_string().contains("角色块不能嵌套"), "{}", error);
+    let error = manager.render_messages("system_only", &data).unwrap_err();
+    assert!(error.to_string().contains("没有生成用户消息"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_few_shot_template_sends_conversation() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("few_shot.hbs"), FEW_SHOT_TEMPLATE).unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::Regex(
+            r#""messages":\[\{"role":"system",[^}]*\},\{"role":"user","content":"Rust 是一门系统编程语言"\},\{"role":"assistant","content":"Rust,系统编程"\},\{"role":"user","content":"Go 适合编写网络服务"\}\]"#
+                .to_string(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("Go,网络服务", false))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut config = test_config(format!("{}/v1/chat/completions", server.url()));
+    config.templates_dir = dir.path().to_path_buf();
+    let service = SmartFetchService::new(config).unwrap();
+    let result = service
+        .extract_from_text_with_progress(
+            "Go 适合编写网络服务",
+            None,
+            Some("few_shot"),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.content, "Go,网络服务");
+    mock.assert_async().await;
+}
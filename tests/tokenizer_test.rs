@@ -0,0 +1,262 @@
+mod common;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{completion_body, test_config};
+use mcp_smart_fetch::{
+    default_context_window, AppConfig, BpeTokenizer, ChatMessage, Encoding, EstimateTokenCounter,
+    SmartFetchError, SmartFetchService, TokenCounter, TokenizerConfig, TokenizerRegistry,
+};
+use std::path::Path;
+
+/// 包含全部单字节和少量合并规则的测试词表
+fn test_vocab() -> String {
+    let mut tokens: Vec<Vec<u8>> = (0..=255u8).map(|byte| vec![byte]).collect();
+    tokens.extend([b"ab".to_vec(), b"cd".to_vec(), b"abcd".to_vec()]);
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(rank, token)| format!("{} {}", STANDARD.encode(token), rank))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 上下文窗口很小的模型，模板只包含内容本身
+fn create_test_config(endpoint: String, templates_dir: &Path, on_overflow: &str) -> AppConfig {
+    std::fs::write(templates_dir.join("default.hbs"), "{{{content}}}").unwrap();
+    std::fs::write(templates_dir.join("reduce.hbs"), "合并：{{{content}}}").unwrap();
+
+    let mut config = test_config(endpoint);
+    config.templates_dir = templates_dir.to_path_buf();
+    config.tokenizer = Some(TokenizerConfig {
+        encoding: Some("estimate".to_string()),
+        vocab_dir: None,
+        on_overflow: Some(on_overflow.to_string()),
+    });
+    config.llm.model = "small-model".to_string();
+    config.llm.max_tokens = Some(100);
+    config.llm.context_window = Some(300);
+    config.processing.chunk_size = Some(100_000);
+    config
+}
+
+#[test]
+fn test_bpe_tokenizer_merges_by_rank() {
+    let tokenizer = BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, &test_vocab()).unwrap();
+
+    assert_eq!(tokenizer.encode("abcd"), [258]);
+    assert_eq!(tokenizer.encode("abcde"), [258, u32::from(b'e')]);
+    assert_eq!(tokenizer.encode("ab ab"), [256, u32::from(b' '), 256]);
+    // 没有合并规则的中文按 UTF-8 字节计数
+    assert_eq!(tokenizer.count_tokens("中文"), 6);
+    assert_eq!(tokenizer.count_tokens(""), 0);
+
+    let error =
+        BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, "YWI=\nnot-a-line 1").unwrap_err();
+    assert!(error.to_string().contains("第 1 行格式无效"), "{}", error);
+}
+
+#[test]
+fn test_estimate_counts_chinese_conservatively() {
+    let counter = EstimateTokenCounter;
+    assert_eq!(counter.count_tokens("abcd efgh"), 3);
+    assert_eq!(counter.count_tokens("中文测试"), 6);
+
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: "abcd".to_string(),
+    }];
+    // 每条消息 4 个格式 token，回复开头 3 个
+    assert_eq!(counter.count_messages(&messages), 4 + 1 + 1 + 3);
+}
+
+#[test]
+fn test_registry_selects_encoding_and_falls_back_to_estimate() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("cl100k_base.tiktoken"), test_vocab()).unwrap();
+
+    let registry = TokenizerRegistry::new(TokenizerConfig {
+        vocab_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    });
+    assert_eq!(registry.counter_for("gpt-4").name(), "cl100k_base");
+    // 目录中没有 o200k_base 词表时使用内置词表，构建时未嵌入则退回估算
+    let bundled_or_estimate = |encoding: Encoding| match encoding.bundled_vocab() {
+        Some(_) => encoding.name(),
+        None => "estimate",
+    };
+    assert_eq!(
+        registry.counter_for("gpt-4o-mini").name(),
+        bundled_or_estimate(Encoding::O200kBase)
+    );
+
+    // 未配置 vocab_dir 时直接使用内置词表
+    let registry = TokenizerRegistry::new(TokenizerConfig::default());
+    assert_eq!(
+        registry.counter_for("gpt-4").name(),
+        bundled_or_estimate(Encoding::Cl100kBase)
+    );
+
+    let registry = TokenizerRegistry::new(TokenizerConfig {
+        encoding: Some("estimate".to_string()),
+        vocab_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    });
+    assert_eq!(registry.counter_for("gpt-4").name(), "estimate");
+
+    assert_eq!(Encoding::for_model("gpt-4o-mini"), Encoding::O200kBase);
+    assert_eq!(default_context_window("gpt-4o-mini"), Some(128_000));
+    assert_eq!(default_context_window("gpt-4"), Some(8_192));
+    assert_eq!(default_context_window("local-model"), None);
+}
+
+#[test]
+fn test_token_count_helper_uses_model_tokenizer() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates_dir = dir.path().join("templates");
+    std::fs::create_dir_all(&templates_dir).unwrap();
+    std::fs::write(templates_dir.join("count.hbs"), "{{token_count content}}").unwrap();
+    std::fs::write(dir.path().join("cl100k_base.tiktoken"), test_vocab()).unwrap();
+
+    let mut config = test_config("http://localhost/v1/chat/completions".to_string());
+    config.templates_dir = templates_dir;
+    config.llm.model = "gpt-4".to_string();
+    config.tokenizer = Some(TokenizerConfig {
+        vocab_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    });
+    let service = SmartFetchService::new(config).unwrap();
+
+    // 测试词表中的中文按 UTF-8 字节计数，估算值为 3
+    let messages = service
+        .render_prompt("count", "中文", None, Default::default())
+        .unwrap();
+    assert_eq!(messages[0].content, "6");
+}
+
+#[test]
+fn test_tokenizer_config_validation() {
+    let mut config = AppConfig::default();
+    assert!(config.validate().is_ok());
+
+    config.tokenizer = Some(TokenizerConfig {
+        encoding: Some("p50k_base".to_string()),
+        ..Default::default()
+    });
+    assert!(config.validate().is_err());
+
+    config.tokenizer = Some(TokenizerConfig::default());
+    config.llm.context_window = Some(1000);
+    config.llm.max_tokens = Some(1000);
+    assert!(config.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_prompt_exceeding_context_window_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_body(completion_body("不应调用", false))
+        .expect(0)
+        .create_async()
+        .await;
+
+    let config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        dir.path(),
+        "error",
+    );
+    let service = SmartFetchService::new(config).unwrap();
+    let error = service
+        .extract_from_text(&"长文本内容。".repeat(40), None)
+        .await
+        .unwrap_err();
+
+    match &error {
+        SmartFetchError::ContextWindowExceeded {
+            model,
+            max_tokens,
+            context_window,
+            prompt_tokens,
+        } => {
+            assert_eq!(model, "small-model");
+            assert_eq!(*max_tokens, 100);
+            assert_eq!(*context_window, 300);
+            assert!(*prompt_tokens > 200);
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+    assert!(error.to_string().contains("上下文窗口"), "{}", error);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_prompt_exceeding_context_window_is_chunked() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("要点", false))
+        .expect_at_least(3)
+        .create_async()
+        .await;
+
+    let config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        dir.path(),
+        "chunk",
+    );
+    let service = SmartFetchService::new(config).unwrap();
+    // 约 360 个 token，超出 300 - 100 的预算
+    let result = service
+        .extract_from_text(&"长文本内容。\n".repeat(40), None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "要点");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_merged_partials_exceeding_context_window_are_reduced_in_batches() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut server = mockito::Server::new_async().await;
+    // 每个分块结果约 75 个 token，全部合并超出预算，两两合并放得下
+    let map = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("长文本内容".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body(&"要点".repeat(25), false))
+        .expect_at_least(3)
+        .create_async()
+        .await;
+    let reduce = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("合并：## 分块".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(completion_body("合并要点", false))
+        .expect_at_least(2)
+        .create_async()
+        .await;
+
+    let config = create_test_config(
+        format!("{}/v1/chat/completions", server.url()),
+        dir.path(),
+        "chunk",
+    );
+    let service = SmartFetchService::new(config).unwrap();
+    let result = service
+        .extract_from_text(&"长文本内容。\n".repeat(120), None)
+        .await
+        .unwrap();
+
+    assert_eq!(result, "合并要点");
+    map.assert_async().await;
+    reduce.assert_async().await;
+}
@@ -27,9 +27,13 @@ fn test_llm_config_validation() {
 
 #[test]
 fn test_document_processor_creation() {
+    // 未配置时接受加载器注册表支持的所有格式
     let processing_config = AppConfig::default().processing;
-    assert!(processing_config
-        .supported_formats
+    assert!(processing_config.supported_formats.is_empty());
+    let processor = DocumentProcessor::new(processing_config).unwrap();
+    assert!(processor
+        .loader_registry()
+        .supported_formats()
         .contains(&"txt".to_string()));
 }
 
@@ -69,6 +73,7 @@ fn test_template_data_creation() {
         content: "测试内容".to_string(),
         custom_prompt: Some("自定义提示词".to_string()),
         metadata,
+        variables: HashMap::new(),
     };
 
     assert_eq!(template_data.content, "测试内容");
@@ -174,3 +179,17 @@ fn test_token_estimation() {
     assert!(english_tokens > 0);
     assert!(chinese_tokens > 0);
 }
+
+#[test]
+fn test_content_chunking_multibyte() {
+    let mut processing_config = AppConfig::default().processing;
+    processing_config.chunk_size = Some(100);
+    let processor = DocumentProcessor::new(processing_config).unwrap();
+
+    // 分块边界落在多字节字符中间时不应 panic
+    let chinese_text = "中文内容没有任何句子边界".repeat(50);
+    let chunks = processor.chunk_content(&chinese_text).unwrap();
+
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.concat(), chinese_text);
+}